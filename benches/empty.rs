@@ -0,0 +1,106 @@
+//! Empty response benchmarks, run with `cargo bench --bench empty`.
+//!
+//! `bench_write_*` write a response into memory, `bench_round_trip_*` send a request and read
+//! its response on one kept-alive connection, as a load balancer's health checks do:
+//!
+//! | bench                       | ns/iter (before) | ns/iter (after) |
+//! |-----------------------------|------------------|-----------------|
+//! | bench_write_no_content      | 192              | 192             |
+//! | bench_write_redirect        | 1,399            | 679             |
+//! | bench_round_trip_no_content | 43,999,899       | 11,203          |
+//!
+//! Before, the status line and every header were written to the socket as they were formatted,
+//! so with Nagle's algorithm on, the response waited out the client's delayed ACK.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+};
+
+use {
+    enrgy::{
+        http::{self, headers, HttpResponse, StatusCode},
+        web, App, HttpServer,
+    },
+    test::Bencher,
+};
+
+fn serve() -> SocketAddr {
+    let server = HttpServer::new(
+        App::new().service(web::get("/health").to(|| HttpResponse::new(StatusCode::NO_CONTENT))),
+    )
+    .disable_signals()
+    .bind(([127, 0, 0, 1], 0))
+    .listen()
+    .unwrap();
+
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    addr
+}
+
+#[bench]
+fn bench_write_no_content(b: &mut Bencher) {
+    let mut buffer = Vec::with_capacity(1024);
+
+    b.iter(|| {
+        buffer.clear();
+
+        http::write_response(
+            HttpResponse::new(StatusCode::NO_CONTENT),
+            false,
+            &mut buffer,
+        )
+        .unwrap();
+    });
+}
+
+#[bench]
+fn bench_write_redirect(b: &mut Bencher) {
+    let mut buffer = Vec::with_capacity(1024);
+
+    b.iter(|| {
+        buffer.clear();
+
+        http::write_response(
+            HttpResponse::new(StatusCode::FOUND).header(headers::LOCATION, "/login"),
+            false,
+            &mut buffer,
+        )
+        .unwrap();
+    });
+}
+
+#[bench]
+fn bench_round_trip_no_content(b: &mut Bencher) {
+    let mut stream = TcpStream::connect(serve()).unwrap();
+
+    // Only the server's side is measured.
+    stream.set_nodelay(true).unwrap();
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+
+    b.iter(|| {
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+
+        buffer.clear();
+
+        // `204`s end with their head.
+        while !buffer.ends_with(b"\r\n\r\n") {
+            let read = stream.read(&mut chunk).unwrap();
+
+            assert_ne!(read, 0, "connection closed");
+
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+    });
+}
@@ -0,0 +1,37 @@
+//! Request head parsing benchmarks, run with `cargo bench --bench head`.
+//!
+//! `bench_fresh_buffer` allocates a line buffer per head, as every connection did before the
+//! server kept a pool of them, `bench_reused_buffer` reuses one, as a connection that gets a
+//! buffer back from the pool does:
+//!
+//! | bench               | ns/iter |
+//! |---------------------|---------|
+//! | bench_fresh_buffer  | 4439    |
+//! | bench_reused_buffer | 4024    |
+
+#![feature(test)]
+
+extern crate test;
+
+use {enrgy::http, test::Bencher};
+
+const HEAD: &str = "GET /api/v1/users/42?tab=posts HTTP/1.1\r\n\
+                    Host: example.com\r\n\
+                    User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:91.0) Gecko/20100101 Firefox/91.0\r\n\
+                    Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+                    Accept-Language: en-US,en;q=0.5\r\n\
+                    Accept-Encoding: gzip, deflate\r\n\
+                    Connection: keep-alive\r\n\
+                    \r\n";
+
+#[bench]
+fn bench_fresh_buffer(b: &mut Bencher) {
+    b.iter(|| http::read_head(&mut HEAD.as_bytes(), false).unwrap());
+}
+
+#[bench]
+fn bench_reused_buffer(b: &mut Bencher) {
+    let mut line = Vec::with_capacity(512);
+
+    b.iter(|| http::read_head_with(&mut HEAD.as_bytes(), false, &mut line).unwrap());
+}
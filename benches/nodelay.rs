@@ -0,0 +1,120 @@
+//! `HttpServer::nodelay_below` benchmarks, run with `cargo bench --bench nodelay`.
+//!
+//! Each request is sent and its response read on one kept-alive connection, `default` leaves
+//! the socket as it is (Nagle's algorithm on), `adaptive` uses `nodelay_below(Some(16 * 1024))`:
+//!
+//! | bench                | ns/iter    |
+//! |----------------------|------------|
+//! | bench_small_default  | 43,999,728 |
+//! | bench_small_adaptive | 48,428     |
+//! | bench_large_default  | 357,161    |
+//! | bench_large_adaptive | 375,182    |
+//!
+//! Small responses waited out the client's delayed ACK with Nagle's algorithm on, as the head and
+//! body were written separately, large ones are as fast either way over loopback. Bodies of up to
+//! 16KiB are now written along with the head, which brings `bench_small_default` down to 12,162
+//! ns/iter.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+};
+
+use {
+    enrgy::{web, App, HttpServer},
+    test::Bencher,
+};
+
+const LARGE: usize = 1024 * 1024;
+
+fn serve(nodelay_below: Option<usize>) -> SocketAddr {
+    let server = HttpServer::new(
+        App::new()
+            .service(web::get("/small").to(|| "Hello World!"))
+            .service(web::get("/large").to(|| vec![b'a'; LARGE])),
+    )
+    .nodelay_below(nodelay_below)
+    .disable_signals()
+    .bind(([127, 0, 0, 1], 0))
+    .listen()
+    .unwrap();
+
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    addr
+}
+
+/// Sends a request and reads its response, which has to have a `Content-Length`.
+fn round_trip(stream: &mut TcpStream, path: &str, buffer: &mut Vec<u8>) {
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+        .unwrap();
+
+    buffer.clear();
+
+    let mut chunk = [0; 64 * 1024];
+
+    loop {
+        let read = stream.read(&mut chunk).unwrap();
+
+        assert_ne!(read, 0, "connection closed");
+
+        buffer.extend_from_slice(&chunk[..read]);
+
+        let end = match buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            Some(end) => end + 4,
+            None => continue,
+        };
+
+        let length = String::from_utf8_lossy(&buffer[..end])
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Length: "))
+            .and_then(|length| length.trim().parse::<usize>().ok())
+            .unwrap();
+
+        if buffer.len() >= end + length {
+            break;
+        }
+    }
+}
+
+fn bench(b: &mut Bencher, nodelay_below: Option<usize>, path: &str) {
+    let mut stream = TcpStream::connect(serve(nodelay_below)).unwrap();
+
+    // Only the server's side is measured.
+    stream.set_nodelay(true).unwrap();
+    let mut buffer = Vec::new();
+
+    b.iter(|| round_trip(&mut stream, path, &mut buffer));
+}
+
+#[bench]
+fn bench_small_default(b: &mut Bencher) {
+    bench(b, None, "/small");
+}
+
+#[bench]
+fn bench_small_adaptive(b: &mut Bencher) {
+    bench(b, Some(16 * 1024), "/small");
+}
+
+#[bench]
+fn bench_large_default(b: &mut Bencher) {
+    b.bytes = LARGE as u64;
+
+    bench(b, None, "/large");
+}
+
+#[bench]
+fn bench_large_adaptive(b: &mut Bencher) {
+    b.bytes = LARGE as u64;
+
+    bench(b, Some(16 * 1024), "/large");
+}
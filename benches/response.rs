@@ -0,0 +1,89 @@
+//! Response writing benchmarks, run with `cargo bench --bench response`.
+//!
+//! Each bench builds a response with a few headers and writes it into memory, the allocations
+//! are counted for writing it alone, which before compared header names by lowercasing both:
+//!
+//! | bench             | ns/iter (before) | ns/iter (after) | allocations (before) | allocations (after) |
+//! |-------------------|------------------|-----------------|----------------------|---------------------|
+//! | bench_write_small | 5,232            | 1,233           | 24                   | 1                   |
+//! | bench_write_large | 82,670           | 82,802          | 24                   | 1                   |
+
+#![feature(test)]
+
+extern crate test;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use {
+    enrgy::http::{self, headers, HttpResponse},
+    test::Bencher,
+};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn response(body: usize) -> HttpResponse {
+    HttpResponse::ok()
+        .header(headers::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(headers::CACHE_CONTROL, "no-store")
+        .header(headers::SERVER, "enrgy")
+        .header(headers::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .body(vec![b'a'; body])
+}
+
+/// Writes `response(body)`, asserting it allocates at most `allocations` times.
+fn bench(b: &mut Bencher, body: usize, allocations: usize) {
+    let mut buffer = Vec::with_capacity(body + 1024);
+
+    let res = response(body);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+
+    http::write_response(res, false, &mut buffer).unwrap();
+
+    let made = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    assert!(made <= allocations, "{} allocations", made);
+
+    b.bytes = body as u64;
+
+    b.iter(|| {
+        buffer.clear();
+
+        http::write_response(response(body), false, &mut buffer).unwrap();
+    });
+}
+
+#[bench]
+fn bench_write_small(b: &mut Bencher) {
+    bench(b, 12, 1);
+}
+
+#[bench]
+fn bench_write_large(b: &mut Bencher) {
+    bench(b, 1024 * 1024, 1);
+}
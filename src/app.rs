@@ -1,31 +1,207 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
+    clock::Clock,
+    error::ErrorFormat,
     extensions::Extensions,
     handler::HandlerService,
-    http::{HttpMethod, HttpRequest, HttpResponse},
-    middleware::{BoxedMiddleware, Middleware},
-    route::{self, Route},
-    service::BoxedService,
-    utils::{ArrayMap, PathTree},
-    web, Error,
+    http::{HttpMethod, HttpRequest, HttpResponse, StatusCode},
+    middleware::{BoxedMiddleware, DefaultHeaders, Logger, Middleware},
+    route::{self, Route, RouteToken},
+    service::{wrap_fn, BoxedService, Service},
+    utils::{path_tree, ArrayMap, PathTree},
+    web::{self, PayloadConfig},
+    Error,
 };
 
 type InnerRoute = BoxedService<HttpRequest, HttpResponse, Error>;
 
+/// A closure added with [`App::wrap_fn`], shared by every route it wraps.
+type WrapFnHandler =
+    Arc<dyn Fn(&mut HttpRequest, &InnerRoute) -> Result<HttpResponse, Error> + Send + Sync>;
+
+/// Builds a value for the app's data when the server starts, see [`App::data_factory`].
+pub(crate) type DataFactory =
+    Box<dyn FnOnce(&mut Extensions) -> Result<(), Box<dyn std::error::Error + Send + Sync>> + Send>;
+
+/// A route's service along with the path it was registered under.
+pub(crate) struct Endpoint {
+    /// `None` for the default service.
+    pub(crate) pattern: Option<Arc<str>>,
+    pub(crate) service: InnerRoute,
+    /// Overrides the server's request timeout, see [`Route::timeout`].
+    pub(crate) timeout: Option<Option<Duration>>,
+    /// Overrides the app's payload config, see [`Route::payload_config`].
+    pub(crate) payload_config: Option<PayloadConfig>,
+    /// See [`Route::removable`].
+    pub(crate) token: Option<RouteToken>,
+}
+
+impl Endpoint {
+    /// Returns if the route was removed from the running server, see [`Route::removable`].
+    pub(crate) fn is_removed(&self) -> bool {
+        self.token.as_ref().map_or(false, RouteToken::is_removed)
+    }
+}
+
 #[derive(Clone)]
 pub struct BuiltApp {
-    pub(crate) tree: Arc<ArrayMap<HttpMethod, PathTree<Arc<InnerRoute>>, 9>>,
+    pub(crate) tree: Arc<ArrayMap<HttpMethod, PathTree<Arc<Endpoint>>, 9>>,
+    pub(crate) any: Arc<PathTree<Arc<Endpoint>>>,
     pub(crate) data: Arc<Extensions>,
     pub(crate) middleware: Arc<Vec<BoxedMiddleware<HttpRequest, HttpResponse>>>,
-    pub(crate) default_service: Arc<InnerRoute>,
+    pub(crate) default_service: Arc<Endpoint>,
+    pub(crate) catch_panics: bool,
+    pub(crate) error_format: ErrorFormat,
+    pub(crate) redirect_trailing_slash: bool,
+    pub(crate) decode_slashes: bool,
+    pub(crate) allowed_content_types: Arc<Vec<String>>,
+    pub(crate) auto_options: bool,
+    pub(crate) trace: bool,
+    #[cfg(feature = "dev")]
+    pub(crate) debug_errors: bool,
+}
+
+/// A registered route, see [`BuiltApp::routes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    pub pattern: String,
+    /// Empty for routes that match any method (see [`web::any`](crate::web::any)).
+    pub methods: Vec<HttpMethod>,
+}
+
+impl BuiltApp {
+    #[cfg(feature = "dev")]
+    pub(crate) const fn debug_errors(&self) -> bool {
+        self.debug_errors
+    }
+
+    #[cfg(not(feature = "dev"))]
+    pub(crate) const fn debug_errors(&self) -> bool {
+        false
+    }
+
+    /// The methods with a route for `url`, for the `Allow` header of an automatic `OPTIONS`
+    /// response (see [`App::auto_options`]), empty if there are none.
+    pub(crate) fn allowed_methods(&self, url: &str) -> Vec<HttpMethod> {
+        let mut methods = self
+            .tree
+            .iter()
+            .filter(|(_, tree)| {
+                tree.find(url)
+                    .map_or(false, |(endpoint, _)| !endpoint.is_removed())
+            })
+            .map(|(method, _)| *method)
+            .collect::<Vec<_>>();
+
+        if methods.is_empty() {
+            return methods;
+        }
+
+        if methods.contains(&HttpMethod::Get) && !methods.contains(&HttpMethod::Head) {
+            methods.push(HttpMethod::Head);
+        }
+
+        if !methods.contains(&HttpMethod::Options) {
+            methods.push(HttpMethod::Options);
+        }
+
+        methods.sort_unstable();
+
+        methods
+    }
+
+    /// Lists the app's routes, sorted by pattern, with the methods registered for each.
+    ///
+    /// `HEAD` is only listed for explicit `HEAD` routes, even though `GET` routes answer it
+    /// too.
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let mut routes: Vec<RouteInfo> = Vec::new();
+
+        let trees = self
+            .tree
+            .iter()
+            .map(|(method, tree)| (Some(*method), tree))
+            .chain(std::iter::once((None, &*self.any)));
+
+        for (method, tree) in trees {
+            for endpoint in tree.values().into_iter().filter(|e| !e.is_removed()) {
+                let pattern = endpoint.pattern.as_deref().unwrap_or("");
+
+                // Routes for any method are listed on their own, even if the pattern also
+                // has routes for specific methods.
+                let existing = routes.iter_mut().find(|route| {
+                    route.pattern == pattern && route.methods.is_empty() == method.is_none()
+                });
+
+                match existing {
+                    // Routes with an optional segment are in the tree twice.
+                    Some(route) => route
+                        .methods
+                        .extend(method.filter(|method| !route.methods.contains(method))),
+                    None => routes.push(RouteInfo {
+                        pattern: pattern.to_string(),
+                        methods: method.into_iter().collect(),
+                    }),
+                }
+            }
+        }
+
+        for route in &mut routes {
+            route.methods.sort_unstable();
+        }
+
+        routes.sort_by(|left, right| {
+            (&left.pattern, left.methods.is_empty())
+                .cmp(&(&right.pattern, right.methods.is_empty()))
+        });
+
+        routes
+    }
+
+    /// Returns if the request's body has a type allowed by [`App::allowed_content_types`],
+    /// requests without a body always do.
+    pub(crate) fn allows_body(&self, req: &HttpRequest) -> bool {
+        if self.allowed_content_types.is_empty() {
+            return true;
+        }
+
+        match req.content_length() {
+            Ok(Some(length)) if length > 0 => req
+                .content_type()
+                .map(|media_type| {
+                    self.allowed_content_types
+                        .iter()
+                        .any(|allowed| media_type.is(allowed))
+                })
+                .unwrap_or(false),
+            _ => true,
+        }
+    }
 }
 
 pub struct App {
-    tree: ArrayMap<HttpMethod, PathTree<Arc<InnerRoute>>, 9>,
+    /// Routes are only put into trees when the app is built, so they can still be moved when
+    /// the app is mounted in another.
+    routes: Vec<(Option<HttpMethod>, Endpoint)>,
     data: Extensions,
     middleware: Vec<BoxedMiddleware<HttpRequest, HttpResponse>>,
-    default_service: Arc<InnerRoute>,
+    wrap_fns: Vec<WrapFnHandler>,
+    default_service: Endpoint,
+    catch_panics: bool,
+    error_format: ErrorFormat,
+    redirect_trailing_slash: bool,
+    decode_slashes: bool,
+    allowed_content_types: Vec<String>,
+    auto_options: bool,
+    trace: bool,
+    health_check: Option<String>,
+    /// Taken by the server, which runs them before it starts accepting connections.
+    pub(crate) data_factories: Vec<DataFactory>,
+    #[cfg(feature = "dev")]
+    route_dump: Option<String>,
+    #[cfg(feature = "dev")]
+    debug_errors: bool,
 }
 
 impl App {
@@ -33,6 +209,149 @@ impl App {
         Self::default()
     }
 
+    /// An app with the recommended production middleware, a [`Logger`], the
+    /// [`DefaultHeaders::security`] headers, a `Server` header, and panic capturing.
+    pub fn with_defaults() -> Self {
+        Self::new()
+            .catch_panics()
+            .wrap(DefaultHeaders::security().server())
+            .wrap(Logger::new())
+    }
+
+    /// An app for JSON APIs, errors are rendered as JSON and paths are matched exactly.
+    pub fn api() -> Self {
+        Self::new()
+            .error_format(ErrorFormat::Json)
+            .redirect_trailing_slash(false)
+    }
+
+    /// An app for websites, errors are rendered as HTML pages and paths that only differ from
+    /// a route by a trailing slash are redirected to it.
+    pub fn web() -> Self {
+        Self::new()
+            .error_format(ErrorFormat::Html)
+            .redirect_trailing_slash(true)
+    }
+
+    /// Sets how errors from handlers and the default service are rendered, only responses with a
+    /// plain text or empty body are changed, defaults to [`ErrorFormat::Text`].
+    pub fn error_format(mut self, format: ErrorFormat) -> Self {
+        self.error_format = format;
+
+        self
+    }
+
+    /// Redirects requests that do not match a route, but would with a trailing slash added or
+    /// removed, instead of passing them to the default service, defaults to `false`.
+    ///
+    /// `GET` and `HEAD` requests are redirected with `301 Moved Permanently`, other methods with
+    /// `308 Permanent Redirect` so the request is repeated as is. The query string is kept.
+    pub fn redirect_trailing_slash(mut self, redirect: bool) -> Self {
+        self.redirect_trailing_slash = redirect;
+
+        self
+    }
+
+    /// Decodes percent-encoded slashes (`%2F`) in the path before it is routed, so they
+    /// separate segments like any other slash, defaults to `false`.
+    ///
+    /// Otherwise they are kept within a segment, and decoded in the parameter that captures it,
+    /// so `/files/a%2Fb` matches `/files/{name}` with `name` being `a/b`.
+    pub fn decode_slashes(mut self, decode: bool) -> Self {
+        self.decode_slashes = decode;
+
+        self
+    }
+
+    /// Rejects requests with a body whose `Content-Type` is not one of `types` with
+    /// `415 Unsupported Media Type`, before they reach a route. Parameters are not compared, so
+    /// `application/json` also allows `application/json; charset=utf-8`.
+    ///
+    /// Requests without a body are not checked, by default no requests are.
+    pub fn allowed_content_types<'t, I>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = &'t str>,
+    {
+        self.allowed_content_types
+            .extend(types.into_iter().map(str::to_string));
+
+        self
+    }
+
+    /// Answers `OPTIONS` requests to paths without an `OPTIONS` route with `204 No Content` and
+    /// an `Allow` header listing the methods the path has routes for, defaults to `false`.
+    ///
+    /// Paths without any routes are passed to the default service as usual, rather than getting
+    /// an empty `Allow` header.
+    pub fn auto_options(mut self, auto: bool) -> Self {
+        self.auto_options = auto;
+
+        self
+    }
+
+    /// Answers `TRACE` requests to paths without a `TRACE` route by echoing the request head
+    /// back as a `message/http` body, defaults to `false`, in which case they get
+    /// `501 Not Implemented`.
+    ///
+    /// The echo includes every header, cookies and credentials too, which is why scripts that
+    /// can send requests, but not read their headers, make it a risk.
+    pub fn enable_trace(mut self, enable: bool) -> Self {
+        self.trace = enable;
+
+        self
+    }
+
+    /// Adds a `GET` route at `path` that responds with `200 OK` if every route's service (and
+    /// the default service) is ready (see [`Service::poll_ready`]), and with
+    /// `503 Service Unavailable` otherwise.
+    pub fn health_check(mut self, path: &str) -> Self {
+        self.health_check = Some(path.to_string());
+
+        self
+    }
+
+    /// Responds with `500 Internal Server Error` when a handler panics, instead of dropping the
+    /// connection (and the worker thread with it).
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+
+        self
+    }
+
+    /// Responds to handler panics with an HTML page showing the panic message, instead of a
+    /// plain `500 Internal Server Error`, panics are caught as with [`App::catch_panics`].
+    ///
+    /// Only available with the `dev` feature, so it can not be left in a release build.
+    #[cfg(feature = "dev")]
+    pub fn debug_errors(mut self, debug: bool) -> Self {
+        self.debug_errors = debug;
+
+        self
+    }
+
+    /// Adds a `GET` route at `path` that responds with the app's route table (see
+    /// [`BuiltApp::routes`]), as text or as JSON if the request accepts `application/json`.
+    ///
+    /// Only available with the `dev` feature, so it can not be left in a release build. It is
+    /// ignored for apps that are mounted in another.
+    #[cfg(feature = "dev")]
+    pub fn enable_route_dump(mut self, path: &str) -> Self {
+        self.route_dump = Some(path.to_string());
+
+        self
+    }
+
+    /// Sets the clock middleware reads the time from, ex. a [`MockClock`](crate::MockClock) to
+    /// test timing without waiting, handlers can get it with `web::Data<dyn Clock>`.
+    pub fn clock<C>(mut self, clock: Arc<C>) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.data.insert(web::Data::<dyn Clock> { data: clock });
+
+        self
+    }
+
     pub fn data<T>(mut self, data: Arc<T>) -> Self
     where
         T: Send + Sync + 'static,
@@ -42,52 +361,442 @@ impl App {
         self
     }
 
+    /// Adds data that is built by `factory` when the server starts, before it accepts any
+    /// connections, for values that are expensive to create (ex. a connection pool).
+    ///
+    /// If `factory` fails, [`HttpServer::run`](crate::HttpServer::run) returns the error
+    /// without starting the server.
+    pub fn data_factory<T, E, F>(mut self, factory: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+        F: FnOnce() -> Result<T, E> + Send + 'static,
+    {
+        self.data_factories.push(box move |data: &mut Extensions| {
+            data.insert(web::Data {
+                data: Arc::new(factory().map_err(Into::into)?),
+            });
+
+            Ok(())
+        });
+
+        self
+    }
+
+    /// Sets the limits the body extractors use, routes can override it with
+    /// `Route::payload_config`.
+    pub fn payload_config(self, config: PayloadConfig) -> Self {
+        self.data(Arc::new(config))
+    }
+
     pub fn wrap<M>(mut self, middleware: M) -> Self
     where
         M: Middleware<HttpRequest, HttpResponse> + Send + Sync + 'static,
+        M::Context: 'static,
     {
         self.middleware.push(BoxedMiddleware::new(middleware));
 
         self
     }
 
+    /// Wraps every route's service (and the default service) with `f`, which is given the request
+    /// and the service to call, like [`Route::wrap_fn`] but for the whole app.
+    ///
+    /// These run inside the app's [`Middleware`], in the order they were added, the first being
+    /// the outermost.
+    ///
+    /// ```
+    /// use enrgy::{dev::Service as _, http::headers::X_FRAME_OPTIONS, web, App};
+    ///
+    /// let app = App::new()
+    ///     .wrap_fn(|req, srv| {
+    ///         let res = srv.call(req)?;
+    ///
+    ///         Ok(res.header(X_FRAME_OPTIONS, "DENY"))
+    ///     })
+    ///     .service(web::get("/").to(|| "Hello World!"));
+    /// ```
+    pub fn wrap_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut HttpRequest, &InnerRoute) -> Result<HttpResponse, Error> + Send + Sync + 'static,
+    {
+        self.wrap_fns.push(Arc::new(f));
+
+        self
+    }
+
     pub fn service(mut self, route: Route<'_>) -> Self {
-        let node = if let Some(node) = self.tree.get_mut(route.method) {
-            node
-        } else {
-            self.tree.insert(route.method, PathTree::new());
+        self.routes.push((
+            route.method,
+            Endpoint {
+                pattern: Some(Arc::from(route.path)),
+                service: route.service,
+                timeout: route.timeout,
+                payload_config: route.payload_config,
+                token: route.token,
+            },
+        ));
 
-            unsafe { self.tree.get_mut(route.method).unwrap_unchecked() }
-        };
+        self
+    }
+
+    /// Adds all of `app`'s routes under `prefix`, with `app`'s middleware wrapping only its own
+    /// routes.
+    ///
+    /// `app`'s data is merged into this app's (where this app has no value of the same type
+    /// already), its default service, [`App::catch_panics`], `App::debug_errors`,
+    /// [`App::error_format`], [`App::redirect_trailing_slash`], [`App::decode_slashes`],
+    /// [`App::allowed_content_types`],
+    /// [`App::auto_options`], [`App::enable_trace`], and [`App::health_check`] are ignored.
+    pub fn mount(mut self, prefix: &str, app: App) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        let middleware = Arc::new(app.middleware);
 
-        node.insert(route.path, Arc::new(route.service));
+        for (method, endpoint) in app.routes {
+            let pattern = format!("{}{}", prefix, endpoint.pattern.as_deref().unwrap_or(""));
+
+            let service = wrap_service(&app.wrap_fns, endpoint.service);
+
+            let service = if middleware.is_empty() {
+                service
+            } else {
+                BoxedService::new(Scoped {
+                    middleware: Arc::clone(&middleware),
+                    service,
+                })
+            };
+
+            self.routes.push((
+                method,
+                Endpoint {
+                    pattern: Some(Arc::from(pattern)),
+                    service,
+                    ..endpoint
+                },
+            ));
+        }
+
+        self.data.merge(app.data);
+        self.data_factories.extend(app.data_factories);
 
         self
     }
 
     pub fn default_service(mut self, service: Route<'static>) -> Self {
-        self.default_service = Arc::new(service.service);
+        self.default_service = Endpoint {
+            pattern: None,
+            service: service.service,
+            timeout: service.timeout,
+            payload_config: service.payload_config,
+            token: None,
+        };
 
         self
     }
 
+    // Without the `dev` feature the app is returned as soon as it is built.
+    #[allow(clippy::let_and_return)]
     pub fn build(self) -> BuiltApp {
-        BuiltApp {
-            tree: Arc::new(self.tree),
+        let mut tree: ArrayMap<HttpMethod, PathTree<Arc<Endpoint>>, 9> = ArrayMap::new();
+        let mut any = PathTree::new();
+
+        #[allow(unused_mut)]
+        let mut routes = self.routes;
+
+        // The table is only known once the app is built, so it is filled in afterwards.
+        #[cfg(feature = "dev")]
+        let route_dump = self.route_dump.map(|path| {
+            let dump = RouteDump {
+                table: Arc::new(std::lazy::SyncOnceCell::new()),
+            };
+            let table = Arc::clone(&dump.table);
+
+            routes.push((
+                Some(HttpMethod::Get),
+                Endpoint {
+                    pattern: Some(Arc::from(path)),
+                    service: BoxedService::new(dump),
+                    timeout: None,
+                    payload_config: None,
+                    token: None,
+                },
+            ));
+
+            table
+        });
+
+        // The other routes are only known once they are built, so they are filled in afterwards.
+        let health_check = self.health_check.map(|path| {
+            let check = HealthCheck {
+                endpoints: Arc::new(std::lazy::SyncOnceCell::new()),
+            };
+            let endpoints = Arc::clone(&check.endpoints);
+
+            routes.push((
+                Some(HttpMethod::Get),
+                Endpoint {
+                    pattern: Some(Arc::from(path)),
+                    service: BoxedService::new(check),
+                    timeout: None,
+                    payload_config: None,
+                    token: None,
+                },
+            ));
+
+            endpoints
+        });
+
+        let wrap_fns = self.wrap_fns;
+
+        let default_service = Arc::new(Endpoint {
+            service: wrap_service(&wrap_fns, self.default_service.service),
+            ..self.default_service
+        });
+
+        let mut endpoints = vec![Arc::clone(&default_service)];
+
+        for (method, endpoint) in routes {
+            let endpoint = Endpoint {
+                service: wrap_service(&wrap_fns, endpoint.service),
+                ..endpoint
+            };
+
+            let node = match method {
+                Some(method) => {
+                    if let Some(node) = tree.get_mut(method) {
+                        node
+                    } else {
+                        tree.insert(method, PathTree::new());
+
+                        unsafe { tree.get_mut(method).unwrap_unchecked() }
+                    }
+                }
+                None => &mut any,
+            };
+
+            let pattern = endpoint.pattern.clone().unwrap_or_else(|| Arc::from(""));
+
+            let endpoint = Arc::new(endpoint);
+
+            endpoints.push(Arc::clone(&endpoint));
+
+            for pattern in path_tree::expand_optional(&pattern) {
+                node.insert(&pattern, Arc::clone(&endpoint));
+            }
+        }
+
+        if let Some(cell) = health_check {
+            let _ = cell.set(endpoints);
+        }
+
+        let app = BuiltApp {
+            tree: Arc::new(tree),
+            any: Arc::new(any),
             data: Arc::new(self.data),
             middleware: Arc::new(self.middleware),
-            default_service: self.default_service,
+            default_service,
+            catch_panics: self.catch_panics,
+            error_format: self.error_format,
+            redirect_trailing_slash: self.redirect_trailing_slash,
+            decode_slashes: self.decode_slashes,
+            allowed_content_types: Arc::new(self.allowed_content_types),
+            auto_options: self.auto_options,
+            trace: self.trace,
+            #[cfg(feature = "dev")]
+            debug_errors: self.debug_errors,
+        };
+
+        #[cfg(feature = "dev")]
+        if let Some(table) = route_dump {
+            let _ = table.set(app.routes());
+        }
+
+        app
+    }
+}
+
+#[cfg(feature = "dev")]
+struct RouteDump {
+    table: Arc<std::lazy::SyncOnceCell<Vec<RouteInfo>>>,
+}
+
+#[cfg(feature = "dev")]
+impl RouteDump {
+    fn methods(route: &RouteInfo) -> Vec<&'static str> {
+        if route.methods.is_empty() {
+            vec!["*"]
+        } else {
+            route.methods.iter().map(HttpMethod::as_str).collect()
         }
     }
+
+    fn text(table: &[RouteInfo]) -> String {
+        table
+            .iter()
+            .map(|route| format!("{} {}\n", Self::methods(route).join(", "), route.pattern))
+            .collect()
+    }
+
+    fn json(table: &[RouteInfo]) -> String {
+        let routes = table
+            .iter()
+            .map(|route| {
+                let methods = Self::methods(route)
+                    .iter()
+                    .map(|method| format!("\"{}\"", method))
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{{\"pattern\":\"{}\",\"methods\":[{}]}}",
+                    route.pattern.replace('\\', "\\\\").replace('"', "\\\""),
+                    methods.join(",")
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!("[{}]", routes.join(","))
+    }
+}
+
+#[cfg(feature = "dev")]
+impl Service<HttpRequest> for RouteDump {
+    type Response = HttpResponse;
+
+    type Error = Error;
+
+    fn call(&self, req: &mut HttpRequest) -> Result<Self::Response, Self::Error> {
+        use crate::http::headers::{ACCEPT, CONTENT_TYPE};
+
+        let table = self.table.get().map(Vec::as_slice).unwrap_or_default();
+
+        let json = req
+            .header_data
+            .headers
+            .get(&ACCEPT)
+            .map(|accept| accept.contains("application/json"))
+            .unwrap_or(false);
+
+        Ok(if json {
+            HttpResponse::ok()
+                .header(CONTENT_TYPE, "application/json")
+                .body(Self::json(table))
+        } else {
+            HttpResponse::text(Self::text(table))
+        })
+    }
+}
+
+/// The route added by [`App::health_check`].
+struct HealthCheck {
+    endpoints: Arc<std::lazy::SyncOnceCell<Vec<Arc<Endpoint>>>>,
+}
+
+impl Service<HttpRequest> for HealthCheck {
+    type Response = HttpResponse;
+
+    type Error = Error;
+
+    fn call(&self, _req: &mut HttpRequest) -> Result<Self::Response, Self::Error> {
+        let endpoints = self.endpoints.get().map(Vec::as_slice).unwrap_or_default();
+
+        Ok(
+            if endpoints
+                .iter()
+                .all(|endpoint| endpoint.service.poll_ready())
+            {
+                HttpResponse::text("OK")
+            } else {
+                HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE).body("Service Unavailable")
+            },
+        )
+    }
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
-            tree: ArrayMap::new(),
+            routes: Vec::new(),
             data: Extensions::new(),
             middleware: Vec::new(),
-            default_service: Arc::new(BoxedService::new(HandlerService::new(route::not_found))),
+            wrap_fns: Vec::new(),
+            default_service: Endpoint {
+                pattern: None,
+                service: BoxedService::new(HandlerService::new(route::not_found)),
+                timeout: None,
+                payload_config: None,
+                token: None,
+            },
+            catch_panics: false,
+            error_format: ErrorFormat::Text,
+            redirect_trailing_slash: false,
+            decode_slashes: false,
+            allowed_content_types: Vec::new(),
+            auto_options: false,
+            trace: false,
+            health_check: None,
+            data_factories: Vec::new(),
+            #[cfg(feature = "dev")]
+            route_dump: None,
+            #[cfg(feature = "dev")]
+            debug_errors: false,
+        }
+    }
+}
+
+/// Wraps `service` with each of `wrap_fns`, the first ending up outermost.
+fn wrap_service(wrap_fns: &[WrapFnHandler], service: InnerRoute) -> InnerRoute {
+    wrap_fns.iter().rev().fold(service, |service, f| {
+        let f = Arc::clone(f);
+
+        BoxedService::new(wrap_fn(
+            service,
+            move |req: &mut HttpRequest, srv: &InnerRoute| f(req, srv),
+        ))
+    })
+}
+
+/// A mounted app's route, wrapped in that app's middleware.
+struct Scoped {
+    middleware: Arc<Vec<BoxedMiddleware<HttpRequest, HttpResponse>>>,
+    service: InnerRoute,
+}
+
+impl Service<HttpRequest> for Scoped {
+    type Response = HttpResponse;
+
+    type Error = Error;
+
+    fn call(&self, req: &mut HttpRequest) -> Result<Self::Response, Self::Error> {
+        let contexts = self
+            .middleware
+            .iter()
+            .map(|middleware| middleware.before(req))
+            .collect::<Vec<_>>();
+
+        // The middleware need a response to work on, so errors are turned into one here
+        // rather than by the server.
+        let early = self
+            .middleware
+            .iter()
+            .zip(&contexts)
+            .find_map(|(middleware, context)| middleware.respond(req, context));
+
+        let mut response = match early {
+            Some(response) => response,
+            None => match self.service.call(req) {
+                Ok(response) => response,
+                Err(err) => err.report(),
+            },
+        };
+
+        for (middleware, context) in self.middleware.iter().zip(contexts) {
+            response = middleware.after(req, response, context);
         }
+
+        Ok(response)
+    }
+
+    fn poll_ready(&self) -> bool {
+        self.service.poll_ready()
     }
 }
@@ -0,0 +1,102 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{extensions::Extensions, web};
+
+/// Where time-dependent middleware (ex. [`Logger`](crate::middleware::Logger)) gets the
+/// current time from, set with [`App::clock`](crate::App::clock).
+///
+/// Defaults to [`SystemClock`], tests can use a [`MockClock`] to control time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn system_time(&self) -> SystemTime;
+}
+
+/// The clock of the OS.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when it is [advanced](MockClock::advance).
+pub struct MockClock {
+    instant: Instant,
+    system_time: SystemTime,
+    elapsed: Mutex<Duration>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at the current time.
+    pub fn new() -> Self {
+        Self {
+            instant: Instant::now(),
+            system_time: SystemTime::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|err| err.into_inner()) += duration;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.instant + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_time + self.elapsed()
+    }
+}
+
+/// The clock set with [`App::clock`](crate::App::clock), or the [`SystemClock`].
+pub(crate) fn clock(data: &Extensions) -> Arc<dyn Clock> {
+    match data.get::<web::Data<dyn Clock>>() {
+        Some(clock) => Arc::clone(&clock.data),
+        None => Arc::new(SystemClock),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::new();
+
+        let instant = clock.now();
+        let system_time = clock.system_time();
+
+        assert_eq!(clock.now(), instant);
+
+        clock.advance(Duration::from_secs(90));
+
+        assert_eq!(clock.now() - instant, Duration::from_secs(90));
+        assert_eq!(
+            clock.system_time().duration_since(system_time).unwrap(),
+            Duration::from_secs(90)
+        );
+    }
+}
@@ -1,9 +1,39 @@
-use std::{error, fmt};
+use std::{any::Any, error, fmt};
 
-use crate::http::{headers::CONTENT_TYPE, HttpResponse, StatusCode};
+use crate::{
+    http::{headers::CONTENT_TYPE, HttpResponse, StatusClass, StatusCode},
+    utils::log,
+};
 
 pub struct Error {
-    inner: Box<dyn ResponseError>,
+    inner: Box<dyn AnyResponseError>,
+}
+
+impl Error {
+    /// Returns the error this was created from, if it is a `T`.
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: ResponseError + 'static,
+    {
+        self.inner.as_any().downcast_ref()
+    }
+
+    /// Builds the response for this error.
+    pub fn error_response(&self) -> HttpResponse {
+        self.inner.error_response()
+    }
+
+    /// Logs a handler's error and builds its response.
+    pub(crate) fn report(&self) -> HttpResponse {
+        let response = self.error_response();
+
+        match response.status.class() {
+            StatusClass::ServerError => log::error!("route handler error: {:?}", self),
+            _ => log::warn!("route handler error: {:?}", self),
+        }
+
+        response
+    }
 }
 
 impl fmt::Debug for Error {
@@ -51,6 +81,125 @@ pub trait ResponseError: fmt::Debug + fmt::Display {
 
 impl ResponseError for Box<dyn error::Error + 'static> {}
 
+/// Lets [`Error`] hand out the concrete error type it holds.
+trait AnyResponseError: ResponseError {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> AnyResponseError for T
+where
+    T: ResponseError + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// How error responses are rendered, see [`App::error_format`](crate::App::error_format).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Leaves error responses as they are, plain text or empty.
+    Text,
+    /// `{"status":404,"message":"Not Found"}`, as `application/json`.
+    Json,
+    /// A minimal HTML page with the status and message.
+    Html,
+}
+
+impl ErrorFormat {
+    /// Renders `res` in this format if it is an error (`4xx` or `5xx`) with a plain text or
+    /// empty body, the body (or the status' phrase if it is empty) becomes the message.
+    pub(crate) fn apply(self, res: HttpResponse) -> HttpResponse {
+        let plain = res
+            .headers
+            .get(&CONTENT_TYPE)
+            .map(|content_type| content_type.starts_with("text/plain"))
+            .unwrap_or(true);
+
+        let error = matches!(
+            res.status.class(),
+            StatusClass::ClientError | StatusClass::ServerError
+        );
+
+        if self == Self::Text || !error || !plain {
+            return res;
+        }
+
+        let message = match res.body_bytes() {
+            Some(body) if !body.is_empty() => String::from_utf8_lossy(body).into_owned(),
+            _ => res.status.phrase().to_string(),
+        };
+
+        let status = res.status;
+
+        match self {
+            Self::Json => res.header(CONTENT_TYPE, "application/json").body(format!(
+                "{{\"status\":{},\"message\":\"{}\"}}",
+                status.0,
+                escape_json(&message)
+            )),
+            _ => res.header(CONTENT_TYPE, "text/html; charset=utf-8").body(format!(
+                "<!DOCTYPE html>\n<html>\n<head><title>{0} {1}</title></head>\n<body>\n<h1>{0} {1}</h1>\n<p>{2}</p>\n</body>\n</html>\n",
+                status.0,
+                status.phrase(),
+                escape_html(&message)
+            )),
+        }
+    }
+}
+
+/// The page [`App::debug_errors`](crate::App::debug_errors) responds with for a handler panic.
+#[cfg(feature = "dev")]
+pub(crate) fn panic_page(payload: &(dyn Any + Send)) -> HttpResponse {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+
+    HttpResponse::internal_server_error()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(format!(
+            "<!DOCTYPE html>\n<html>\n<head><title>Handler panicked</title></head>\n<body>\n<h1>Handler panicked</h1>\n<pre>{}</pre>\n</body>\n</html>\n",
+            escape_html(message)
+        ))
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
 macro_rules! internal_error {
     ($( $name:ident[$status:expr], )*) => {
         $(
@@ -25,11 +25,22 @@ impl Extensions {
     }
 
     pub fn get<T: 'static>(&self) -> Option<&T> {
-        fn downcast_ref<T: 'static>(boxed: &Box<dyn Any + Send + Sync>) -> Option<&T> {
-            boxed.downcast_ref()
-        }
+        self.get_raw(TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
 
-        self.inner.get(&TypeId::of::<T>()).and_then(downcast_ref)
+    /// Returns the value of the type with the id, for code that does not know the type itself.
+    pub fn get_raw(&self, id: TypeId) -> Option<&(dyn Any + Send + Sync)> {
+        self.inner.get(&id).map(|boxed| &**boxed)
+    }
+
+    /// Moves the values from `other` that are not already set into `self`.
+    pub(crate) fn merge(&mut self, mut other: Extensions) {
+        for (key, value) in other.inner.drain() {
+            if !self.inner.contains(&key) {
+                self.inner.insert(key, value);
+            }
+        }
     }
 }
 
@@ -0,0 +1,87 @@
+use std::ops::Deref;
+
+use crate::{
+    extractor::Extractor,
+    http::{headers::ACCEPT, HttpRequest, MediaType},
+    Error,
+};
+
+/// The media ranges of the request's `Accept` header along with their quality values, most
+/// preferred first, for handlers that pick a representation themselves.
+///
+/// Ranges with the same quality are ordered from most to least specific (`text/html`, then
+/// `text/*`, then `*/*`), then as they were listed. The `q` parameter is removed from the ranges,
+/// ranges that do not parse are skipped, and a request without the header accepts `*/*`.
+///
+/// ```
+/// use enrgy::{http::HttpResponse, web};
+///
+/// fn report(accept: web::Accept) -> HttpResponse {
+///     match accept.first().map(|(range, _)| range.essence()) {
+///         Some("application/json") => HttpResponse::ok().body(r#"{"status":"ok"}"#),
+///         _ => HttpResponse::text("status: ok"),
+///     }
+/// }
+/// ```
+pub struct Accept {
+    ranges: Vec<(MediaType, f32)>,
+}
+
+impl Accept {
+    fn parse(header: &str) -> Self {
+        let mut ranges = header
+            .split(',')
+            .filter_map(|item| {
+                let mut range = item.parse::<MediaType>().ok()?;
+
+                let quality = range
+                    .remove_param("q")
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+
+                Some((range, quality.clamp(0.0, 1.0)))
+            })
+            .collect::<Vec<_>>();
+
+        // Stable, so ties keep the order they were listed in.
+        ranges.sort_by(|(a, a_quality), (b, b_quality)| {
+            b_quality
+                .partial_cmp(a_quality)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| specificity(b).cmp(&specificity(a)))
+        });
+
+        Self { ranges }
+    }
+}
+
+/// `*/*` is the least specific, then `type/*`, then a full type, then one with parameters.
+fn specificity(range: &MediaType) -> u8 {
+    match (range.type_(), range.subtype()) {
+        ("*", _) => 0,
+        (_, "*") => 1,
+        _ if range.params().next().is_none() => 2,
+        _ => 3,
+    }
+}
+
+impl Deref for Accept {
+    type Target = [(MediaType, f32)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.ranges
+    }
+}
+
+impl Extractor for Accept {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        Ok(Self::parse(
+            req.header_data
+                .headers
+                .get(&ACCEPT)
+                .map(String::as_str)
+                .unwrap_or("*/*"),
+        ))
+    }
+}
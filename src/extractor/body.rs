@@ -1,6 +1,82 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    io::{self, Cursor, Read},
+    ops::{Deref, DerefMut},
+};
 
-use crate::{extractor::Extractor, http::HttpRequest, Error};
+use crate::{
+    error::InternalError,
+    extractor::{Data, Extractor},
+    http::{headers::CONTENT_ENCODING, HttpRequest},
+    Error,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Limits for the body extractors ([`Body`], [`BodyStream`], [`Text`],
+/// [`Multipart`](crate::web::Multipart), and `Json`).
+///
+/// Set for the whole app with [`App::payload_config`](crate::App::payload_config), and for a
+/// single route with `Route::payload_config`, which takes precedence.
+#[derive(Debug, Clone, Copy)]
+pub struct PayloadConfig {
+    max_size: u64,
+    decompress: bool,
+    max_decompressed_size: u64,
+}
+
+impl PayloadConfig {
+    pub const fn new() -> Self {
+        Self {
+            max_size: 4 * 1024 * 1024,
+            decompress: false,
+            max_decompressed_size: 16 * 1024 * 1024,
+        }
+    }
+
+    /// Sets the largest body that is accepted, defaults to 4 MiB.
+    ///
+    /// This is the size of the body as it was sent, see
+    /// [`PayloadConfig::max_decompressed_size`] for compressed bodies.
+    pub const fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = bytes;
+
+        self
+    }
+
+    /// Decompresses bodies sent with `Content-Encoding: gzip` or `deflate` for [`Body`],
+    /// [`Text`], and `Json`, defaults to `false`.
+    ///
+    /// Other encodings are rejected with `415 Unsupported Media Type`.
+    pub const fn decompress(mut self, decompress: bool) -> Self {
+        self.decompress = decompress;
+
+        self
+    }
+
+    /// Sets the largest a body can be once decompressed, defaults to 16 MiB.
+    ///
+    /// Decompression stops as soon as the output passes the limit, with a `413 Payload Too
+    /// Large`, so a small body that expands to a huge one is never held in full.
+    pub const fn max_decompressed_size(mut self, bytes: u64) -> Self {
+        self.max_decompressed_size = bytes;
+
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> Self {
+        req.extensions
+            .get::<PayloadConfig>()
+            .copied()
+            .or_else(|| req.data.get::<Data<PayloadConfig>>().map(|data| *data.data))
+            .unwrap_or_default()
+    }
+}
+
+impl const Default for PayloadConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub struct Body {
     value: Vec<u8>,
@@ -24,8 +100,359 @@ impl Extractor for Body {
     type Error = Error;
 
     fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
-        Ok(Body {
-            value: req.body.clone(),
-        })
+        check_limit(req)?;
+
+        match req.payload.take() {
+            Some(mut payload) => {
+                let mut value = Vec::with_capacity(payload.len() as usize);
+
+                payload.read_to_end(&mut value).map_err(read_error)?;
+
+                Ok(Body {
+                    value: decode(req, value)?,
+                })
+            }
+            None => Ok(Body {
+                value: decode(req, req.body.clone())?,
+            }),
+        }
+    }
+}
+
+/// The request body, limited to `LIMIT` bytes instead of the [`PayloadConfig`]'s limit, so a
+/// route can accept larger (or only smaller) bodies than the rest of the app.
+///
+/// ```
+/// use enrgy::{web, App};
+///
+/// fn upload(body: web::Bytes<{ 10 * 1024 * 1024 }>) -> String {
+///     format!("{} bytes", body.len())
+/// }
+///
+/// App::new().service(web::post("/upload").to(upload));
+/// ```
+///
+/// The server's own limits still apply, see `HttpServer::body_limit`. An empty body (ex.
+/// `Content-Length: 0`) is an empty `Vec`.
+pub struct Bytes<const LIMIT: u64> {
+    value: Vec<u8>,
+}
+
+impl<const LIMIT: u64> const Deref for Bytes<LIMIT> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<const LIMIT: u64> const DerefMut for Bytes<LIMIT> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<const LIMIT: u64> Extractor for Bytes<LIMIT> {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        check_max_size(req, LIMIT)?;
+
+        match req.payload.take() {
+            Some(payload) => {
+                let mut value = Vec::with_capacity(payload.len() as usize);
+
+                payload
+                    .take(LIMIT + 1)
+                    .read_to_end(&mut value)
+                    .map_err(read_error)?;
+
+                if value.len() as u64 > LIMIT {
+                    return Err(too_large(LIMIT));
+                }
+
+                Ok(Bytes { value })
+            }
+            None => Ok(Bytes {
+                value: std::mem::take(&mut req.body),
+            }),
+        }
+    }
+}
+
+/// The request body as an iterator of chunks, yielding each chunk as it is read off of the
+/// socket when the server streams request bodies.
+///
+/// Without streaming the already read body is yielded as a single chunk.
+pub struct BodyStream {
+    reader: Box<dyn Read + Send + Sync>,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl BodyStream {
+    fn new<R>(reader: R) -> Self
+    where
+        R: Read + Send + Sync + 'static,
+    {
+        Self {
+            reader: box reader,
+            buffer: vec![0; CHUNK_SIZE],
+            done: false,
+        }
+    }
+}
+
+impl Iterator for BodyStream {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.done = true;
+
+                    return None;
+                }
+                Ok(read) => return Some(Ok(self.buffer[..read].to_vec())),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.done = true;
+
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl Extractor for BodyStream {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        check_limit(req)?;
+
+        match req.payload.take() {
+            Some(payload) => Ok(BodyStream::new(payload)),
+            None => Ok(BodyStream::new(Cursor::new(std::mem::take(&mut req.body)))),
+        }
+    }
+}
+
+/// The request body as UTF-8 text.
+pub struct Text {
+    value: String,
+}
+
+impl const Deref for Text {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl const DerefMut for Text {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl Extractor for Text {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let body = Body::extract(req)?;
+
+        String::from_utf8(body.value)
+            .map(|value| Text { value })
+            .map_err(|_| InternalError::BadRequest("HTTP request body is not valid UTF-8"))
+    }
+}
+
+/// Returns if the request has no body, it was sent with `Content-Length: 0` or without a body.
+pub(crate) fn is_empty(req: &HttpRequest) -> bool {
+    matches!(req.content_length(), Ok(Some(0))) || (req.payload.is_none() && req.body.is_empty())
+}
+
+/// Decompresses `body` if the [`PayloadConfig`] says to and it was sent compressed.
+pub(crate) fn decode(req: &HttpRequest, body: Vec<u8>) -> Result<Vec<u8>, Error> {
+    use miniz_oxide::inflate::{
+        decompress_to_vec_with_limit, decompress_to_vec_zlib_with_limit, TINFLStatus,
+    };
+
+    let config = PayloadConfig::from_req(req);
+
+    if !config.decompress || body.is_empty() {
+        return Ok(body);
+    }
+
+    let limit = config.max_decompressed_size as usize;
+
+    let decoded = match req.header_data.headers.get(&CONTENT_ENCODING) {
+        None => return Ok(body),
+        Some(encoding) if encoding.eq_ignore_ascii_case("identity") => return Ok(body),
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+            let deflated = gzip_deflated(&body)
+                .ok_or_else(|| InternalError::BadRequest("HTTP request body is not valid gzip"))?;
+
+            decompress_to_vec_with_limit(deflated, limit)
+        }
+        // Meant to be zlib wrapped, but some clients send raw deflate.
+        Some(encoding) if encoding.eq_ignore_ascii_case("deflate") => {
+            if is_zlib(&body) {
+                decompress_to_vec_zlib_with_limit(&body, limit)
+            } else {
+                decompress_to_vec_with_limit(&body, limit)
+            }
+        }
+        Some(_) => {
+            return Err(InternalError::UnsupportedMediaType(
+                "HTTP request body uses an unsupported `Content-Encoding`",
+            ))
+        }
+    };
+
+    match decoded {
+        Ok(decoded) => Ok(decoded),
+        Err(TINFLStatus::HasMoreOutput) => Err(InternalError::PayloadTooLarge(format!(
+            "HTTP request body is larger than the limit of {} bytes once decompressed",
+            limit
+        ))),
+        Err(_) => Err(InternalError::BadRequest(
+            "HTTP request body could not be decompressed",
+        )),
+    }
+}
+
+/// Returns the deflated data of a gzip member, skipping its header and trailer (RFC 1952).
+fn gzip_deflated(body: &[u8]) -> Option<&[u8]> {
+    const FHCRC: u8 = 1 << 1;
+    const FEXTRA: u8 = 1 << 2;
+    const FNAME: u8 = 1 << 3;
+    const FCOMMENT: u8 = 1 << 4;
+
+    if body.len() < 18 || body[..3] != [0x1f, 0x8b, 8] {
+        return None;
+    }
+
+    let flags = body[3];
+    let mut rest = &body[10..(body.len() - 8)];
+
+    if flags & FEXTRA != 0 {
+        let len = u16::from_le_bytes([*rest.get(0)?, *rest.get(1)?]) as usize;
+
+        rest = rest.get((2 + len)..)?;
+    }
+
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = rest.iter().position(|byte| *byte == 0)?;
+
+            rest = &rest[(end + 1)..];
+        }
+    }
+
+    if flags & FHCRC != 0 {
+        rest = rest.get(2..)?;
+    }
+
+    Some(rest)
+}
+
+/// If `body` starts with a zlib header, RFC 1950.
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+/// Checks the body's length against the server's and the [`PayloadConfig`]'s limits.
+pub(crate) fn check_limit(req: &HttpRequest) -> Result<(), Error> {
+    check_max_size(req, PayloadConfig::from_req(req).max_size)
+}
+
+/// Checks the body's length against the server's limit and `max_size`.
+fn check_max_size(req: &HttpRequest, max_size: u64) -> Result<(), Error> {
+    let length = match &req.payload {
+        Some(payload) if payload.exceeds_limit() => return Err(too_large(payload.limit())),
+        Some(payload) => payload.len(),
+        None => req
+            .content_length()
+            .ok()
+            .flatten()
+            .unwrap_or(req.body.len() as u64),
+    };
+
+    if length > max_size {
+        Err(too_large(max_size))
+    } else {
+        Ok(())
+    }
+}
+
+fn too_large(limit: u64) -> Error {
+    InternalError::PayloadTooLarge(format!(
+        "HTTP request body is larger than the limit of {} bytes",
+        limit
+    ))
+}
+
+pub(crate) fn read_error(err: io::Error) -> Error {
+    match err.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            InternalError::RequestTimeout(format!("HTTP request body could not be read: {}", err))
+        }
+        _ => InternalError::BadRequest(format!("HTTP request body could not be read: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gzip_deflated() {
+        let trailer = [0; 8];
+
+        let plain = [
+            &[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff][..],
+            b"data",
+            &trailer,
+        ]
+        .concat();
+
+        assert_eq!(gzip_deflated(&plain), Some(&b"data"[..]));
+
+        // With `FEXTRA`, `FNAME`, `FCOMMENT`, and `FHCRC`.
+        let flagged = [
+            &[0x1f, 0x8b, 8, 0b11110, 0, 0, 0, 0, 0, 0xff][..],
+            &[2, 0, b'x', b'x'],
+            b"name\0",
+            b"comment\0",
+            &[0, 0],
+            b"data",
+            &trailer,
+        ]
+        .concat();
+
+        assert_eq!(gzip_deflated(&flagged), Some(&b"data"[..]));
+
+        assert_eq!(gzip_deflated(b"not gzip at all, not at all"), None);
+        assert_eq!(gzip_deflated(&plain[..17]), None);
+    }
+
+    #[test]
+    fn test_is_zlib() {
+        assert!(is_zlib(&[0x78, 0x9c, 0]));
+        assert!(is_zlib(&[0x78, 0x01, 0]));
+        assert!(!is_zlib(&[0x78, 0x9d, 0]));
+        assert!(!is_zlib(&[0x4b, 0x49]));
+        assert!(!is_zlib(&[0x78]));
     }
 }
@@ -0,0 +1,140 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    extractor::Extractor,
+    http::{
+        date,
+        headers::{IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE},
+        HttpRequest,
+    },
+    Error,
+};
+
+/// An entity tag from `If-Match` or `If-None-Match`, without its quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityTag {
+    pub weak: bool,
+    pub tag: String,
+}
+
+impl EntityTag {
+    /// Parses a single tag, ex. `"abc"` or `W/"abc"`.
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value),
+        };
+
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+
+        (!tag.contains('"')).then(|| Self {
+            weak,
+            tag: tag.to_string(),
+        })
+    }
+
+    /// Compares with an `ETag` value ignoring weakness, `W/"a"` matches `"a"`.
+    pub fn weak_eq(&self, etag: &str) -> bool {
+        Self::parse(etag).map_or(false, |other| other.tag == self.tag)
+    }
+
+    /// Compares with an `ETag` value, both have to be strong.
+    pub fn strong_eq(&self, etag: &str) -> bool {
+        Self::parse(etag).map_or(false, |other| {
+            !self.weak && !other.weak && other.tag == self.tag
+        })
+    }
+}
+
+/// The tags of an `If-Match` or `If-None-Match` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityTags {
+    /// `*`, any current representation.
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+impl EntityTags {
+    fn parse(value: &str) -> Self {
+        if value.trim() == "*" {
+            Self::Any
+        } else {
+            Self::Tags(value.split(',').filter_map(EntityTag::parse).collect())
+        }
+    }
+}
+
+/// The request's conditional headers, parsed, for handlers that answer them themselves.
+///
+/// Tags that are not quoted and dates that are not an `IMF-fixdate` are ignored, as RFC 7232
+/// says to do with invalid dates.
+#[derive(Debug, Clone, Default)]
+pub struct Conditionals {
+    pub if_match: Option<EntityTags>,
+    pub if_none_match: Option<EntityTags>,
+    pub if_modified_since: Option<SystemTime>,
+    pub if_unmodified_since: Option<SystemTime>,
+}
+
+impl Conditionals {
+    /// Returns if a `GET` or `HEAD` can be answered with `304 Not Modified`, going by
+    /// `If-None-Match`, or `If-Modified-Since` if there is no `If-None-Match` (RFC 7232 section
+    /// 6).
+    pub fn not_modified(&self, etag: Option<&str>, modified: Option<SystemTime>) -> bool {
+        match &self.if_none_match {
+            Some(EntityTags::Any) => etag.is_some(),
+            Some(EntityTags::Tags(tags)) => {
+                etag.map_or(false, |etag| tags.iter().any(|tag| tag.weak_eq(etag)))
+            }
+            None => match (self.if_modified_since, modified) {
+                (Some(since), Some(modified)) => whole_seconds(modified) <= since,
+                _ => false,
+            },
+        }
+    }
+
+    /// Returns if the request has to be answered with `412 Precondition Failed`, going by
+    /// `If-Match`, or `If-Unmodified-Since` if there is no `If-Match` (RFC 7232 section 6).
+    pub fn precondition_failed(&self, etag: Option<&str>, modified: Option<SystemTime>) -> bool {
+        match &self.if_match {
+            Some(EntityTags::Any) => etag.is_none(),
+            Some(EntityTags::Tags(tags)) => {
+                !etag.map_or(false, |etag| tags.iter().any(|tag| tag.strong_eq(etag)))
+            }
+            None => match (self.if_unmodified_since, modified) {
+                (Some(since), Some(modified)) => whole_seconds(modified) > since,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The dates in the headers only have whole seconds.
+fn whole_seconds(time: SystemTime) -> SystemTime {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| UNIX_EPOCH + Duration::from_secs(since.as_secs()))
+        .unwrap_or(time)
+}
+
+impl Extractor for Conditionals {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let headers = &req.header_data.headers;
+
+        Ok(Self {
+            if_match: headers.get(&IF_MATCH).map(|value| EntityTags::parse(value)),
+            if_none_match: headers
+                .get(&IF_NONE_MATCH)
+                .map(|value| EntityTags::parse(value)),
+            if_modified_since: headers
+                .get(&IF_MODIFIED_SINCE)
+                .and_then(|value| date::parse(value)),
+            if_unmodified_since: headers
+                .get(&IF_UNMODIFIED_SINCE)
+                .and_then(|value| date::parse(value)),
+        })
+    }
+}
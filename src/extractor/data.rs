@@ -1,4 +1,4 @@
-use std::{ops::Deref, sync::Arc};
+use std::{any::TypeId, ops::Deref, sync::Arc};
 
 use crate::{error::InternalError, extractor::Extractor, http::HttpRequest, Error};
 
@@ -27,7 +27,11 @@ where
     type Error = Error;
 
     fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
-        if let Some(data) = req.data.get::<Data<T>>() {
+        let data = req
+            .data_raw(TypeId::of::<Data<T>>())
+            .and_then(|data| data.downcast_ref::<Data<T>>());
+
+        if let Some(data) = data {
             Ok(Data {
                 data: data.data.clone(),
             })
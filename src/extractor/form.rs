@@ -0,0 +1,187 @@
+use std::{
+    fmt::Debug,
+    io::Read,
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
+
+use crate::{
+    error::InternalError,
+    extractor::{body, DeserializeError, Extractor},
+    http::{encoding::form, HttpRequest},
+    Error,
+};
+
+/// Checks that the body is a URL encoded form, reading a streamed body into `req.body`, so more
+/// than one field can be extracted.
+fn read_form(req: &mut HttpRequest, key: &'static str) -> Result<(), Error> {
+    if body::is_empty(req) {
+        return Err(InternalError::BadRequest(format!(
+            "HTTP request body is empty, expected a form with the key `{}`",
+            key
+        )));
+    }
+
+    if !req
+        .content_type()
+        .map(|media_type| media_type.is("application/x-www-form-urlencoded"))
+        .unwrap_or(false)
+    {
+        return Err(InternalError::UnsupportedMediaType(
+            "HTTP request body is not a URL encoded form",
+        ));
+    }
+
+    body::check_limit(req)?;
+
+    if let Some(mut payload) = req.payload.take() {
+        req.body.clear();
+
+        payload
+            .read_to_end(&mut req.body)
+            .map_err(body::read_error)?;
+    }
+
+    Ok(())
+}
+
+/// Finds the value of `key` in a URL encoded form body.
+fn get_value(req: &mut HttpRequest, key: &'static str) -> Result<String, Error> {
+    read_form(req, key)?;
+
+    match form::parse(&req.body).find(|(name, _)| name == key) {
+        Some((_, value)) => Ok(value.into_owned()),
+        None => Err(InternalError::BadRequest(format!(
+            "HTTP request form did not contain a value with the key `{}`",
+            key
+        ))),
+    }
+}
+
+/// The value of the field `KEY` of a URL encoded form body.
+///
+/// Fields of nested structures are extracted by their whole name, ex. `Form<"user[name]">` for
+/// `user[name]=Jane`, see [`FormList`] for array fields.
+///
+/// An empty body (ex. `Content-Length: 0`) is responded to with `400 Bad Request`, before the
+/// `Content-Type` is checked.
+pub struct Form<const KEY: &'static str> {
+    value: String,
+}
+
+impl<const KEY: &'static str> const Deref for Form<KEY> {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<const KEY: &'static str> const DerefMut for Form<KEY> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<const KEY: &'static str> Extractor for Form<KEY> {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        get_value(req, KEY).map(|value| Self { value })
+    }
+}
+
+/// Every value of the field `KEY` of a URL encoded form body, in order, whether it was sent as
+/// `KEY[]` (as HTML forms with array fields do) or `KEY` repeated, empty if there are none.
+///
+/// ```
+/// use enrgy::{web, App};
+///
+/// // `tags[]=rust&tags[]=http`
+/// fn tags(tags: web::FormList<"tags">) -> String {
+///     tags.join(", ")
+/// }
+///
+/// App::new().service(web::post("/tags").to(tags));
+/// ```
+pub struct FormList<const KEY: &'static str> {
+    values: Vec<String>,
+}
+
+impl<const KEY: &'static str> const Deref for FormList<KEY> {
+    type Target = Vec<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl<const KEY: &'static str> const DerefMut for FormList<KEY> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+impl<const KEY: &'static str> Extractor for FormList<KEY> {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        read_form(req, KEY)?;
+
+        let values = form::parse(&req.body)
+            .filter(|(name, _)| name == KEY || name.strip_suffix("[]") == Some(KEY))
+            .map(|(_, value)| value.into_owned())
+            .collect();
+
+        Ok(Self { values })
+    }
+}
+
+/// Parses the value of the field `KEY` of a URL encoded form body, failing with a
+/// [`DeserializeError`] if it can not be parsed.
+pub struct ParseForm<const KEY: &'static str, T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    value: T,
+}
+
+impl<const KEY: &'static str, T> const Deref for ParseForm<KEY, T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<const KEY: &'static str, T> const DerefMut for ParseForm<KEY, T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<const KEY: &'static str, T> Extractor for ParseForm<KEY, T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let value = get_value(req, KEY)?;
+
+        match T::from_str(&value) {
+            Ok(value) => Ok(Self { value }),
+            Err(err) => Err(DeserializeError::new("form", KEY, err).into()),
+        }
+    }
+}
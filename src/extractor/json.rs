@@ -0,0 +1,242 @@
+use std::{
+    io::Read,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    error::InternalError,
+    extractor::{body, Data, Extractor},
+    http::{headers::CONTENT_TYPE, HttpRequest, MediaType},
+    Error,
+};
+
+/// Limits and content type rules for the [`Json`] extractor, registered with
+/// [`App::data`](crate::App::data).
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use enrgy::{web, App};
+///
+/// let app = App::new().data(Arc::new(web::JsonConfig::new().limit(1024).strict(true)));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct JsonConfig {
+    limit: usize,
+    strict: bool,
+}
+
+impl JsonConfig {
+    pub const fn new() -> Self {
+        Self {
+            limit: 256 * 1024,
+            strict: false,
+        }
+    }
+
+    /// Sets the largest body that is accepted, defaults to 256 KiB.
+    pub const fn limit(mut self, bytes: usize) -> Self {
+        self.limit = bytes;
+
+        self
+    }
+
+    /// Sets whether the `Content-Type` has to be exactly `application/json`, otherwise any
+    /// `application/*+json` type is accepted, defaults to `false`.
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+
+        self
+    }
+
+    fn accepts(&self, media_type: &MediaType) -> bool {
+        media_type.is("application/json")
+            || (!self.strict
+                && media_type.type_() == "application"
+                && media_type.suffix() == Some("json"))
+    }
+}
+
+impl const Default for JsonConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request body deserialized from JSON.
+///
+/// An empty body (ex. `Content-Length: 0`) is responded to with `400 Bad Request`, before the
+/// `Content-Type` is checked, take an `Option<Json<T>>` to get `None` for it instead.
+pub struct Json<T> {
+    value: T,
+}
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> const Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> const DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> Extractor for Json<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        serde_json::from_slice(&read(req)?)
+            .map(|value| Json { value })
+            .map_err(invalid)
+    }
+}
+
+/// `None` if the request has no body, see [`Json`].
+impl<T> Extractor for Option<Json<T>>
+where
+    T: DeserializeOwned,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        if body::is_empty(req) {
+            return Ok(None);
+        }
+
+        Json::extract(req).map(Some)
+    }
+}
+
+/// A JSON request body that is deserialized when asked for, into types that borrow from it
+/// (ex. with `&str` fields) instead of allocating a copy of every string.
+///
+/// Extractors can not borrow from the request, so the body is moved into the `JsonRef`, and
+/// values borrow from that. It follows the same [`JsonConfig`] as [`Json`].
+///
+/// ```
+/// use enrgy::{http::HttpResponse, web};
+///
+/// fn index(body: web::JsonRef) -> HttpResponse {
+///     match body.get::<Vec<&str>>() {
+///         Ok(names) => HttpResponse::text(names.join(", ")),
+///         Err(err) => HttpResponse::bad_request().body(err.to_string()),
+///     }
+/// }
+/// ```
+pub struct JsonRef {
+    bytes: Vec<u8>,
+}
+
+impl JsonRef {
+    pub fn get<'r, T>(&'r self) -> Result<T, Error>
+    where
+        T: Deserialize<'r>,
+    {
+        serde_json::from_slice(&self.bytes).map_err(invalid)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Extractor for JsonRef {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        Ok(JsonRef { bytes: read(req)? })
+    }
+}
+
+/// Checks the request's `Content-Type` and reads its body, within the [`JsonConfig`]'s limit.
+fn read(req: &mut HttpRequest) -> Result<Vec<u8>, Error> {
+    let config = req
+        .data
+        .get::<Data<JsonConfig>>()
+        .map(|data| *data.data)
+        .unwrap_or_default();
+
+    if body::is_empty(req) {
+        return Err(InternalError::BadRequest(
+            "HTTP request body is empty, expected JSON",
+        ));
+    }
+
+    let media_type = match req.content_type() {
+        Some(media_type) => media_type,
+        None if req.header_data.headers.contains(&CONTENT_TYPE) => {
+            return Err(InternalError::BadRequest(
+                "HTTP request `Content-Type` is malformed",
+            ))
+        }
+        None => {
+            return Err(InternalError::UnsupportedMediaType(
+                "HTTP request is missing a `Content-Type`",
+            ))
+        }
+    };
+
+    if !config.accepts(media_type) {
+        return Err(InternalError::UnsupportedMediaType(
+            "HTTP request `Content-Type` is not JSON",
+        ));
+    }
+
+    let too_large = || {
+        InternalError::PayloadTooLarge(format!(
+            "HTTP request body is larger than the JSON limit of {} bytes",
+            config.limit
+        ))
+    };
+
+    match req.content_length() {
+        Ok(Some(length)) if length > config.limit as u64 => return Err(too_large()),
+        Ok(_) => {}
+        Err(_) => {
+            return Err(InternalError::BadRequest(
+                "HTTP request `Content-Length` is malformed",
+            ))
+        }
+    }
+
+    body::check_limit(req)?;
+
+    let bytes = match req.payload.take() {
+        Some(payload) => {
+            let mut bytes = Vec::new();
+
+            payload
+                .take(config.limit as u64 + 1)
+                .read_to_end(&mut bytes)
+                .map_err(body::read_error)?;
+
+            bytes
+        }
+        None => std::mem::take(&mut req.body),
+    };
+
+    if bytes.len() > config.limit {
+        Err(too_large())
+    } else {
+        body::decode(req, bytes)
+    }
+}
+
+fn invalid(err: serde_json::Error) -> Error {
+    InternalError::BadRequest(format!("HTTP request body is not valid JSON: {}", err))
+}
@@ -1,18 +1,41 @@
+pub mod accept;
 pub mod body;
+pub mod conditionals;
 pub mod data;
+pub mod form;
 pub mod header;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod multipart;
 pub mod param;
 pub mod query;
+pub mod session;
+pub mod signed;
 
 pub use self::{
-    body::Body,
+    accept::Accept,
+    body::{Body, BodyStream, Bytes, PayloadConfig, Text},
+    conditionals::{Conditionals, EntityTag, EntityTags},
     data::Data,
+    form::{Form, FormList, ParseForm},
     header::{Header, OptionalHeader, ParseHeader},
-    param::{OptionalParam, Param, ParseParam},
-    query::{OptionalQuery, ParseQuery, Query, RawQuery},
+    multipart::{BufferedField, Fields, Multipart, MultipartField, SavedField},
+    param::{OptionalParam, Param, ParseParam, Path, SecurePath, SecurePathConfig},
+    query::{DefaultQuery, OptionalQuery, ParseQuery, Query, QueryConfig, RawQuery},
+    session::{Session, SessionData},
+    signed::{Signed, SignedConfig},
 };
 
-use crate::{http::HttpRequest, Error};
+#[cfg(feature = "json")]
+pub use self::json::{Json, JsonConfig, JsonRef};
+
+use std::fmt;
+
+use crate::{
+    error::ResponseError,
+    http::{HttpRequest, StatusCode},
+    Error,
+};
 
 pub trait Extractor: Sized {
     type Error;
@@ -20,6 +43,78 @@ pub trait Extractor: Sized {
     fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error>;
 }
 
+/// A query or form value that could not be parsed into the type the handler asked for,
+/// responds with `400 Bad Request`.
+///
+/// Handlers can take a `Result<E, DeserializeError>` (where `E` is ex. a
+/// [`ParseQuery`] or [`ParseForm`]) to respond to it themselves.
+///
+/// The field is the extractor's own key, as values are extracted one key at a time instead of
+/// being deserialized with serde there is no error path to take it from.
+#[derive(Debug, Clone)]
+pub struct DeserializeError {
+    source: &'static str,
+    field: String,
+    reason: String,
+}
+
+impl DeserializeError {
+    pub(crate) fn new<R>(source: &'static str, field: &str, reason: R) -> Self
+    where
+        R: fmt::Debug,
+    {
+        Self {
+            source,
+            field: field.to_string(),
+            reason: format!("{:?}", reason),
+        }
+    }
+
+    /// The key of the value that could not be parsed.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Why the value could not be parsed.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HTTP request {} with key `{}` could not be parsed: {}",
+            self.source, self.field, self.reason
+        )
+    }
+}
+
+impl ResponseError for DeserializeError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Any other error is still responded to as usual.
+impl<T> Extractor for Result<T, DeserializeError>
+where
+    T: Extractor<Error = Error>,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        match T::extract(req) {
+            Ok(value) => Ok(Ok(value)),
+            Err(err) => match err.downcast_ref::<DeserializeError>() {
+                Some(err) => Ok(Err(err.clone())),
+                None => Err(err),
+            },
+        }
+    }
+}
+
 impl Extractor for () {
     type Error = Error;
 
@@ -0,0 +1,838 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    error::InternalError,
+    extractor::{body, Extractor},
+    http::{headers::CONTENT_TYPE, HttpRequest, MediaType},
+    Error,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+const MAX_HEADER_LINE: usize = 8 * 1024;
+const DEFAULT_FIELD_LIMIT: u64 = 10 * 1024 * 1024;
+
+static UPLOAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Preamble,
+    Delimiter,
+    Body,
+    End,
+}
+
+struct Parser<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    eof: bool,
+    /// `\r\n--{boundary}`, the preamble is parsed as if it started with `\r\n`.
+    delimiter: Vec<u8>,
+    state: State,
+}
+
+impl<R> Parser<R>
+where
+    R: Read,
+{
+    fn new(reader: R, boundary: &str) -> Self {
+        let mut delimiter = Vec::with_capacity(boundary.len() + 4);
+
+        delimiter.extend_from_slice(b"\r\n--");
+        delimiter.extend_from_slice(boundary.as_bytes());
+
+        let mut buffer = Vec::with_capacity(CHUNK_SIZE + delimiter.len());
+
+        buffer.extend_from_slice(b"\r\n");
+
+        Self {
+            reader,
+            buffer,
+            position: 0,
+            eof: false,
+            delimiter,
+            state: State::Preamble,
+        }
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.buffer[self.position..]
+    }
+
+    /// Reads from the source until at least `min` bytes are buffered or the source is exhausted.
+    fn fill(&mut self, min: usize) -> io::Result<()> {
+        while self.available().len() < min && !self.eof {
+            if self.position > 0 {
+                self.buffer.drain(..self.position);
+                self.position = 0;
+            }
+
+            let len = self.buffer.len();
+
+            self.buffer.resize(len + CHUNK_SIZE, 0);
+
+            let read = match self.reader.read(&mut self.buffer[len..]) {
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                    self.buffer.truncate(len);
+
+                    continue;
+                }
+                Err(err) => {
+                    self.buffer.truncate(len);
+
+                    return Err(err);
+                }
+            };
+
+            self.buffer.truncate(len + read);
+
+            if read == 0 {
+                self.eof = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_body(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.state != State::Body || out.is_empty() {
+            return Ok(0);
+        }
+
+        self.fill(self.delimiter.len() + out.len().min(CHUNK_SIZE))?;
+
+        let available = self.available();
+
+        let len = match find(available, &self.delimiter) {
+            Some(0) => {
+                self.position += self.delimiter.len();
+                self.state = State::Delimiter;
+
+                return Ok(0);
+            }
+            Some(index) => index.min(out.len()),
+            None if self.eof => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "multipart body ended before the closing boundary",
+                ))
+            }
+            // Hold back enough bytes for a delimiter that may be split across reads.
+            None => available
+                .len()
+                .saturating_sub(self.delimiter.len() - 1)
+                .min(out.len()),
+        };
+
+        out[..len].copy_from_slice(&available[..len]);
+
+        self.position += len;
+
+        Ok(len)
+    }
+
+    fn read_line(&mut self) -> Result<String, Error> {
+        loop {
+            if let Some(index) = find(self.available(), b"\r\n") {
+                let line = String::from_utf8_lossy(&self.available()[..index]).into_owned();
+
+                self.position += index + 2;
+
+                return Ok(line);
+            }
+
+            if self.available().len() > MAX_HEADER_LINE {
+                return Err(InternalError::RequestHeaderFieldsTooLarge(
+                    "multipart field header line is too long",
+                ));
+            }
+
+            if self.eof {
+                return Err(InternalError::BadRequest(
+                    "multipart field headers ended unexpectedly",
+                ));
+            }
+
+            let min = self.available().len() + 1;
+
+            self.fill(min).map_err(InternalError::BadRequest)?;
+        }
+    }
+
+    fn next_field(&mut self) -> Result<Option<FieldHeaders>, Error> {
+        loop {
+            match self.state {
+                State::End => return Ok(None),
+                State::Preamble => {
+                    self.fill(self.delimiter.len())
+                        .map_err(InternalError::BadRequest)?;
+
+                    match find(self.available(), &self.delimiter) {
+                        Some(index) => {
+                            self.position += index + self.delimiter.len();
+                            self.state = State::Delimiter;
+                        }
+                        None if self.eof => {
+                            return Err(InternalError::BadRequest(
+                                "multipart body does not contain the boundary",
+                            ))
+                        }
+                        None => {
+                            let keep = self.delimiter.len() - 1;
+
+                            self.position += self.available().len().saturating_sub(keep);
+
+                            let min = keep + 1;
+
+                            self.fill(min).map_err(InternalError::BadRequest)?;
+                        }
+                    }
+                }
+                State::Body => {
+                    // Skip whatever is left of the previous field.
+                    io::copy(&mut BodyReader(self), &mut io::sink())
+                        .map_err(InternalError::BadRequest)?;
+                }
+                State::Delimiter => {
+                    self.fill(2).map_err(InternalError::BadRequest)?;
+
+                    if self.available().starts_with(b"--") {
+                        self.state = State::End;
+
+                        return Ok(None);
+                    }
+
+                    if !self.available().starts_with(b"\r\n") {
+                        return Err(InternalError::BadRequest(
+                            "multipart boundary is not followed by a line break",
+                        ));
+                    }
+
+                    self.position += 2;
+
+                    let headers = self.read_headers()?;
+
+                    self.state = State::Body;
+
+                    return Ok(Some(headers));
+                }
+            }
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<FieldHeaders, Error> {
+        let mut headers = FieldHeaders {
+            name: String::new(),
+            filename: None,
+            content_type: None,
+        };
+
+        loop {
+            let line = self.read_line()?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            let (key, value) = match line.find(':') {
+                Some(index) => (line[..index].trim(), line[(index + 1)..].trim()),
+                None => continue,
+            };
+
+            if key.eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').skip(1) {
+                    let (key, value) = match param.find('=') {
+                        Some(index) => (param[..index].trim(), param[(index + 1)..].trim()),
+                        None => continue,
+                    };
+
+                    let value = value.trim_matches('"').to_string();
+
+                    if key.eq_ignore_ascii_case("name") {
+                        headers.name = value;
+                    } else if key.eq_ignore_ascii_case("filename") {
+                        headers.filename = Some(value);
+                    }
+                }
+            } else if key.eq_ignore_ascii_case("content-type") {
+                headers.content_type = Some(value.to_string());
+            }
+        }
+
+        Ok(headers)
+    }
+}
+
+struct BodyReader<'p, R>(&'p mut Parser<R>);
+
+impl<'p, R> Read for BodyReader<'p, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_body(buf)
+    }
+}
+
+struct FieldHeaders {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+}
+
+/// A `multipart/form-data` request body.
+///
+/// Fields are parsed one at a time, with each field's content being read through a
+/// small fixed size buffer, allowing file fields to be written to disk as they are read.
+pub struct Multipart {
+    parser: Parser<Box<dyn Read + Send + Sync>>,
+    field_limit: u64,
+}
+
+impl Multipart {
+    pub(crate) fn new<R>(reader: R, boundary: &str) -> Self
+    where
+        R: Read + Send + Sync + 'static,
+    {
+        Self {
+            parser: Parser::new(box reader, boundary),
+            field_limit: DEFAULT_FIELD_LIMIT,
+        }
+    }
+
+    /// Sets the maximum amount of bytes a single field can contain, defaults to 10 MiB.
+    pub fn field_limit(mut self, limit: u64) -> Self {
+        self.field_limit = limit;
+
+        self
+    }
+
+    /// Returns the next field in the body, skipping any unread content of the previous field.
+    pub fn next_field(&mut self) -> Result<Option<MultipartField<'_>>, Error> {
+        match self.parser.next_field()? {
+            Some(headers) => Ok(Some(MultipartField {
+                parser: &mut self.parser,
+                headers,
+                limit: self.field_limit,
+                read: 0,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterates over the fields, each one is parsed and read into memory (up to the
+    /// [field limit](Multipart::field_limit)) only once it is asked for, so the rest of the body
+    /// is left unread if the iteration stops early.
+    ///
+    /// Use [`Multipart::next_field`] to stream a field's content instead, ex. to disk.
+    pub fn fields(&mut self) -> Fields<'_> {
+        Fields {
+            multipart: self,
+            done: false,
+        }
+    }
+}
+
+impl Extractor for Multipart {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let media_type = req
+            .header_data
+            .headers
+            .get(&CONTENT_TYPE)
+            .ok_or_else(|| {
+                InternalError::UnsupportedMediaType("HTTP request is missing a `Content-Type`")
+            })?
+            .parse::<MediaType>()
+            .map_err(|_| InternalError::BadRequest("HTTP request `Content-Type` is malformed"))?;
+
+        if !media_type.is("multipart/form-data") {
+            return Err(InternalError::UnsupportedMediaType(
+                "HTTP request `Content-Type` is not `multipart/form-data`",
+            ));
+        }
+
+        let boundary = media_type
+            .param("boundary")
+            .filter(|boundary| !boundary.is_empty())
+            .ok_or_else(|| {
+                InternalError::BadRequest("HTTP request `Content-Type` is missing a boundary")
+            })?;
+
+        body::check_limit(req)?;
+
+        match req.payload.take() {
+            Some(payload) => Ok(Multipart::new(payload, boundary)),
+            None => Ok(Multipart::new(
+                Cursor::new(std::mem::take(&mut req.body)),
+                boundary,
+            )),
+        }
+    }
+}
+
+/// A single field of a [`Multipart`] body, reading from it yields the field's content.
+pub struct MultipartField<'m> {
+    parser: &'m mut Parser<Box<dyn Read + Send + Sync>>,
+    headers: FieldHeaders,
+    limit: u64,
+    read: u64,
+}
+
+impl<'m> MultipartField<'m> {
+    pub fn name(&self) -> &str {
+        &self.headers.name
+    }
+
+    pub fn filename(&self) -> Option<&str> {
+        self.headers.filename.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.content_type.as_deref()
+    }
+
+    /// Copies the field's content into `writer`, returning the amount of bytes written.
+    pub fn save_to<W>(&mut self, writer: &mut W) -> io::Result<u64>
+    where
+        W: Write,
+    {
+        io::copy(self, writer)
+    }
+
+    /// Writes the field's content to a new uniquely named file in `dir`.
+    pub fn save_in<P>(&mut self, dir: P) -> io::Result<SavedField>
+    where
+        P: AsRef<Path>,
+    {
+        // Never opens an existing file (or follows a link someone left under the same name),
+        // the next name is tried instead.
+        let (path, mut file) = loop {
+            let path = dir.as_ref().join(format!(
+                "enrgy-upload-{}-{}",
+                process::id(),
+                UPLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        match self.save_to(&mut file) {
+            Ok(size) => Ok(SavedField { path, size }),
+            Err(err) => {
+                drop(file);
+
+                let _ = std::fs::remove_file(&path);
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Writes the field's content to a new file in the system's temporary directory.
+    pub fn save(&mut self) -> io::Result<SavedField> {
+        self.save_in(std::env::temp_dir())
+    }
+}
+
+impl<'m> Read for MultipartField<'m> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.parser.read_body(buf)?;
+
+        self.read += read as u64;
+
+        if self.read > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("multipart field `{}` exceeds the size limit", self.name()),
+            ));
+        }
+
+        Ok(read)
+    }
+}
+
+/// An iterator over the fields of a [`Multipart`] body, see [`Multipart::fields`].
+///
+/// Ends after the first error.
+pub struct Fields<'m> {
+    multipart: &'m mut Multipart,
+    done: bool,
+}
+
+impl<'m> Fields<'m> {
+    fn read_field(&mut self) -> Result<Option<BufferedField>, Error> {
+        let mut field = match self.multipart.next_field()? {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+
+        let mut data = Vec::new();
+
+        if let Err(err) = field.read_to_end(&mut data) {
+            return Err(if field.read > field.limit {
+                InternalError::PayloadTooLarge(format!(
+                    "multipart field `{}` exceeds the size limit of {} bytes",
+                    field.name(),
+                    field.limit
+                ))
+            } else {
+                body::read_error(err)
+            });
+        }
+
+        Ok(Some(BufferedField {
+            name: std::mem::take(&mut field.headers.name),
+            filename: field.headers.filename.take(),
+            content_type: field.headers.content_type.take(),
+            data,
+        }))
+    }
+}
+
+impl<'m> Iterator for Fields<'m> {
+    type Item = Result<BufferedField, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let field = self.read_field().transpose();
+
+        self.done = !matches!(field, Some(Ok(_)));
+
+        field
+    }
+}
+
+/// A field read into memory by [`Fields`].
+#[derive(Debug)]
+pub struct BufferedField {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// A field that has been written to disk.
+#[derive(Debug)]
+pub struct SavedField {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Produces a multipart body with a single large file field without holding it in memory.
+    struct Upload {
+        head: Cursor<Vec<u8>>,
+        remaining: usize,
+        tail: Cursor<Vec<u8>>,
+    }
+
+    impl Read for Upload {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.head.read(buf)?;
+
+            if read != 0 {
+                return Ok(read);
+            }
+
+            if self.remaining != 0 {
+                let len = self.remaining.min(buf.len());
+
+                buf[..len].iter_mut().for_each(|byte| *byte = b'a');
+
+                self.remaining -= len;
+
+                return Ok(len);
+            }
+
+            self.tail.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            Hello\r\n\
+            --abc\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            World\r\n\
+            --abc--\r\n";
+
+        let mut multipart = Multipart::new(Cursor::new(body.as_bytes().to_vec()), "abc");
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+        let mut content = String::new();
+        field.read_to_string(&mut content).unwrap();
+        assert_eq!(field.name(), "title");
+        assert_eq!(field.filename(), None);
+        assert_eq!(content, "Hello");
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+        let mut content = String::new();
+        field.read_to_string(&mut content).unwrap();
+        assert_eq!(field.name(), "file");
+        assert_eq!(field.filename(), Some("a.txt"));
+        assert_eq!(field.content_type(), Some("text/plain"));
+        assert_eq!(content, "World");
+
+        assert!(multipart.next_field().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_large_field_to_disk() {
+        const SIZE: usize = 4 * 1024 * 1024;
+
+        let upload = Upload {
+            head: Cursor::new(
+                b"--abc\r\nContent-Disposition: form-data; name=\"file\"; filename=\"big.bin\"\r\n\r\n"
+                    .to_vec(),
+            ),
+            remaining: SIZE,
+            tail: Cursor::new(b"\r\n--abc--\r\n".to_vec()),
+        };
+
+        let mut multipart = Multipart::new(upload, "abc");
+
+        let saved = multipart.next_field().unwrap().unwrap().save().unwrap();
+
+        assert_eq!(saved.size, SIZE as u64);
+        assert_eq!(std::fs::metadata(&saved.path).unwrap().len(), SIZE as u64);
+        assert!(multipart.parser.buffer.capacity() < 64 * 1024);
+        assert!(multipart.next_field().unwrap().is_none());
+
+        std::fs::remove_file(&saved.path).unwrap();
+    }
+
+    #[test]
+    fn test_field_limit() {
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"file\"\r\n\
+            \r\n\
+            0123456789\r\n\
+            --abc--\r\n";
+
+        let mut multipart =
+            Multipart::new(Cursor::new(body.as_bytes().to_vec()), "abc").field_limit(4);
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+
+        assert!(field.save_to(&mut Vec::new()).is_err());
+    }
+
+    /// Interrupts every other read, like a signal arriving mid-upload.
+    struct Interrupting {
+        inner: Cursor<Vec<u8>>,
+        interrupt: bool,
+    }
+
+    impl Read for Interrupting {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+
+            let len = buf.len().min(8);
+
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_interrupted_reads() {
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            Hello World\r\n\
+            --abc--\r\n";
+
+        let mut multipart = Multipart::new(
+            Interrupting {
+                inner: Cursor::new(body.as_bytes().to_vec()),
+                interrupt: false,
+            },
+            "abc",
+        );
+
+        let mut field = multipart.next_field().unwrap().unwrap();
+        let mut content = String::new();
+        field.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "Hello World");
+
+        assert!(multipart.next_field().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_save_in_existing_file() {
+        let dir = std::env::temp_dir().join(format!("enrgy-save-in-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Taken by the names the next uploads would get.
+        let next = UPLOAD_COUNTER.load(Ordering::Relaxed);
+        let existing = (next..next + 8)
+            .map(|counter| {
+                let path = dir.join(format!("enrgy-upload-{}-{}", process::id(), counter));
+                std::fs::write(&path, "existing").unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"file\"\r\n\
+            \r\n\
+            Hello\r\n\
+            --abc--\r\n";
+
+        let mut multipart = Multipart::new(Cursor::new(body.as_bytes().to_vec()), "abc");
+
+        let saved = multipart
+            .next_field()
+            .unwrap()
+            .unwrap()
+            .save_in(&dir)
+            .unwrap();
+
+        assert!(!existing.contains(&saved.path));
+        assert_eq!(std::fs::read_to_string(&saved.path).unwrap(), "Hello");
+
+        for path in &existing {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), "existing");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Counts how many bytes have been read from the body.
+    struct Counted {
+        inner: Cursor<Vec<u8>>,
+        read: Arc<AtomicUsize>,
+    }
+
+    impl Read for Counted {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+
+            self.read.fetch_add(read, Ordering::SeqCst);
+
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn test_fields_iter() {
+        let large = "x".repeat(64 * 1024);
+
+        let body = format!(
+            "--abc\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            hello\r\n\
+            --abc\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            {}\r\n\
+            --abc\r\n\
+            Content-Disposition: form-data; name=\"last\"\r\n\
+            \r\n\
+            bye\r\n\
+            --abc--\r\n",
+            large
+        );
+
+        let read = Arc::new(AtomicUsize::new(0));
+
+        let counted = |body: &str| Counted {
+            inner: Cursor::new(body.as_bytes().to_vec()),
+            read: read.clone(),
+        };
+
+        let mut multipart = Multipart::new(counted(&body), "abc");
+        let mut fields = multipart.fields();
+
+        let field = fields.next().unwrap().unwrap();
+
+        assert_eq!(field.name, "title");
+        assert_eq!(field.data, b"hello");
+        assert!(read.load(Ordering::SeqCst) < large.len());
+
+        let field = fields.next().unwrap().unwrap();
+
+        assert_eq!(field.name, "file");
+        assert_eq!(field.filename.as_deref(), Some("a.txt"));
+        assert_eq!(field.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(field.data, large.as_bytes());
+
+        let field = fields.next().unwrap().unwrap();
+
+        assert_eq!(field.name, "last");
+        assert_eq!(field.data, b"bye");
+        assert!(fields.next().is_none());
+
+        // Stopping after the first field leaves the rest of the body unread.
+        read.store(0, Ordering::SeqCst);
+
+        let mut multipart = Multipart::new(counted(&body), "abc");
+
+        let names = multipart
+            .fields()
+            .take(1)
+            .map(|field| field.unwrap().name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["title"]);
+        assert!(read.load(Ordering::SeqCst) < large.len());
+    }
+
+    #[test]
+    fn test_fields_limit() {
+        let body = "--abc\r\n\
+            Content-Disposition: form-data; name=\"file\"\r\n\
+            \r\n\
+            0123456789\r\n\
+            --abc--\r\n";
+
+        let mut multipart =
+            Multipart::new(Cursor::new(body.as_bytes().to_vec()), "abc").field_limit(4);
+        let mut fields = multipart.fields();
+
+        let err = fields.next().unwrap().unwrap_err();
+
+        assert_eq!(
+            err.error_response().status,
+            crate::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(fields.next().is_none());
+    }
+}
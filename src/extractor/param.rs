@@ -1,20 +1,30 @@
 use std::{
     fmt::Debug,
+    io,
     ops::{Deref, DerefMut},
+    path::{Component, PathBuf},
     str::FromStr,
+    sync::Arc,
 };
 
-use crate::{error::InternalError, extractor::Extractor, http::HttpRequest, Error};
+use crate::{
+    error::InternalError,
+    extractor::{Data, Extractor},
+    http::HttpRequest,
+    Error,
+};
 
 fn get_value<'req>(req: &'req HttpRequest, key: &'static str) -> Option<&'req String> {
     req.params.get(key)
 }
 
+/// A parameter the route does not have is a mistake in the app rather than the request, so it
+/// responds with `500 Internal Server Error`, while one that does not parse is the client's.
 fn get_value_err<'req>(req: &'req HttpRequest, key: &'static str) -> Result<&'req String, Error> {
     match get_value(req, key) {
         Some(v) => Ok(v),
-        None => Err(InternalError::BadRequest(format!(
-            "HTTP request URL parameters did not contain a value with the key `{}`",
+        None => Err(InternalError::InternalServerError(format!(
+            "route has no path parameter with the key `{}`",
             key
         ))),
     }
@@ -129,3 +139,169 @@ where
         }
     }
 }
+
+/// The route's path parameter, parsed, for routes with exactly one (ex. `/users/:id`).
+///
+/// Using it on a route without exactly one parameter is a mistake in the app rather than the
+/// request, so it responds with `500 Internal Server Error`.
+pub struct Path<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    value: T,
+}
+
+impl<T> const Deref for Path<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> const DerefMut for Path<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> Extractor for Path<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let mut params = req.params.iter();
+
+        let (key, value) = match (params.next(), params.next()) {
+            (Some(param), None) => param,
+            (None, _) => {
+                return Err(InternalError::InternalServerError(
+                    "route has no path parameters to extract",
+                ))
+            }
+            (Some(_), Some(_)) => {
+                return Err(InternalError::InternalServerError(
+                    "route has more than one path parameter, use `Param` to extract them",
+                ))
+            }
+        };
+
+        match T::from_str(value) {
+            Ok(value) => Ok(Self { value }),
+            Err(err) => Err(InternalError::BadRequest(format!(
+                "HTTP request URL parameter with key `{}` could not be parsed: {:?}",
+                key, err
+            ))),
+        }
+    }
+}
+
+/// The base directory for [`SecurePath`], registered with [`App::data`](crate::App::data).
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use enrgy::{web, App};
+///
+/// let app = App::new().data(Arc::new(web::SecurePathConfig::new("./uploads")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecurePathConfig {
+    base: PathBuf,
+}
+
+impl SecurePathConfig {
+    pub fn new<P: Into<PathBuf>>(base: P) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+/// The path parameter `KEY` resolved to an existing file or directory inside of the
+/// [`SecurePathConfig`]'s base directory, so handlers can open it as is.
+///
+/// Paths with `..` components, or that lead out of the base through a symlink, are rejected
+/// with `403 Forbidden`, paths that do not exist with `404 Not Found`.
+pub struct SecurePath<const KEY: &'static str> {
+    value: PathBuf,
+}
+
+impl<const KEY: &'static str> SecurePath<KEY> {
+    pub fn into_inner(self) -> PathBuf {
+        self.value
+    }
+}
+
+impl<const KEY: &'static str> const Deref for SecurePath<KEY> {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<const KEY: &'static str> Extractor for SecurePath<KEY> {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let config = match req.data.get::<Data<SecurePathConfig>>() {
+            Some(config) => Arc::clone(&config.data),
+            None => {
+                return Err(InternalError::InternalServerError(
+                    "`SecurePath` was used without a `SecurePathConfig`",
+                ))
+            }
+        };
+
+        let value = get_value_err(&*req, KEY)?;
+
+        let mut path = config.base.clone();
+
+        for component in std::path::Path::new(value.trim_start_matches('/')).components() {
+            match component {
+                Component::Normal(part) => path.push(part),
+                Component::CurDir => {}
+                _ => {
+                    return Err(InternalError::Forbidden(
+                        "HTTP request path leads outside of the base directory",
+                    ))
+                }
+            }
+        }
+
+        let base = config.base.canonicalize().map_err(|err| {
+            InternalError::InternalServerError(format!(
+                "`SecurePath` base directory could not be resolved: {}",
+                err
+            ))
+        })?;
+
+        // Symlinks are only followed here, a path that escapes through one is still caught.
+        let value = match path.canonicalize() {
+            Ok(value) => value,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(InternalError::NotFound("HTTP request path does not exist"))
+            }
+            Err(err) => return Err(InternalError::InternalServerError(err.to_string())),
+        };
+
+        if value.starts_with(&base) {
+            Ok(Self { value })
+        } else {
+            Err(InternalError::Forbidden(
+                "HTTP request path leads outside of the base directory",
+            ))
+        }
+    }
+}
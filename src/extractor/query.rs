@@ -4,14 +4,98 @@ use std::{
     str::FromStr,
 };
 
-use crate::{error::InternalError, extractor::Extractor, http::HttpRequest, Error};
+use crate::{
+    error::InternalError,
+    extractor::{DeserializeError, Extractor},
+    http::HttpRequest,
+    web::Data,
+    Error,
+};
+
+/// How the query extractors treat malformed query strings.
+///
+/// By default they are lenient like browsers, a bare key (`?debug`) has an empty value and
+/// pairs without a key (`?=1`) are skipped. In strict mode either, or a `%` that is not
+/// followed by two hexadecimal digits, is rejected with `400 Bad Request`.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use enrgy::{web, App};
+///
+/// let app = App::new().data(Arc::new(web::QueryConfig::new().strict(true)));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct QueryConfig {
+    strict: bool,
+}
+
+impl QueryConfig {
+    pub const fn new() -> Self {
+        Self { strict: false }
+    }
 
-fn get_value<'req>(req: &'req HttpRequest, key: &'static str) -> Option<&'req String> {
-    req.header_data.query_params.get(key)
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> Self {
+        req.data
+            .get::<Data<QueryConfig>>()
+            .map(|data| *data.data)
+            .unwrap_or_default()
+    }
+}
+
+impl const Default for QueryConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the first pair of `query` that strict mode rejects, see [`QueryConfig`].
+fn malformed(query: &str) -> Option<&str> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .find(|pair| {
+            let bytes = pair.as_bytes();
+
+            let invalid_escape = bytes.iter().enumerate().any(|(i, byte)| {
+                *byte == b'%'
+                    && !matches!(
+                        bytes.get((i + 1)..(i + 3)),
+                        Some([a, b]) if a.is_ascii_hexdigit() && b.is_ascii_hexdigit()
+                    )
+            });
+
+            match pair.split_once('=') {
+                Some((key, _)) => key.is_empty() || invalid_escape,
+                None => true,
+            }
+        })
+}
+
+fn get_value<'req>(
+    req: &'req HttpRequest,
+    key: &'static str,
+) -> Result<Option<&'req String>, Error> {
+    if QueryConfig::from_req(req).strict {
+        if let Some(pair) = malformed(req.query_string()) {
+            return Err(InternalError::BadRequest(format!(
+                "HTTP request URL query contains a malformed pair `{}`",
+                pair
+            )));
+        }
+    }
+
+    Ok(req.header_data.query_params.get(key))
 }
 
 fn get_value_err<'req>(req: &'req HttpRequest, key: &'static str) -> Result<&'req String, Error> {
-    match get_value(req, key) {
+    match get_value(req, key)? {
         Some(v) => Ok(v),
         None => Err(InternalError::BadRequest(format!(
             "HTTP request URL query did not contain a value with the key `{}`",
@@ -74,7 +158,7 @@ impl<const KEY: &'static str> Extractor for OptionalQuery<KEY> {
 
     fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
         Ok(Self {
-            value: get_value(&*req, KEY).cloned(),
+            value: get_value(&*req, KEY)?.cloned(),
         })
     }
 }
@@ -120,16 +204,78 @@ where
         match get_value_err(&*req, KEY) {
             Ok(value) => match T::from_str(value) {
                 Ok(value) => Ok(Self { value }),
-                Err(err) => Err(InternalError::BadRequest(format!(
-                    "HTTP request URL query with key `{}` could not be parsed: {:?}",
-                    KEY, err
-                ))),
+                Err(err) => Err(DeserializeError::new("URL query", KEY, err).into()),
             },
             Err(err) => Err(err),
         }
     }
 }
 
+/// Parses the query value with the key `KEY`, falling back to parsing `DEFAULT` when it is absent
+/// (ex. `DefaultQuery<"page", u32, "1">`).
+///
+/// Query values are extracted one key at a time rather than deserialized into a struct (serde is
+/// only a dependency of the `json` feature), so this takes the place of a `#[serde(default)]`
+/// field.
+pub struct DefaultQuery<const KEY: &'static str, T, const DEFAULT: &'static str>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    value: T,
+}
+
+impl<const KEY: &'static str, T, const DEFAULT: &'static str> const Deref
+    for DefaultQuery<KEY, T, DEFAULT>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<const KEY: &'static str, T, const DEFAULT: &'static str> const DerefMut
+    for DefaultQuery<KEY, T, DEFAULT>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<const KEY: &'static str, T, const DEFAULT: &'static str> Extractor
+    for DefaultQuery<KEY, T, DEFAULT>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        match get_value(&*req, KEY)? {
+            Some(value) => match T::from_str(value) {
+                Ok(value) => Ok(Self { value }),
+                Err(err) => Err(DeserializeError::new("URL query", KEY, err).into()),
+            },
+            None => match T::from_str(DEFAULT) {
+                Ok(value) => Ok(Self { value }),
+                Err(err) => Err(InternalError::InternalServerError(format!(
+                    "default value `{}` for URL query key `{}` could not be parsed: {:?}",
+                    DEFAULT, KEY, err
+                ))),
+            },
+        }
+    }
+}
+
+/// The query string as it was sent, without the `?` and still percent-encoded (see
+/// [`HttpRequest::query_string`]), empty if there is none.
 pub struct RawQuery {
     value: String,
 }
@@ -153,7 +299,7 @@ impl Extractor for RawQuery {
 
     fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
         Ok(Self {
-            value: req.header_data.query.clone(),
+            value: req.query_string().to_string(),
         })
     }
 }
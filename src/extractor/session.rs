@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{error::InternalError, extractor::Extractor, http::HttpRequest, Error};
+
+/// The values of a session, as kept by a [`SessionStore`](crate::middleware::SessionStore).
+pub type SessionData = HashMap<String, String>;
+
+/// The request's session, changes are saved once the response has been created.
+///
+/// Requires the [`Session`](crate::middleware::Session) middleware.
+#[derive(Clone)]
+pub struct Session {
+    pub(crate) state: Arc<Mutex<SessionState>>,
+}
+
+pub(crate) struct SessionState {
+    pub(crate) data: SessionData,
+    pub(crate) changed: bool,
+    /// The session was cleared, its id is dropped.
+    pub(crate) cleared: bool,
+    /// The session is moved to a new id.
+    pub(crate) renewed: bool,
+}
+
+impl Session {
+    pub(crate) fn new(data: SessionData) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SessionState {
+                data,
+                changed: false,
+                cleared: false,
+                renewed: false,
+            })),
+        }
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.state().data.get(key).cloned()
+    }
+
+    pub fn set<K, V>(&self, key: K, value: V)
+    where
+        K: ToString,
+        V: ToString,
+    {
+        let mut state = self.state();
+
+        state.data.insert(key.to_string(), value.to_string());
+        state.changed = true;
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut state = self.state();
+
+        let value = state.data.remove(key);
+
+        state.changed |= value.is_some();
+
+        value
+    }
+
+    /// Removes every value, and the session itself, the client is told to forget its cookie.
+    pub fn clear(&self) {
+        let mut state = self.state();
+
+        state.data.clear();
+        state.cleared = true;
+    }
+
+    /// Moves the session to a new id, ex. after logging in, so an id an attacker got the
+    /// client to use (session fixation) is of no use.
+    pub fn renew(&self) {
+        let mut state = self.state();
+
+        state.renewed = true;
+        state.cleared = false;
+    }
+}
+
+impl Extractor for Session {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        match req.extensions.get::<Session>() {
+            Some(session) => Ok(session.clone()),
+            None => Err(InternalError::InternalServerError(
+                "`Session` was used without the `Session` middleware",
+            )),
+        }
+    }
+}
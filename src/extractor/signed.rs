@@ -0,0 +1,128 @@
+use std::{
+    io::Read,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    error::InternalError,
+    extractor::{body, Data, Extractor},
+    http::{HttpHeaderName, HttpRequest},
+    utils::hmac,
+    Error,
+};
+
+/// The secret and signature header for [`Signed`], registered with
+/// [`App::data`](crate::App::data).
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use enrgy::{web, App};
+///
+/// let app = App::new().data(Arc::new(web::SignedConfig::new(
+///     b"webhook secret",
+///     "X-Hub-Signature-256",
+/// )));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SignedConfig {
+    secret: Vec<u8>,
+    header: HttpHeaderName,
+}
+
+impl SignedConfig {
+    pub fn new(secret: &[u8], header: &'static str) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            header: HttpHeaderName::new(header),
+        }
+    }
+
+    fn verify(&self, signature: &str, body: &[u8]) -> bool {
+        let signature = signature.trim();
+        let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+
+        let expected = hmac::to_hex(&hmac::hmac_sha256(&self.secret, body));
+
+        hmac::constant_time_eq(
+            expected.as_bytes(),
+            signature.to_ascii_lowercase().as_bytes(),
+        )
+    }
+}
+
+/// Runs the extractor `T` (ex. [`Json`](crate::web::Json) or [`Text`](crate::web::Text)) only
+/// once the body's HMAC-SHA256 signature has been checked, as webhooks are signed.
+///
+/// The signature is read from the [`SignedConfig`]'s header, hex encoded, optionally prefixed
+/// with `sha256=`, and is compared to one made from the raw body. Requests with a missing or
+/// wrong signature are rejected with `401 Unauthorized`.
+pub struct Signed<T> {
+    value: T,
+}
+
+impl<T> Signed<T> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> const Deref for Signed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> const DerefMut for Signed<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> Extractor for Signed<T>
+where
+    T: Extractor<Error = Error>,
+{
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        let config = match req.data.get::<Data<SignedConfig>>() {
+            Some(config) => config.data.clone(),
+            None => {
+                return Err(InternalError::InternalServerError(
+                    "`Signed` was used without a `SignedConfig`",
+                ))
+            }
+        };
+
+        let signature = match req.header_data.headers.get(&config.header) {
+            Some(signature) => signature.clone(),
+            None => {
+                return Err(InternalError::Unauthorized(
+                    "HTTP request is missing its signature",
+                ))
+            }
+        };
+
+        body::check_limit(req)?;
+
+        // The whole body is needed to check it, so it is buffered for `T` to extract from.
+        if let Some(mut payload) = req.payload.take() {
+            let mut body = Vec::with_capacity(payload.len() as usize);
+
+            payload.read_to_end(&mut body).map_err(body::read_error)?;
+
+            req.body = body;
+        }
+
+        if !config.verify(&signature, &req.body) {
+            return Err(InternalError::Unauthorized(
+                "HTTP request body does not match its signature",
+            ));
+        }
+
+        T::extract(req).map(|value| Signed { value })
+    }
+}
@@ -0,0 +1,434 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Write as _,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::InternalError,
+    http::{
+        date,
+        headers::{
+            ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+            IF_RANGE, LAST_MODIFIED, RANGE,
+        },
+        HttpMethod, HttpRequest, HttpResponse, StatusCode,
+    },
+    service::Service,
+    utils::hmac::{self, Sha256},
+    Error,
+};
+
+/// The most ranges a request can ask for before the whole file is sent instead.
+const MAX_RANGES: usize = 16;
+
+/// Serves the files in a directory, for a route with a catch-all parameter.
+///
+/// `GET` requests with a `Range` header get only the requested bytes, requests for more than
+/// one range get them as a `multipart/byteranges` body, unless the ranges overlap or add up to
+/// the whole file, then it is sent as is.
+///
+/// Responses have an `ETag` and `Last-Modified`, so `If-None-Match` and `If-Modified-Since`
+/// requests for an unchanged file get a `304 Not Modified`. The `ETag` is weak, from the file's
+/// size and modification time, unless [`Files::strong_etags`] is set.
+///
+/// ```no_run
+/// use enrgy::{web, App};
+///
+/// App::new().service(web::get("/static/*file").service(web::Files::new("./public")));
+/// ```
+pub struct Files {
+    dir: PathBuf,
+    cache: bool,
+    strong_etags: bool,
+    #[cfg(feature = "dev")]
+    watch: bool,
+    entries: Mutex<HashMap<PathBuf, Arc<Entry>>>,
+}
+
+struct Entry {
+    modified: Option<SystemTime>,
+    len: u64,
+    content: Vec<u8>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Files {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            cache: false,
+            strong_etags: false,
+            #[cfg(feature = "dev")]
+            watch: false,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keeps files in memory after they are first read, instead of reading them on every
+    /// request.
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+
+        self
+    }
+
+    /// Uses a hash of the file's content as its `ETag`, instead of its size and modification
+    /// time, which also lets `If-Range` requests compare it.
+    pub fn strong_etags(mut self, strong: bool) -> Self {
+        self.strong_etags = strong;
+
+        self
+    }
+
+    /// Checks the modification time of cached files on every request, reading them again if
+    /// they were changed on disk.
+    #[cfg(feature = "dev")]
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+
+        self
+    }
+
+    #[cfg(feature = "dev")]
+    const fn watching(&self) -> bool {
+        self.watch
+    }
+
+    #[cfg(not(feature = "dev"))]
+    const fn watching(&self) -> bool {
+        false
+    }
+
+    /// Resolves the request's catch-all parameter to a path inside of the directory, `None` if
+    /// it would escape it.
+    fn resolve(&self, req: &HttpRequest) -> Option<PathBuf> {
+        let name = req.pattern.as_deref()?.rsplit_once('*')?.1;
+        let file = req.params.get(name)?;
+
+        let mut path = self.dir.clone();
+
+        for component in Path::new(file.trim_start_matches('/')).components() {
+            match component {
+                Component::Normal(part) => path.push(part),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+
+        Some(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Entry> {
+        let metadata = fs::metadata(path)?;
+
+        if !metadata.is_file() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        let modified = metadata.modified().ok();
+        let content = fs::read(path)?;
+
+        let etag = if self.strong_etags {
+            let mut hasher = Sha256::new();
+
+            hasher.update(&content);
+
+            Some(format!("\"{}\"", hmac::to_hex(&hasher.finish()[..16])))
+        } else {
+            modified
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| format!("W/\"{:x}-{:x}\"", metadata.len(), modified.as_nanos()))
+        };
+
+        Ok(Entry {
+            modified,
+            len: metadata.len(),
+            content,
+            etag,
+            last_modified: modified.map(date::format),
+        })
+    }
+
+    fn load(&self, path: &Path) -> io::Result<Arc<Entry>> {
+        if !self.cache {
+            return self.read(path).map(Arc::new);
+        }
+
+        let cached = self
+            .entries
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(path)
+            .cloned();
+
+        if let Some(entry) = cached {
+            if !self.watching() {
+                return Ok(entry);
+            }
+
+            let metadata = fs::metadata(path)?;
+
+            if metadata.modified().ok() == entry.modified && metadata.len() == entry.len {
+                return Ok(entry);
+            }
+        }
+
+        let entry = Arc::new(self.read(path)?);
+
+        self.entries
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(path.to_path_buf(), Arc::clone(&entry));
+
+        Ok(entry)
+    }
+}
+
+impl Service<HttpRequest> for Files {
+    type Response = HttpResponse;
+
+    type Error = Error;
+
+    fn call(&self, req: &mut HttpRequest) -> Result<Self::Response, Self::Error> {
+        let path = match self.resolve(req) {
+            Some(path) => path,
+            None => return Ok(HttpResponse::not_found()),
+        };
+
+        match self.load(&path) {
+            Ok(entry) => Ok(respond(req, &entry, content_type(&path))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HttpResponse::not_found()),
+            Err(err) => Err(InternalError::InternalServerError(err)),
+        }
+    }
+}
+
+fn respond(req: &HttpRequest, entry: &Entry, content_type: &'static str) -> HttpResponse {
+    let content = &entry.content[..];
+    let len = content.len() as u64;
+
+    let validators = |mut res: HttpResponse| {
+        if let Some(etag) = &entry.etag {
+            res.headers.insert(ETAG, etag.clone());
+        }
+
+        if let Some(last_modified) = &entry.last_modified {
+            res.headers.insert(LAST_MODIFIED, last_modified.clone());
+        }
+
+        res
+    };
+
+    let full = || {
+        validators(
+            HttpResponse::ok()
+                .header(ACCEPT_RANGES, "bytes")
+                .header(CONTENT_TYPE, content_type)
+                .body(content.to_vec()),
+        )
+    };
+
+    let headers = &req.header_data.headers;
+
+    if matches!(req.header_data.method, HttpMethod::Get | HttpMethod::Head)
+        && not_modified(req, entry)
+    {
+        return validators(HttpResponse::new(StatusCode::NOT_MODIFIED));
+    }
+
+    let if_range = headers
+        .get(&IF_RANGE)
+        .map(|validator| range_current(validator.trim(), entry))
+        .unwrap_or(true);
+
+    if req.header_data.method != HttpMethod::Get || !if_range {
+        return full();
+    }
+
+    let ranges = match headers.get(&RANGE).and_then(|range| ranges(range, len)) {
+        Some(ranges) => ranges,
+        None => return full(),
+    };
+
+    let slice = |range: &Range<u64>| &content[range.start as usize..range.end as usize];
+
+    match &ranges[..] {
+        [] => HttpResponse::new(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(CONTENT_RANGE, format!("bytes */{}", len)),
+        [range] => validators(
+            HttpResponse::new(StatusCode::PARTIAL_CONTENT)
+                .header(ACCEPT_RANGES, "bytes")
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_RANGE, content_range(range, len))
+                .body(slice(range).to_vec()),
+        ),
+        _ if !worth_splitting(&ranges, len) => full(),
+        _ => {
+            let boundary = format!("{:016x}", RandomState::new().build_hasher().finish());
+
+            let mut body = Vec::new();
+
+            for range in &ranges {
+                let mut head = String::new();
+
+                let _ = write!(
+                    head,
+                    "--{}\r\n{}: {}\r\n{}: {}\r\n\r\n",
+                    boundary,
+                    CONTENT_TYPE,
+                    content_type,
+                    CONTENT_RANGE,
+                    content_range(range, len)
+                );
+
+                body.extend_from_slice(head.as_bytes());
+                body.extend_from_slice(slice(range));
+                body.extend_from_slice(b"\r\n");
+            }
+
+            body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+            validators(
+                HttpResponse::new(StatusCode::PARTIAL_CONTENT)
+                    .header(ACCEPT_RANGES, "bytes")
+                    .header(
+                        CONTENT_TYPE,
+                        format!("multipart/byteranges; boundary={}", boundary),
+                    )
+                    .body(body),
+            )
+        }
+    }
+}
+
+/// Returns if the client's copy is current, going by `If-None-Match`, or `If-Modified-Since`
+/// if there is no `If-None-Match`.
+fn not_modified(req: &HttpRequest, entry: &Entry) -> bool {
+    let headers = &req.header_data.headers;
+
+    if let Some(tags) = headers.get(&IF_NONE_MATCH) {
+        // Weak comparison, `W/"a"` matches `"a"`.
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+        return match &entry.etag {
+            Some(etag) => tags
+                .split(',')
+                .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag)),
+            None => false,
+        };
+    }
+
+    match (
+        headers
+            .get(&IF_MODIFIED_SINCE)
+            .and_then(|since| date::parse(since)),
+        entry.modified,
+    ) {
+        // The date only has whole seconds.
+        (Some(since), Some(modified)) => date::parse(&date::format(modified))
+            .map(|modified| modified <= since)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Returns if an `If-Range` validator still describes the file, which only a strong `ETag` or
+/// the exact `Last-Modified` date can.
+fn range_current(validator: &str, entry: &Entry) -> bool {
+    if validator.starts_with('"') {
+        entry.etag.as_deref() == Some(validator)
+    } else {
+        entry.last_modified.as_deref() == Some(validator)
+    }
+}
+
+/// Parses a `Range` header into the satisfiable ranges for a body of `len` bytes, `None` if it
+/// is malformed or not in bytes, in which case it is ignored.
+fn ranges(header: &str, len: u64) -> Option<Vec<Range<u64>>> {
+    let (unit, specs) = header.split_once('=')?;
+
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let mut ranges = Vec::new();
+
+    for spec in specs.split(',').map(str::trim) {
+        let (first, last) = spec.split_once('-')?;
+
+        let range = match (first.trim(), last.trim()) {
+            ("", "") => return None,
+            ("", suffix) => {
+                let suffix = suffix.parse::<u64>().ok()?;
+
+                len.saturating_sub(suffix)..len
+            }
+            (first, "") => first.parse::<u64>().ok()?..len,
+            (first, last) => {
+                let first = first.parse::<u64>().ok()?;
+                let last = last.parse::<u64>().ok()?;
+
+                if last < first {
+                    return None;
+                }
+
+                first..last.saturating_add(1).min(len)
+            }
+        };
+
+        if range.start < range.end {
+            ranges.push(range);
+        }
+    }
+
+    Some(ranges)
+}
+
+/// Returns if the ranges are few enough, do not overlap, and do not add up to the whole body,
+/// otherwise the whole body is smaller and simpler to send.
+fn worth_splitting(ranges: &[Range<u64>], len: u64) -> bool {
+    if ranges.len() > MAX_RANGES {
+        return false;
+    }
+
+    let mut sorted = ranges.to_vec();
+
+    sorted.sort_by_key(|range| range.start);
+
+    let overlap = sorted.windows(2).any(|pair| pair[0].end > pair[1].start);
+    let total = sorted
+        .iter()
+        .map(|range| range.end - range.start)
+        .sum::<u64>();
+
+    !overlap && total < len
+}
+
+fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, len)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("ico") => "image/x-icon",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
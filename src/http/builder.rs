@@ -0,0 +1,151 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use crate::{
+    extensions::Extensions,
+    http::{
+        headers::HttpHeaderName, parse_query, HttpHeaderData, HttpHeaders, HttpMethod, HttpParams,
+        HttpRequest, HttpVersion, TypedHeaders,
+    },
+    web,
+};
+
+/// Builds a request without a connection, to call a handler or service directly, ex. in a unit
+/// test with `Route::call`, see [`HttpRequest::builder`].
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use enrgy::{http::HttpRequest, web};
+///
+/// struct Greeting(&'static str);
+///
+/// fn greet(name: web::Param<"name">, greeting: web::Data<Greeting>) -> String {
+///     format!("{} {}!", greeting.0, *name)
+/// }
+///
+/// let mut req = HttpRequest::builder()
+///     .uri("/greet/Ferris")
+///     .param("name", "Ferris")
+///     .data(Arc::new(Greeting("Hello")))
+///     .build();
+///
+/// let res = web::get("/greet/:name").to(greet).call(&mut req).unwrap();
+///
+/// assert_eq!(res.body_bytes(), Some(&b"Hello Ferris!"[..]));
+/// ```
+pub struct HttpRequestBuilder {
+    req: HttpRequest,
+    data: Extensions,
+}
+
+impl HttpRequestBuilder {
+    /// A `GET /` HTTP/1.1 request, without headers or a body.
+    pub fn new() -> Self {
+        Self {
+            req: HttpRequest {
+                header_data: HttpHeaderData {
+                    method: HttpMethod::Get,
+                    scheme: None,
+                    authority: None,
+                    url: "/".to_string(),
+                    query: String::new(),
+                    query_params: HttpParams::new(),
+                    version: HttpVersion::Http11,
+                    headers: HttpHeaders::new(),
+                },
+                body: Vec::new(),
+                payload: None,
+                params: HttpParams::new(),
+                pattern: None,
+                data: Arc::new(Extensions::new()),
+                extensions: Extensions::new(),
+                peer_addr: None,
+                typed: TypedHeaders::default(),
+            },
+            data: Extensions::new(),
+        }
+    }
+
+    pub fn method(mut self, method: HttpMethod) -> Self {
+        self.req.header_data.method = method;
+
+        self
+    }
+
+    pub fn version(mut self, version: HttpVersion) -> Self {
+        self.req.header_data.version = version;
+
+        self
+    }
+
+    /// Sets the path, and the query string if `uri` has one (ex. `/items?page=2`).
+    pub fn uri(mut self, uri: &str) -> Self {
+        let (url, query) = uri.split_at(uri.find('?').unwrap_or_else(|| uri.len()));
+
+        self.req.header_data.url = url.to_string();
+        self.req.header_data.query = query.to_string();
+        self.req.header_data.query_params = parse_query(query);
+
+        self
+    }
+
+    pub fn header<V>(mut self, key: HttpHeaderName, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.req.header_data.headers.insert(key, value.to_string());
+
+        self
+    }
+
+    /// Sets a path parameter, as if it had been matched by the route.
+    pub fn param<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: ToString,
+        V: ToString,
+    {
+        self.req.params.insert(key.to_string(), value.to_string());
+
+        self
+    }
+
+    /// Sets the body, a `Content-Length` header is not added.
+    pub fn body<B>(mut self, body: B) -> Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.req.body = body.into();
+
+        self
+    }
+
+    /// Adds data that is read with `web::Data<T>`, like [`App::data`](crate::App::data).
+    pub fn data<T>(mut self, data: Arc<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.data.insert(web::Data { data });
+
+        self
+    }
+
+    pub fn peer_addr(mut self, addr: SocketAddr) -> Self {
+        self.req.peer_addr = Some(addr);
+
+        self
+    }
+
+    pub fn build(self) -> HttpRequest {
+        let mut req = self.req;
+
+        req.data = Arc::new(self.data);
+
+        req
+    }
+}
+
+impl Default for HttpRequestBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
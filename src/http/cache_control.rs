@@ -0,0 +1,159 @@
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visibility {
+    Public,
+    Private,
+}
+
+/// A builder for `Cache-Control` response directives.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use enrgy::http::CacheControl;
+///
+/// let directives = CacheControl::new().public().max_age(Duration::from_secs(3600));
+///
+/// assert_eq!(directives.to_string(), "public, max-age=3600");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheControl {
+    visibility: Option<Visibility>,
+    no_cache: bool,
+    no_store: bool,
+    max_age: Option<u64>,
+    immutable: bool,
+}
+
+impl CacheControl {
+    pub const fn new() -> Self {
+        Self {
+            visibility: None,
+            no_cache: false,
+            no_store: false,
+            max_age: None,
+            immutable: false,
+        }
+    }
+
+    /// The response may be stored by any cache, including shared ones.
+    pub const fn public(mut self) -> Self {
+        self.visibility = Some(Visibility::Public);
+
+        self
+    }
+
+    /// The response may only be stored by the client's private cache.
+    pub const fn private(mut self) -> Self {
+        self.visibility = Some(Visibility::Private);
+
+        self
+    }
+
+    /// Caches must revalidate the response before using it.
+    pub const fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+
+        self
+    }
+
+    /// Caches must not store the response at all.
+    pub const fn no_store(mut self) -> Self {
+        self.no_store = true;
+
+        self
+    }
+
+    /// The response is fresh for `age`, the duration is truncated to whole seconds.
+    pub const fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age.as_secs());
+
+        self
+    }
+
+    /// The response will not change while it is fresh.
+    pub const fn immutable(mut self) -> Self {
+        self.immutable = true;
+
+        self
+    }
+}
+
+impl const Default for CacheControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+
+        let mut directive = |f: &mut fmt::Formatter<'_>, args: fmt::Arguments<'_>| {
+            if !first {
+                f.write_str(", ")?;
+            }
+
+            first = false;
+
+            f.write_fmt(args)
+        };
+
+        match self.visibility {
+            Some(Visibility::Public) => directive(f, format_args!("public"))?,
+            Some(Visibility::Private) => directive(f, format_args!("private"))?,
+            None => {}
+        }
+
+        if self.no_cache {
+            directive(f, format_args!("no-cache"))?;
+        }
+
+        if self.no_store {
+            directive(f, format_args!("no-store"))?;
+        }
+
+        if let Some(age) = self.max_age {
+            directive(f, format_args!("max-age={}", age))?;
+        }
+
+        if self.immutable {
+            directive(f, format_args!("immutable"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache_control() {
+        assert_eq!(CacheControl::new().to_string(), "");
+
+        assert_eq!(
+            CacheControl::new().no_cache().no_store().to_string(),
+            "no-cache, no-store"
+        );
+
+        assert_eq!(
+            CacheControl::new()
+                .public()
+                .max_age(Duration::from_secs(31_536_000))
+                .immutable()
+                .to_string(),
+            "public, max-age=31536000, immutable"
+        );
+
+        assert_eq!(
+            CacheControl::new()
+                .private()
+                .max_age(Duration::from_millis(1500))
+                .to_string(),
+            "private, max-age=1"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    io::{self, Read},
+    process::{Child, ChildStdout},
+};
+
+use crate::{
+    http::{HttpBody, HttpResponse},
+    utils::log,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The chunks of a command's output, see [`HttpResponse::from_command`].
+struct Output {
+    child: Child,
+    stdout: ChildStdout,
+    done: bool,
+    /// Set once the command has exited successfully.
+    succeeded: bool,
+}
+
+impl Output {
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+
+        loop {
+            match self.stdout.read(&mut chunk) {
+                Ok(read) => {
+                    chunk.truncate(read);
+
+                    return Ok(chunk);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn wait(&mut self) {
+        self.done = true;
+
+        match self.child.wait() {
+            Ok(status) if status.success() => self.succeeded = true,
+            Ok(status) => log::error!("command exited with {}", status),
+            Err(err) => log::error!("unable to wait for command: {}", err),
+        }
+    }
+}
+
+impl Iterator for Output {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_chunk() {
+            Ok(chunk) if !chunk.is_empty() => Some(chunk),
+            Ok(_) => {
+                self.wait();
+
+                None
+            }
+            Err(err) => {
+                log::error!("unable to read command output: {}", err);
+
+                let _ = self.child.kill();
+
+                self.wait();
+
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        // The client went away before the output ended, the command is not waited on otherwise.
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+pub(crate) fn response(mut child: Child) -> HttpResponse {
+    let stdout = match child.stdout.take() {
+        Some(stdout) => stdout,
+        None => {
+            log::error!("command's stdout is not piped");
+
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return HttpResponse::internal_server_error().body("Internal Server Error");
+        }
+    };
+
+    let mut output = Output {
+        child,
+        stdout,
+        done: false,
+        succeeded: false,
+    };
+
+    // The status can only be changed until the first chunk is written, so a command that fails
+    // without any output is still reported as an error.
+    match output.next() {
+        Some(first) => {
+            HttpResponse::ok().body(HttpBody::stream(Some(first).into_iter().chain(output)))
+        }
+        None if output.succeeded => HttpResponse::ok().finish(),
+        None => HttpResponse::internal_server_error().body("Internal Server Error"),
+    }
+}
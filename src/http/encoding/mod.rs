@@ -1,4 +1,146 @@
 //! Vendored versions of [percent-encoding](https://github.com/servo/rust-url) and [form_urlencoded](https://github.com/servo/rust-url).
+//!
+//! [`percent_encode`] and [`percent_decode`] cover the common cases, the [`percent`] module has
+//! the lower level (and lenient) versions.
 
 pub mod form;
 pub mod percent;
+
+use crate::http::HttpError;
+
+pub use self::percent::{AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+
+/// The bytes that are encoded in a path segment, everything but the unreserved and
+/// sub-delimiter characters (RFC 3986 section 3.3), so `/` can not split the segment.
+pub const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b',')
+    .remove(b';')
+    .remove(b':')
+    .remove(b'@');
+
+/// The bytes that are encoded in a query string key or value, so `&`, `=`, and `+` can not
+/// change its meaning.
+pub const QUERY: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b',')
+    .remove(b';')
+    .remove(b':')
+    .remove(b'@')
+    .remove(b'/')
+    .remove(b'?');
+
+/// Percent encodes the bytes of `input` that are in `set`, along with any non-ASCII bytes.
+///
+/// ```
+/// use enrgy::http::encoding::{percent_encode, PATH_SEGMENT};
+///
+/// assert_eq!(percent_encode("a b/c".as_bytes(), PATH_SEGMENT), "a%20b%2Fc");
+/// ```
+pub fn percent_encode(input: &[u8], set: &'static AsciiSet) -> String {
+    percent::percent_encode(input, set).to_string()
+}
+
+/// Decodes a percent encoded string, unlike [`percent::percent_decode_str`] a `%` has to be
+/// followed by two hexadecimal digits.
+///
+/// ```
+/// use enrgy::http::encoding::percent_decode;
+///
+/// assert_eq!(percent_decode("a%20b").unwrap(), b"a b");
+/// assert!(percent_decode("100%").is_err());
+/// ```
+pub fn percent_decode(input: &str) -> Result<Vec<u8>, HttpError> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] != b'%' {
+            decoded.push(bytes[index]);
+            index += 1;
+
+            continue;
+        }
+
+        let hex = |offset: usize| {
+            bytes
+                .get(index + offset)
+                .and_then(|byte| (*byte as char).to_digit(16))
+        };
+
+        match (hex(1), hex(2)) {
+            (Some(high), Some(low)) => decoded.push((high * 16 + low) as u8),
+            _ => return Err(HttpError::InvalidPercentEncoding { offset: index }),
+        }
+
+        index += 3;
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for input in [
+            "hello world",
+            "a/b?c=d&e",
+            "100% ünïcödé",
+            "~-._!$'()*,;:@",
+            "",
+        ] {
+            for set in [PATH_SEGMENT, QUERY, NON_ALPHANUMERIC] {
+                let encoded = percent_encode(input.as_bytes(), set);
+
+                assert!(encoded.is_ascii(), "{}", encoded);
+                assert_eq!(percent_decode(&encoded).unwrap(), input.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_sets() {
+        assert_eq!(percent_encode(b"a b/c?d", PATH_SEGMENT), "a%20b%2Fc%3Fd");
+        assert_eq!(
+            percent_encode(b"a b/c?d&e=f+g", QUERY),
+            "a%20b/c?d%26e%3Df%2Bg"
+        );
+        assert_eq!(percent_encode(b"~user@host", PATH_SEGMENT), "~user@host");
+    }
+
+    #[test]
+    fn test_invalid_decode() {
+        for (input, offset) in [("%", 0), ("ab%2", 2), ("%zz", 0), ("a%20%g0", 4)] {
+            match percent_decode(input) {
+                Err(HttpError::InvalidPercentEncoding { offset: actual }) => {
+                    assert_eq!(actual, offset, "{}", input)
+                }
+                other => panic!("{}: {:?}", input, other),
+            }
+        }
+
+        assert_eq!(percent_decode("%2f%2F").unwrap(), b"//");
+    }
+}
@@ -11,6 +11,27 @@ impl HttpHeaderName {
     pub const fn new(key: &'static str) -> Self {
         Self(Cow::Borrowed(key))
     }
+
+    /// If the name is a non-empty token, as header names have to be.
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_empty() && self.0.bytes().all(is_token)
+    }
+}
+
+/// If `value` can be written as a header value, it can not contain line breaks (which would
+/// allow injecting headers) or null bytes.
+pub fn is_valid_value(value: &str) -> bool {
+    !value
+        .bytes()
+        .any(|byte| matches!(byte, b'\r' | b'\n' | b'\0'))
+}
+
+const fn is_token(byte: u8) -> bool {
+    matches!(byte,
+        b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9'
+        | b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_'
+        | b'`' | b'|' | b'~'
+    )
 }
 
 impl fmt::Display for HttpHeaderName {
@@ -19,15 +40,16 @@ impl fmt::Display for HttpHeaderName {
     }
 }
 
+// Header names are tokens, so only ASCII letters have a case.
 impl cmp::PartialEq<str> for HttpHeaderName {
     fn eq(&self, other: &str) -> bool {
-        self.0.to_lowercase() == other.to_lowercase()
+        self.0.eq_ignore_ascii_case(other)
     }
 }
 
 impl cmp::PartialEq<HttpHeaderName> for HttpHeaderName {
     fn eq(&self, other: &HttpHeaderName) -> bool {
-        self.0.to_lowercase() == other.0.to_lowercase()
+        self.0.eq_ignore_ascii_case(&other.0)
     }
 }
 
@@ -74,6 +96,7 @@ impl_headers! {
     FORWARDED => "Forwarded",
     FROM => "From",
     HOST => "Host",
+    IDEMPOTENCY_KEY => "Idempotency-Key",
     IF_MATCH => "If-Match",
     IF_MODIFIED_SINCE => "If-Modified-Since",
     IF_NONE_MATCH => "If-None-match",
@@ -88,8 +111,12 @@ impl_headers! {
     PROXY_AUTHENTICATE => "Proxy-Authenticate",
     PROXY_AUTHORIZATION => "Proxy-Authorization",
     PROXY_CONNECTION => "Proxy-Connection",
+    RANGE => "Range",
     REFERER => "Referer",
+    REFERRER_POLICY => "Referrer-Policy",
     RETRY_AFTER => "Retry-After",
+    SEC_FETCH_MODE => "Sec-Fetch-Mode",
+    SEC_FETCH_SITE => "Sec-Fetch-Site",
     SERVER => "Server",
     SERVER_TIMING => "Server-Timing",
     SET_COOKIE => "Set-Cookie",
@@ -105,4 +132,7 @@ impl_headers! {
     VIA => "Via",
     WARNING => "Warning",
     WWW_AUTHENTICATE => "WWW-Authenticate",
+    X_CONTENT_TYPE_OPTIONS => "X-Content-Type-Options",
+    X_FORWARDED_PROTO => "X-Forwarded-Proto",
+    X_FRAME_OPTIONS => "X-Frame-Options",
 }
@@ -0,0 +1,242 @@
+use std::{fmt, str::FromStr};
+
+use crate::http::HttpError;
+
+/// A parsed media type, as found in `Content-Type` and `Accept`.
+///
+/// The type, subtype, and parameter names are lowercased, parameter values are kept as is
+/// (with any quoting removed).
+///
+/// ```
+/// use enrgy::http::MediaType;
+///
+/// let media_type = "Application/JSON; charset=utf-8".parse::<MediaType>().unwrap();
+///
+/// assert_eq!(media_type.essence(), "application/json");
+/// assert_eq!(media_type.param("charset"), Some("utf-8"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    essence: String,
+    slash: usize,
+    params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// The type and subtype, without parameters (ex. `application/json`).
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// The type, ex. `application` in `application/json`.
+    pub fn type_(&self) -> &str {
+        &self.essence[..self.slash]
+    }
+
+    /// The subtype, ex. `json` in `application/json`.
+    pub fn subtype(&self) -> &str {
+        &self.essence[(self.slash + 1)..]
+    }
+
+    /// The structured syntax suffix of the subtype, ex. `json` in `application/vnd.api+json`.
+    pub fn suffix(&self) -> Option<&str> {
+        let subtype = self.subtype();
+
+        subtype.rfind('+').map(|index| &subtype[(index + 1)..])
+    }
+
+    /// Returns the value of a parameter, the name is matched case-insensitively.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns `true` if the essence matches, case-insensitively.
+    pub fn is(&self, essence: &str) -> bool {
+        self.essence.eq_ignore_ascii_case(essence)
+    }
+
+    pub(crate) fn remove_param(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .params
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+
+        Some(self.params.remove(index).1)
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = HttpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s.trim();
+
+        let end = rest.find(';').unwrap_or_else(|| rest.len());
+        let essence = rest[..end].trim().to_ascii_lowercase();
+        rest = &rest[end..];
+
+        let slash = match essence.find('/') {
+            Some(slash) if is_token(&essence[..slash]) && is_token(&essence[(slash + 1)..]) => {
+                slash
+            }
+            _ => return Err(HttpError::InvalidMediaType),
+        };
+
+        let mut params = Vec::new();
+
+        while let Some(stripped) = rest.strip_prefix(';') {
+            rest = stripped.trim_start();
+
+            if rest.is_empty() {
+                break;
+            }
+
+            let eq = rest.find('=').ok_or(HttpError::InvalidMediaType)?;
+            let name = rest[..eq].trim();
+
+            if !is_token(name) {
+                return Err(HttpError::InvalidMediaType);
+            }
+
+            rest = rest[(eq + 1)..].trim_start();
+
+            let value = if let Some(quoted) = rest.strip_prefix('"') {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => value.push(c),
+                            None => return Err(HttpError::InvalidMediaType),
+                        },
+                        Some((index, '"')) => {
+                            rest = quoted[(index + 1)..].trim_start();
+
+                            break;
+                        }
+                        Some((_, c)) => value.push(c),
+                        None => return Err(HttpError::InvalidMediaType),
+                    }
+                }
+
+                value
+            } else {
+                let end = rest.find(';').unwrap_or_else(|| rest.len());
+                let value = rest[..end].trim();
+
+                rest = &rest[end..];
+
+                if !is_token(value) {
+                    return Err(HttpError::InvalidMediaType);
+                }
+
+                value.to_string()
+            };
+
+            if !rest.is_empty() && !rest.starts_with(';') {
+                return Err(HttpError::InvalidMediaType);
+            }
+
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        Ok(Self {
+            essence,
+            slash,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.essence)?;
+
+        for (key, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {}={}", key, value)?;
+            } else {
+                write!(
+                    f,
+                    "; {}=\"{}\"",
+                    key,
+                    value.replace('\\', "\\\\").replace('"', "\\\"")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// RFC 7230 `token`.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let media_type = "application/json; charset=utf-8"
+            .parse::<MediaType>()
+            .unwrap();
+
+        assert_eq!(media_type.essence(), "application/json");
+        assert_eq!(media_type.type_(), "application");
+        assert_eq!(media_type.subtype(), "json");
+        assert_eq!(media_type.param("Charset"), Some("utf-8"));
+        assert!(media_type.is("Application/Json"));
+    }
+
+    #[test]
+    fn test_multipart() {
+        let media_type = "multipart/form-data; boundary=abc"
+            .parse::<MediaType>()
+            .unwrap();
+
+        assert!(media_type.is("multipart/form-data"));
+        assert_eq!(media_type.param("boundary"), Some("abc"));
+
+        let media_type =
+            r#"multipart/form-data; boundary="a \"b\" c""#.parse::<MediaType>().unwrap();
+
+        assert_eq!(media_type.param("boundary"), Some(r#"a "b" c"#));
+    }
+
+    #[test]
+    fn test_suffix() {
+        let media_type = "application/vnd.api+json".parse::<MediaType>().unwrap();
+
+        assert_eq!(media_type.suffix(), Some("json"));
+    }
+
+    #[test]
+    fn test_malformed() {
+        for value in [
+            "",
+            "json",
+            "application/",
+            "/json",
+            "text/plain; charset",
+            "text/plain; charset=\"utf-8",
+            "text/plain; charset=utf 8",
+        ] {
+            assert!(value.parse::<MediaType>().is_err(), "{:?}", value);
+        }
+    }
+}
@@ -1,27 +1,44 @@
-mod encoding;
+pub mod encoding;
 
 pub mod headers;
 pub mod uri;
 
+mod builder;
+mod cache_control;
+mod command;
+pub(crate) mod date;
+mod media_type;
+mod payload;
 mod status;
 
 use std::{
+    any::{Any, TypeId},
     borrow::Cow,
-    cmp, fmt,
-    io::Read,
-    io::{BufRead, Write},
-    net::TcpStream,
+    cmp,
+    convert::TryFrom,
+    fmt,
+    io::{self, BufRead, Read, Write},
+    lazy::OnceCell,
+    net::{SocketAddr, TcpStream},
+    process::Child,
     str::FromStr,
-    sync::Arc,
+    sync::{mpsc::Receiver, Arc, Mutex},
 };
 
 use crate::{
     extensions::Extensions,
     http::uri::HttpResource,
-    utils::{ArrayMap, Ascii, Const},
+    utils::{log, ArrayMap, Ascii, Const},
 };
 
-pub use self::{headers::HttpHeaderName, status::StatusCode};
+pub use self::{
+    builder::HttpRequestBuilder,
+    cache_control::CacheControl,
+    headers::HttpHeaderName,
+    media_type::MediaType,
+    payload::Payload,
+    status::{StatusClass, StatusCode},
+};
 
 #[derive(Debug)]
 pub enum HttpError {
@@ -34,11 +51,175 @@ pub enum HttpError {
 
     ParseUnknownMethod,
     ParseUnknownVersion,
+    /// The connection started with the HTTP/2 connection preface, from a client that assumed
+    /// the server speaks HTTP/2.
+    Http2Preface,
+
+    /// Only returned when parsing strictly, see [`HttpServer::strict_parsing`](crate::HttpServer::strict_parsing).
+    ParseMetaWhitespace,
+    /// Only returned when parsing strictly.
+    ParseHeaderWhitespace,
+    /// Only returned when parsing strictly.
+    ParseHeaderObsoleteFold,
+    /// Only returned when parsing strictly.
+    ParseHeaderMissingColon,
+
+    /// The request head contained a byte that is not valid UTF-8, `offset` is counted from the
+    /// start of the request.
+    InvalidHeaderEncoding {
+        offset: usize,
+    },
+
+    /// A `Content-Type` (or similar) value that is not a valid media type.
+    InvalidMediaType,
+    /// A `Content-Length` value that is not a number.
+    InvalidContentLength,
+    /// More than one `Content-Length` was sent, and they were not all the same, RFC 7230
+    /// section 3.3.2.
+    ConflictingContentLength,
+    /// A header name that is not a token.
+    InvalidHeaderName,
+    /// A header value that contains a line break or null byte.
+    InvalidHeaderValue,
+    /// A `%` that is not followed by two hexadecimal digits, `offset` is the `%`'s.
+    InvalidPercentEncoding {
+        offset: usize,
+    },
+
+    /// The request target contains a control character (ex. a null byte), either as is or
+    /// percent encoded in the path.
+    InvalidTargetControl,
+    /// The request target contains a backslash, as is or percent encoded in the path, only
+    /// returned when parsing strictly.
+    InvalidTargetBackslash,
+
+    /// A line of the request head is longer than `limit`, see
+    /// [`HttpServer::max_header_line_length`](crate::HttpServer::max_header_line_length).
+    HeaderLineTooLong {
+        limit: usize,
+    },
+
+    /// The request head could not be read in full, never [`ReadOutcome::Complete`].
+    Incomplete(ReadOutcome),
 
     Io(std::io::Error),
     ParseInt(std::num::ParseIntError),
 }
 
+impl HttpError {
+    /// The status of the response to a request that failed with the error.
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::ParseUnknownMethod => StatusCode::NOT_IMPLEMENTED,
+            Self::ParseUnknownVersion | Self::Http2Preface => {
+                StatusCode::HTTP_VERSION_NOT_SUPPORTED
+            }
+            Self::Incomplete(ReadOutcome::TimedOut) => StatusCode::REQUEST_TIMEOUT,
+            Self::Incomplete(ReadOutcome::TooLarge) | Self::HeaderLineTooLong { .. } => {
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
+            Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// The response to a request that failed with the error, with [`HttpError::status`] and the
+    /// error's message as the body.
+    ///
+    /// The message never contains any of the request, so this can not fail, however the request
+    /// was malformed.
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse::new(self.status()).body(self.to_string())
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRequest => f.write_str("HTTP request is invalid"),
+            Self::ParseMissingMeta => f.write_str("HTTP request is missing the request line"),
+            Self::ParseMetaMissingMethod => f.write_str("HTTP request line is missing the method"),
+            Self::ParseMetaMissingUri => f.write_str("HTTP request line is missing the target"),
+            Self::ParseMetaMissingVersion => {
+                f.write_str("HTTP request line is missing the version")
+            }
+            Self::ParseUnknownMethod => f.write_str("HTTP request method is not supported"),
+            Self::ParseUnknownVersion => f.write_str("HTTP request version is not supported"),
+            Self::Http2Preface => f.write_str("HTTP/2 is not supported, use HTTP/1.1"),
+            Self::ParseMetaWhitespace => f.write_str("HTTP request line contains extra whitespace"),
+            Self::ParseHeaderWhitespace => {
+                f.write_str("HTTP request header contains whitespace before the colon")
+            }
+            Self::ParseHeaderObsoleteFold => {
+                f.write_str("HTTP request header uses obsolete line folding")
+            }
+            Self::ParseHeaderMissingColon => f.write_str("HTTP request header is missing a colon"),
+            Self::InvalidHeaderEncoding { offset } => write!(
+                f,
+                "HTTP request header contains invalid UTF-8 at offset {}",
+                offset
+            ),
+            Self::InvalidMediaType => f.write_str("HTTP header is not a valid media type"),
+            Self::InvalidContentLength => f.write_str("HTTP `Content-Length` is not a number"),
+            Self::ConflictingContentLength => {
+                f.write_str("HTTP request has conflicting `Content-Length` values")
+            }
+            Self::InvalidHeaderName => f.write_str("HTTP header name is not a token"),
+            Self::InvalidHeaderValue => {
+                f.write_str("HTTP header value contains a line break or null byte")
+            }
+            Self::InvalidPercentEncoding { offset } => {
+                write!(f, "invalid percent encoding at offset {}", offset)
+            }
+            Self::InvalidTargetControl => {
+                f.write_str("HTTP request target contains a control character")
+            }
+            Self::InvalidTargetBackslash => f.write_str("HTTP request target contains a backslash"),
+            Self::HeaderLineTooLong { limit } => write!(
+                f,
+                "HTTP request head has a line longer than the limit of {} bytes",
+                limit
+            ),
+            Self::Incomplete(ReadOutcome::TimedOut) => {
+                f.write_str("HTTP request was sent too slowly")
+            }
+            Self::Incomplete(ReadOutcome::TooLarge) => write!(
+                f,
+                "HTTP request head is larger than the limit of {} bytes",
+                MAX_BYTES
+            ),
+            Self::Incomplete(ReadOutcome::Eof | ReadOutcome::Complete) => {
+                f.write_str("HTTP request head ended early")
+            }
+            Self::Io(err) => write!(f, "unable to read the HTTP request: {}", err),
+            Self::ParseInt(err) => write!(f, "HTTP request contains an invalid number: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::ParseInt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// How reading a request head ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOutcome {
+    /// The whole head was read.
+    Complete,
+    /// The client closed the connection before the end of the head.
+    Eof,
+    /// The read timed out before the end of the head.
+    TimedOut,
+    /// The head is larger than the server reads.
+    TooLarge,
+}
+
 impl const From<std::io::Error> for HttpError {
     fn from(v: std::io::Error) -> Self {
         Self::Io(v)
@@ -51,11 +232,50 @@ impl const From<std::num::ParseIntError> for HttpError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HttpBody {
     None,
     Bytes(&'static [u8]),
     Vector(Vec<u8>),
+    /// See [`HttpBody::stream`].
+    Stream(BodyProducer),
+}
+
+impl HttpBody {
+    /// A body that is produced a chunk at a time while it is written, the next chunk is only
+    /// pulled once the previous one has been written, so a slow client holds back the producer
+    /// rather than the body piling up in memory.
+    ///
+    /// Streamed bodies are sent chunked to HTTP/1.1 clients and end the connection for
+    /// HTTP/1.0 clients, they are never compressed.
+    pub fn stream<I>(chunks: I) -> Self
+    where
+        I: Iterator<Item = Vec<u8>> + Send + 'static,
+    {
+        Self::Stream(BodyProducer {
+            chunks: Arc::new(Mutex::new(box chunks)),
+        })
+    }
+}
+
+/// The chunks of a streamed body, see [`HttpBody::stream`].
+///
+/// Clones share the same chunks.
+#[derive(Clone)]
+pub struct BodyProducer {
+    chunks: Arc<Mutex<Box<dyn Iterator<Item = Vec<u8>> + Send>>>,
+}
+
+impl fmt::Debug for BodyProducer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyProducer").finish_non_exhaustive()
+    }
+}
+
+impl cmp::PartialEq for BodyProducer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.chunks, &other.chunks)
+    }
 }
 
 impl const From<&'static str> for HttpBody {
@@ -95,6 +315,40 @@ pub enum HttpMethod {
     Patch,
 }
 
+impl HttpMethod {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Connect => "CONNECT",
+            Self::Options => "OPTIONS",
+            Self::Trace => "TRACE",
+            Self::Patch => "PATCH",
+        }
+    }
+
+    /// If the method only retrieves data (RFC 7231 section 4.2.1), `GET`, `HEAD`, `OPTIONS`,
+    /// and `TRACE`.
+    pub const fn is_safe(&self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace)
+    }
+
+    /// If repeating the request has the same effect as sending it once (RFC 7231 section
+    /// 4.2.2), the safe methods along with `PUT` and `DELETE`.
+    pub const fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::Put | Self::Delete)
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl FromStr for HttpMethod {
     type Err = HttpError;
 
@@ -114,6 +368,14 @@ impl FromStr for HttpMethod {
     }
 }
 
+impl TryFrom<&str> for HttpMethod {
+    type Error = HttpError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::from_str(value)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HttpVersion {
     Http09,
@@ -145,6 +407,8 @@ impl FromStr for HttpVersion {
 }
 
 pub type HttpHeaders = ArrayMap<headers::HttpHeaderName, String, 32>;
+/// Path and query parameters, path parameters are in the order they appear in the route's
+/// pattern.
 pub type HttpParams = ArrayMap<String, String, 32>;
 
 pub struct HttpRequest2 {
@@ -340,6 +604,11 @@ impl cmp::PartialEq for HttpRequest2 {
 #[derive(Debug, PartialEq)]
 pub struct HttpHeaderData {
     pub method: HttpMethod,
+    /// The scheme of an absolute-form request target, `http` or `https`.
+    pub scheme: Option<&'static str>,
+    /// The authority of an absolute-form request target (`GET http://example.com/ HTTP/1.1`),
+    /// without any user info.
+    pub authority: Option<String>,
     pub url: String,
     pub query: String,
     pub query_params: HttpParams,
@@ -350,17 +619,237 @@ pub struct HttpHeaderData {
 pub struct HttpRequest {
     pub header_data: HttpHeaderData,
     pub body: Vec<u8>,
+    /// The unread body, set when the server streams request bodies or the body was too large to
+    /// read along with the head.
+    pub payload: Option<Payload>,
 
     pub params: HttpParams,
+    /// The path of the route that matched the request (ex. `/users/:id`), `None` if the default
+    /// service is handling it.
+    pub pattern: Option<Arc<str>>,
 
     pub data: Arc<Extensions>,
 
     pub extensions: Extensions,
+
+    /// The address of the client, or of the proxy in front of it, `None` if it is not known.
+    pub peer_addr: Option<SocketAddr>,
+
+    pub(crate) typed: TypedHeaders,
+}
+
+/// Header values that are parsed on first use.
+#[derive(Default)]
+pub(crate) struct TypedHeaders {
+    content_type: OnceCell<Option<MediaType>>,
+    content_length: OnceCell<Result<Option<u64>, ()>>,
+}
+
+impl HttpRequest {
+    /// Starts building a request, to call a handler without a server, see
+    /// [`HttpRequestBuilder`].
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder::new()
+    }
+
+    /// The query string as it was sent, without the `?`, empty if there is none.
+    pub fn query_string(&self) -> &str {
+        self.header_data.query.trim_start_matches('?')
+    }
+
+    /// Reconstructs the URL the client requested, from the `Host` header (or the authority of
+    /// an absolute-form target), the path, and the query string.
+    ///
+    /// The scheme is `http` unless `X-Forwarded-Proto` says `https`, which clients can send
+    /// too, so only rely on it behind a proxy that sets it. Requests without a host get
+    /// `localhost`.
+    pub fn url(&self) -> String {
+        let headers = &self.header_data.headers;
+
+        let scheme = match headers
+            .get(&headers::X_FORWARDED_PROTO)
+            .and_then(|proto| proto.split(',').next())
+        {
+            Some(proto) if proto.trim().eq_ignore_ascii_case("https") => "https",
+            _ => "http",
+        };
+
+        let host = headers
+            .get(&headers::HOST)
+            .or_else(|| self.header_data.authority.as_ref())
+            .map(String::as_str)
+            .unwrap_or("localhost");
+
+        let query = self.query_string();
+
+        if query.is_empty() {
+            format!("{}://{}{}", scheme, host, self.header_data.url)
+        } else {
+            format!("{}://{}{}?{}", scheme, host, self.header_data.url, query)
+        }
+    }
+
+    /// Returns the app data (see [`App::data`](crate::App::data)) with the type id, for
+    /// middleware that is generic over the data it uses.
+    ///
+    /// Data is stored as a [`web::Data`](crate::web::Data), so the id of `T`'s data is
+    /// `TypeId::of::<web::Data<T>>()`, and that is what the value downcasts to.
+    pub fn data_raw(&self, id: TypeId) -> Option<&(dyn Any + Send + Sync)> {
+        self.data.get_raw(id)
+    }
+
+    /// Picks the language from `supported` that the client prefers, going by the quality values
+    /// of its `Accept-Language` header, `supported[0]` if the header is missing or has none of
+    /// them.
+    ///
+    /// A range matches the languages it is a prefix of, and the other way around, so `en`
+    /// matches `en-US` and `en-US` matches `en`. Ties go to the range listed first.
+    ///
+    /// # Panics
+    ///
+    /// If `supported` is empty.
+    pub fn preferred_language<'l>(&self, supported: &[&'l str]) -> &'l str {
+        fn matches(range: &str, language: &str) -> bool {
+            let (short, long) = if range.len() <= language.len() {
+                (range, language)
+            } else {
+                (language, range)
+            };
+
+            long.get(..short.len())
+                .map_or(false, |prefix| prefix.eq_ignore_ascii_case(short))
+                && matches!(long.as_bytes().get(short.len()), None | Some(b'-'))
+        }
+
+        let ranges = self
+            .header_data
+            .headers
+            .get(&headers::ACCEPT_LANGUAGE)
+            .map(|header| {
+                header
+                    .split(',')
+                    .filter_map(|item| {
+                        let mut parts = item.split(';');
+                        let range = parts.next()?.trim();
+
+                        let quality = parts
+                            .find_map(|param| param.trim().strip_prefix("q="))
+                            .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+
+                        (!range.is_empty()).then(|| (range, quality))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut best = (supported[0], 0.0, usize::MAX);
+
+        for language in supported {
+            // The highest quality of the ranges that match, and the first range with it, `*`
+            // only counts for languages no other range matches.
+            let best_of = |wildcard: bool| {
+                ranges
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (range, _))| {
+                        if wildcard {
+                            *range == "*"
+                        } else {
+                            matches(range, language)
+                        }
+                    })
+                    .fold(
+                        None,
+                        |found: Option<(f32, usize)>, (index, (_, quality))| match found {
+                            Some((best, _)) if best >= *quality => found,
+                            _ => Some((*quality, index)),
+                        },
+                    )
+            };
+
+            let found = best_of(false).or_else(|| best_of(true));
+
+            if let Some((quality, index)) = found {
+                let better = match quality.partial_cmp(&best.1) {
+                    Some(cmp::Ordering::Greater) => true,
+                    Some(cmp::Ordering::Equal) => quality > 0.0 && index < best.2,
+                    _ => false,
+                };
+
+                if better {
+                    best = (language, quality, index);
+                }
+            }
+        }
+
+        best.0
+    }
+
+    /// The request's parsed `Content-Type`, `None` if it is missing or malformed.
+    pub fn content_type(&self) -> Option<&MediaType> {
+        self.typed
+            .content_type
+            .get_or_init(|| {
+                self.header_data
+                    .headers
+                    .get(&headers::CONTENT_TYPE)
+                    .and_then(|value| value.parse().ok())
+            })
+            .as_ref()
+    }
+
+    /// The request's `Content-Length`, `None` if it is missing.
+    ///
+    /// Returns [`HttpError::InvalidContentLength`] if the header is not a number.
+    pub fn content_length(&self) -> Result<Option<u64>, HttpError> {
+        self.typed
+            .content_length
+            .get_or_init(|| {
+                self.header_data
+                    .headers
+                    .get(&headers::CONTENT_LENGTH)
+                    .map(|value| parse_content_length(value).map_err(|_| ()))
+                    .transpose()
+            })
+            .map_err(|_| HttpError::InvalidContentLength)
+    }
+
+    /// Copies the rest of the body into `writer`, returning how many bytes were copied.
+    ///
+    /// Streamed bodies are read off of the socket as they are copied, so they never have to
+    /// fit in memory. The body's length is checked against the same limits as the body
+    /// extractors first, returning an [`InvalidData`](io::ErrorKind::InvalidData) error if it
+    /// is too large.
+    pub fn stream_body_to<W>(&mut self, writer: &mut W) -> io::Result<u64>
+    where
+        W: Write + ?Sized,
+    {
+        crate::extractor::body::check_limit(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        match &mut self.payload {
+            Some(payload) => io::copy(payload, writer),
+            None => {
+                let body = std::mem::take(&mut self.body);
+
+                writer.write_all(&body)?;
+
+                Ok(body.len() as u64)
+            }
+        }
+    }
 }
 
+/// A response, which is also its own builder, ending with [`HttpResponse::body`] or, for a
+/// response without one, [`HttpResponse::finish`].
+#[derive(Clone)]
+#[must_use = "a response does nothing unless it is returned"]
 pub struct HttpResponse {
     pub version: HttpVersion,
     pub status: StatusCode,
+    /// Replaces the status code's standard reason phrase in the status line, see
+    /// [`HttpResponse::reason`].
+    pub reason: Option<String>,
     pub headers: ArrayMap<headers::HttpHeaderName, String, 64>,
     pub body: HttpBody,
 }
@@ -371,6 +860,7 @@ impl HttpResponse {
         Self {
             version: HttpVersion::Http10,
             status,
+            reason: None,
             headers: ArrayMap::new(),
             body: HttpBody::None,
         }
@@ -392,258 +882,1479 @@ impl HttpResponse {
         Self::new(StatusCode::BAD_REQUEST)
     }
 
+    /// Creates a `200 OK` response with an HTML body.
+    pub fn html<B>(body: B) -> Self
+    where
+        B: Into<String>,
+    {
+        Self::ok()
+            .header(headers::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(body.into())
+    }
+
+    /// Creates a `200 OK` response with a plain text body.
+    pub fn text<B>(body: B) -> Self
+    where
+        B: Into<String>,
+    {
+        Self::ok()
+            .header(headers::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(body.into())
+    }
+
+    /// Creates a `200 OK` response with a body streamed from `receiver` (see
+    /// [`HttpBody::stream`]), each chunk written as it is received, ending once every sender has
+    /// been dropped.
+    ///
+    /// The handler can return it straight away and leave another thread to produce the body.
+    pub fn from_receiver(receiver: Receiver<Vec<u8>>) -> Self {
+        Self::ok().body(HttpBody::stream(receiver.into_iter()))
+    }
+
+    /// Creates a `200 OK` response with a body streamed from `child`'s stdout (which has to be
+    /// [piped](std::process::Stdio::piped)) as it is produced.
+    ///
+    /// The first chunk is read straight away, so a command that fails (exits with a non-zero
+    /// status) before writing anything gets a `500 Internal Server Error`. Once the output has
+    /// started the status can no longer change, a later failure is only logged. The command is
+    /// killed if the client goes away before the output ends.
+    pub fn from_command(child: Child) -> Self {
+        command::response(child)
+    }
+
+    /// Creates a `426 Upgrade Required` response asking the client to switch to `protocol`,
+    /// for example `TLS/1.2, HTTP/1.1` or `websocket`.
+    pub fn upgrade_required<P>(protocol: P) -> Self
+    where
+        P: ToString,
+    {
+        Self::new(StatusCode::UPGRADE_REQUIRED)
+            .header(headers::UPGRADE, protocol)
+            .header(headers::CONNECTION, "Upgrade")
+    }
+
+    /// Closes the connection once the response is sent, instead of keeping it open for the
+    /// client's next request.
+    pub fn close_connection(self) -> Self {
+        self.header(headers::CONNECTION, "close")
+    }
+
+    /// Creates a redirect to `location`, `status` should be one of the `3xx` codes
+    /// (ex. [`StatusCode::FOUND`] or [`StatusCode::PERMANENT_REDIRECT`]).
+    pub fn redirect<L>(status: StatusCode, location: L) -> Self
+    where
+        L: ToString,
+    {
+        Self::new(status).header(headers::LOCATION, location)
+    }
+
+    /// Creates a `308 Permanent Redirect` to `path` that keeps the request's query string, for
+    /// canonicalizing redirects (ex. `/path?x=1` to `/path/?x=1`).
+    pub fn redirect_keeping_query(req: &HttpRequest, path: &str) -> Self {
+        let query = req.query_string();
+
+        let location = if query.is_empty() {
+            path.to_string()
+        } else if path.contains('?') {
+            format!("{}&{}", path, query)
+        } else {
+            format!("{}?{}", path, query)
+        };
+
+        Self::redirect(StatusCode::PERMANENT_REDIRECT, location)
+    }
+
     pub const fn status(mut self, status: StatusCode) -> Self {
         self.status = status;
 
         self
     }
 
-    pub fn header<V>(mut self, key: headers::HttpHeaderName, value: V) -> Self
+    /// Sends `reason` in the status line instead of the status code's standard phrase (ex.
+    /// `200 Awesome` instead of `200 OK`).
+    ///
+    /// Reasons with line breaks are ignored when the response is written, as they would end
+    /// the status line early.
+    pub fn reason<R>(mut self, reason: R) -> Self
+    where
+        R: Into<String>,
+    {
+        self.reason = Some(reason.into());
+
+        self
+    }
+
+    /// Same as [`HttpResponse::insert_header`].
+    pub fn header<V>(self, key: headers::HttpHeaderName, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.insert_header(key, value)
+    }
+
+    /// Sets a header, replacing any values it already has.
+    pub fn insert_header<V>(mut self, key: headers::HttpHeaderName, value: V) -> Self
     where
         V: ToString,
     {
+        self.headers.remove_all(&key);
         self.headers.insert(key, value.to_string());
 
         self
     }
 
-    pub fn body<B>(mut self, body: B) -> HttpResponse
+    /// Adds a value for a header, keeping the ones it already has, each value is written as
+    /// its own header line (ex. for multiple `Set-Cookie`s).
+    pub fn append_header<V>(mut self, key: headers::HttpHeaderName, value: V) -> Self
     where
-        B: Into<HttpBody>,
+        V: ToString,
     {
-        self.body = body.into();
+        if self.headers.append(key, value.to_string()).is_some() {
+            log::warn!("response has too many headers, not adding another");
+        }
 
         self
     }
-}
 
-pub fn read_request<R>(reader: &mut R) -> Result<(HttpHeaderData, Vec<u8>), HttpError>
-where
-    R: Read,
-{
-    struct State {
-        data: Vec<u8>,
-        total_read: usize,
-        amount_read: usize,
-        read_buffer: [u8; BUFFER_SIZE],
+    /// Like [`HttpResponse::header`], but checks that the header can be written as is, which
+    /// should be used for values that come from user input.
+    ///
+    /// Invalid headers set with [`HttpResponse::header`] are left out when the response is
+    /// written.
+    pub fn with_header<V>(self, key: headers::HttpHeaderName, value: V) -> Result<Self, HttpError>
+    where
+        V: ToString,
+    {
+        let value = value.to_string();
+
+        if !key.is_valid() {
+            return Err(HttpError::InvalidHeaderName);
+        }
+
+        if !headers::is_valid_value(&value) {
+            return Err(HttpError::InvalidHeaderValue);
+        }
+
+        Ok(self.header(key, value))
     }
 
-    fn double_newline(bytes: &[u8]) -> bool {
-        bytes == &b"\r\n\r\n"[..]
+    /// Sets the `Cache-Control` header from the given directives.
+    pub fn cache_control(self, directives: CacheControl) -> Self {
+        self.header(headers::CACHE_CONTROL, directives)
     }
 
-    const BUFFER_SIZE: usize = 512;
-    const MAX_BYTES: usize = 1028 * 8;
+    pub fn body<B>(mut self, body: B) -> HttpResponse
+    where
+        B: Into<HttpBody>,
+    {
+        self.body = body.into();
 
-    let mut state = State {
-        data: Vec::with_capacity(512),
-        total_read: 0,
-        amount_read: BUFFER_SIZE,
-        read_buffer: [0; BUFFER_SIZE],
-    };
+        self
+    }
 
-    loop {
-        if state.amount_read == 0 {
-            break;
-        }
+    /// Ends the response without a body, replacing any it had, the other way to finish building
+    /// a response besides [`HttpResponse::body`].
+    pub fn finish(mut self) -> HttpResponse {
+        self.body = HttpBody::None;
 
-        state.amount_read = reader.read(&mut state.read_buffer)?;
+        self
+    }
 
-        if state.amount_read == 0 {
-            break;
-        }
+    /// Returns the first value of a header, ex. for middleware deciding whether to change the
+    /// response.
+    pub fn get_header(&self, key: &headers::HttpHeaderName) -> Option<&str> {
+        self.headers.get(key).map(String::as_str)
+    }
+
+    /// Returns every header, each of a header's values separately.
+    pub fn iter_headers(&self) -> impl Iterator<Item = (&headers::HttpHeaderName, &str)> {
+        self.headers
+            .iter()
+            .map(|(key, value)| (key, value.as_str()))
+    }
 
-        (state.total_read) += state.amount_read;
+    /// Returns the `Content-Type` header, unparsed.
+    pub fn content_type(&self) -> Option<&str> {
+        self.get_header(&headers::CONTENT_TYPE)
+    }
 
-        state
-            .data
-            .extend_from_slice(&state.read_buffer[..state.amount_read]);
+    /// Returns the body if it is held in memory.
+    pub fn body_bytes(&self) -> Option<&[u8]> {
+        match &self.body {
+            HttpBody::None => Some(&[]),
+            HttpBody::Bytes(bytes) => Some(bytes),
+            HttpBody::Vector(bytes) => Some(bytes),
+            HttpBody::Stream(_) => None,
+        }
+    }
 
-        (state.read_buffer) = [0; BUFFER_SIZE];
+    /// Returns a copy of the response if its body is held in memory (ex. for caching it).
+    pub fn try_clone(&self) -> Option<Self> {
+        self.body_bytes().map(|_| self.clone())
+    }
 
-        if state.data.windows(4).any(double_newline) {
-            break;
+    /// Returns the body for in place rewriting if it is held in memory, static bodies are copied
+    /// into a `Vec` first.
+    pub fn body_mut(&mut self) -> Option<&mut Vec<u8>> {
+        match &mut self.body {
+            HttpBody::None => self.body = HttpBody::Vector(Vec::new()),
+            HttpBody::Bytes(bytes) => self.body = HttpBody::Vector(bytes.to_vec()),
+            HttpBody::Vector(_) | HttpBody::Stream(_) => {}
         }
 
-        if state.total_read >= MAX_BYTES {
-            break;
+        match &mut self.body {
+            HttpBody::Vector(bytes) => Some(bytes),
+            _ => None,
         }
     }
 
-    let header_bytes = if let Some(i) = state.data.windows(4).position(double_newline) {
-        &state.data[..(i + 2)]
-    } else {
-        &state.data[..]
-    };
+    /// Replaces the body, for use where the response is only borrowed (ex. in middleware).
+    pub fn set_body<B>(&mut self, body: B)
+    where
+        B: Into<HttpBody>,
+    {
+        self.body = body.into();
+    }
+}
 
-    let header_str = String::from_utf8_lossy(header_bytes);
+impl From<&'static str> for HttpResponse {
+    fn from(body: &'static str) -> Self {
+        Self::ok().body(body)
+    }
+}
 
-    let header_data = parse_header(header_str.as_ref())?;
+impl From<String> for HttpResponse {
+    fn from(body: String) -> Self {
+        Self::ok().body(body)
+    }
+}
 
-    let (header_data, body) =
-        if let Some(header) = header_data.headers.get(&headers::CONTENT_LENGTH) {
-            let amount_of_bytes = header.trim().parse::<usize>()?;
+impl<B> From<(StatusCode, B)> for HttpResponse
+where
+    B: Into<HttpBody>,
+{
+    fn from((status, body): (StatusCode, B)) -> Self {
+        Self::new(status).body(body)
+    }
+}
 
-            let left_to_read = MAX_BYTES.saturating_sub(state.total_read);
+pub(crate) const MAX_BYTES: usize = 1028 * 8;
 
-            if amount_of_bytes >= left_to_read {
-                (header_data, vec![])
-            } else {
-                let mut body = Vec::with_capacity(left_to_read.min(amount_of_bytes));
+pub fn read_request<R>(reader: &mut R, strict: bool) -> Result<(HttpHeaderData, Vec<u8>), HttpError>
+where
+    R: Read,
+{
+    let (header_data, mut body) = read_head(reader, strict)?;
 
-                reader
-                    .by_ref()
-                    .take(amount_of_bytes as u64)
-                    .read_to_end(&mut body)?;
+    if let Some(header) = header_data.headers.get(&headers::CONTENT_LENGTH) {
+        let amount_of_bytes = header.trim().parse::<usize>()?;
 
-                (header_data, Vec::from(&body[..]))
-            }
+        if amount_of_bytes >= MAX_BYTES {
+            body.clear();
         } else {
-            (header_data, vec![])
-        };
+            read_body_to(reader, &mut body, amount_of_bytes)?;
+        }
+    } else {
+        body.clear();
+    }
 
     Ok((header_data, body))
 }
 
-pub fn parse_header(headers: &str) -> Result<HttpHeaderData, HttpError> {
-    let mut lines = headers.lines();
+/// Reads the rest of a body of `length` bytes, `body` being what was read along with the head.
+pub(crate) fn read_body_to<R>(
+    reader: &mut R,
+    body: &mut Vec<u8>,
+    length: usize,
+) -> std::io::Result<()>
+where
+    R: Read,
+{
+    if body.len() >= length {
+        body.truncate(length);
+    } else {
+        reader
+            .by_ref()
+            .take((length - body.len()) as u64)
+            .read_to_end(body)?;
+    }
 
-    let meta = lines.next().ok_or(HttpError::ParseMissingMeta)?;
+    Ok(())
+}
 
-    let (method, url, query, query_params, version) = {
-        let mut meta_parts = meta.split(' ').filter(|part| !part.is_empty());
+/// Reads a request head off of a connection without reading past its end, which would take
+/// the start of the body, or of a pipelined request, with it.
+pub(crate) struct HeadReader<'s> {
+    stream: &'s TcpStream,
+    /// How much of the `\r\n\r\n` ending the head has been read.
+    matched: usize,
+}
 
-        let method = HttpMethod::from_str(
-            meta_parts
-                .next()
-                .ok_or(HttpError::ParseMetaMissingMethod)?
-                .trim(),
-        )?;
+impl<'s> HeadReader<'s> {
+    pub(crate) const fn new(stream: &'s TcpStream) -> Self {
+        Self { stream, matched: 0 }
+    }
 
-        let url = meta_parts
-            .next()
-            .ok_or(HttpError::ParseMetaMissingUri)?
-            .trim();
+    fn advance(matched: usize, byte: u8) -> usize {
+        match (matched, byte) {
+            (0 | 2, b'\r') => matched + 1,
+            (1 | 3, b'\n') => matched + 1,
+            (_, b'\r') => 1,
+            _ => 0,
+        }
+    }
+}
 
-        let (url, query) = url.split_at(url.find('?').unwrap_or_else(|| url.len()));
+impl<'s> Read for HeadReader<'s> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.matched == 4 || buf.is_empty() {
+            return Ok(0);
+        }
 
-        let mut query_params = HttpParams::new();
+        let peeked = self.stream.peek(buf)?;
 
-        for (key, value) in
-            crate::http::encoding::form::parse(query.trim_start_matches('?').as_bytes())
-        {
-            query_params.insert(key.to_string(), value.to_string());
-        }
+        let mut matched = self.matched;
+        let mut end = peeked;
 
-        let version = HttpVersion::from_str(
-            meta_parts
-                .next()
-                .ok_or(HttpError::ParseMetaMissingVersion)?
-                .trim(),
-        )?;
+        for (i, byte) in buf[..peeked].iter().enumerate() {
+            matched = Self::advance(matched, *byte);
 
-        (
-            method,
-            url.to_string(),
-            query.to_string(),
-            query_params,
-            version,
-        )
-    };
+            if matched == 4 {
+                end = i + 1;
 
-    let headers = {
-        let mut headers = HttpHeaders::new();
-
-        for header in &mut lines {
-            if header.is_empty() {
                 break;
             }
+        }
+
+        let read = Read::read(&mut self.stream, &mut buf[..end])?;
+
+        self.matched = buf[..read]
+            .iter()
+            .fold(self.matched, |matched, byte| Self::advance(matched, *byte));
+
+        Ok(read)
+    }
+}
+
+/// Reads and parses the request head, returning it along with any body bytes that were read
+/// with it.
+///
+/// Lines are parsed as they complete, so only the current line is held rather than the whole
+/// head.
+pub fn read_head<R>(reader: &mut R, strict: bool) -> Result<(HttpHeaderData, Vec<u8>), HttpError>
+where
+    R: Read,
+{
+    read_head_with(reader, strict, &mut Vec::with_capacity(512))
+}
+
+/// Like [`read_head`], holding the current line in `line`, so its allocation can be reused
+/// across requests.
+pub fn read_head_with<R>(
+    reader: &mut R,
+    strict: bool,
+    line: &mut Vec<u8>,
+) -> Result<(HttpHeaderData, Vec<u8>), HttpError>
+where
+    R: Read,
+{
+    read_head_limited(reader, strict, line, usize::MAX)
+}
 
-            if let Some(idx) = header.find(':') {
-                let (key, value) = header.split_at(idx);
+/// Like [`read_head_with`], failing with [`HttpError::HeaderLineTooLong`] as soon as a line
+/// (including its line ending) is longer than `max_line` bytes.
+pub(crate) fn read_head_limited<R>(
+    reader: &mut R,
+    strict: bool,
+    line: &mut Vec<u8>,
+    max_line: usize,
+) -> Result<(HttpHeaderData, Vec<u8>), HttpError>
+where
+    R: Read,
+{
+    const BUFFER_SIZE: usize = 512;
 
-                headers.insert(
-                    headers::HttpHeaderName(Cow::Owned(key.trim().to_string())),
-                    value.trim_start_matches(": ").trim().to_string(),
-                );
+    line.clear();
+
+    let mut parser = HeadParser::new(strict);
+    let mut read_buffer = [0; BUFFER_SIZE];
+    let mut total_read = 0;
+    // How much of the head has been parsed, for the offset of encoding errors.
+    let mut parsed = 0;
+    let mut previous_crlf = false;
+
+    let (outcome, rest) = 'read: loop {
+        let amount_read = match reader.read(&mut read_buffer) {
+            Ok(0) => break (ReadOutcome::Eof, Vec::new()),
+            Ok(amount_read) => amount_read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            // What a read timing out looks like on unix.
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                ) =>
+            {
+                break (ReadOutcome::TimedOut, Vec::new())
             }
+            Err(err) => return Err(err.into()),
+        };
+
+        total_read += amount_read;
+
+        let mut chunk = &read_buffer[..amount_read];
+
+        while let Some(i) = chunk.iter().position(|byte| *byte == b'\n') {
+            line.extend_from_slice(&chunk[..=i]);
+            chunk = &chunk[(i + 1)..];
+
+            if line.len() > max_line {
+                return Err(HttpError::HeaderLineTooLong { limit: max_line });
+            }
+
+            // The head ends at the first `\r\n\r\n`.
+            if previous_crlf && line[..] == b"\r\n"[..] {
+                break 'read (ReadOutcome::Complete, chunk.to_vec());
+            }
+
+            let text =
+                std::str::from_utf8(line).map_err(|err| HttpError::InvalidHeaderEncoding {
+                    offset: parsed + err.valid_up_to(),
+                })?;
+
+            previous_crlf = text.ends_with("\r\n");
+            parsed += line.len();
+
+            let text = &text[..(text.len() - 1)];
+
+            parser.line(text.strip_suffix('\r').unwrap_or(text))?;
+
+            line.clear();
         }
 
-        headers
+        line.extend_from_slice(chunk);
+
+        if line.len() > max_line {
+            return Err(HttpError::HeaderLineTooLong { limit: max_line });
+        }
+
+        if total_read >= MAX_BYTES {
+            break (ReadOutcome::TooLarge, Vec::new());
+        }
     };
 
-    Ok(HttpHeaderData {
-        method,
-        url,
-        query,
-        query_params,
-        version,
-        headers,
-    })
-}
-
-pub fn write_response(
-    res: HttpResponse,
-    compress: bool,
-    stream: &mut TcpStream,
-) -> std::io::Result<()> {
-    write!(
-        stream,
-        "{} {} {}\r\n",
-        res.version,
-        res.status.0,
-        res.status.phrase()
-    )?;
+    if outcome != ReadOutcome::Complete {
+        return Err(HttpError::Incomplete(outcome));
+    }
 
-    for (key, value) in &res.headers {
-        write!(stream, "{}: {}\r\n", key, value)?;
+    Ok((parser.finish()?, rest))
+}
+
+/// Parses a request head.
+///
+/// When `strict` is set anything RFC 7230 says a server must or may reject is rejected, extra
+/// whitespace in the request line, whitespace between a header's name and colon, obsolete line
+/// folding, and header lines without a colon, as are backslashes in the target. Otherwise they
+/// are accepted, with folded lines being joined onto the previous header's value.
+///
+/// Targets with control characters (ex. `%00`) are always rejected.
+pub fn parse_header(headers: &str, strict: bool) -> Result<HttpHeaderData, HttpError> {
+    let mut parser = HeadParser::new(strict);
+
+    for line in headers.lines() {
+        parser.line(line)?;
+    }
+
+    parser.finish()
+}
+
+/// A request line's method, authority, path, query, query parameters, and version.
+type Meta = (
+    HttpMethod,
+    Option<(&'static str, String)>,
+    String,
+    String,
+    HttpParams,
+    HttpVersion,
+);
+
+/// Parses a request head a line at a time, see [`parse_header`].
+struct HeadParser {
+    strict: bool,
+    meta: Option<Meta>,
+    headers: HttpHeaders,
+    /// The last header, which folded lines are joined onto.
+    last: Option<headers::HttpHeaderName>,
+    /// Set after the empty line ending the head, lines after it are ignored.
+    ended: bool,
+}
+
+impl HeadParser {
+    fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            meta: None,
+            headers: HttpHeaders::new(),
+            last: None,
+            ended: false,
+        }
     }
 
-    fn write_bytes(
-        headers: &ArrayMap<headers::HttpHeaderName, String, 64>,
-        bytes: &[u8],
-        compress: bool,
-        stream: &mut TcpStream,
-    ) -> std::io::Result<()> {
-        let pre_compressed = {
-            match headers.get(&headers::CONTENT_ENCODING) {
-                Some(header) => matches!(header.as_str(), "deflate" | "gzip"),
-                None => false,
+    /// Parses the next line, without its line ending.
+    fn line(&mut self, line: &str) -> Result<(), HttpError> {
+        if self.ended {
+            return Ok(());
+        }
+
+        if self.meta.is_none() {
+            self.meta = Some(Self::meta(line, self.strict)?);
+
+            return Ok(());
+        }
+
+        if line.is_empty() {
+            self.ended = true;
+
+            return Ok(());
+        }
+
+        if line.starts_with(&[' ', '\t'][..]) {
+            if self.strict {
+                return Err(HttpError::ParseHeaderObsoleteFold);
+            }
+
+            let folded = line.trim();
+
+            let headers = &mut self.headers;
+
+            if let Some(value) = self.last.as_ref().and_then(|key| headers.get_mut(key)) {
+                if !folded.is_empty() {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+
+                    value.push_str(folded);
+                }
             }
+
+            return Ok(());
+        }
+
+        if let Some(idx) = line.find(':') {
+            let (key, value) = line.split_at(idx);
+
+            if self.strict && key.ends_with(&[' ', '\t'][..]) {
+                return Err(HttpError::ParseHeaderWhitespace);
+            }
+
+            let key = headers::HttpHeaderName(Cow::Owned(key.trim().to_string()));
+
+            let mut value = value.trim_start_matches(": ").trim().to_string();
+
+            // Differing lengths are a way to smuggle a request past a proxy that picked the
+            // other one, identical ones are merged.
+            if key == headers::CONTENT_LENGTH {
+                value = content_length(self.headers.get(&key).map(String::as_str), &value)?;
+            }
+
+            self.headers.insert(key.clone(), value);
+
+            self.last = Some(key);
+        } else if self.strict {
+            return Err(HttpError::ParseHeaderMissingColon);
+        }
+
+        Ok(())
+    }
+
+    fn meta(meta: &str, strict: bool) -> Result<Meta, HttpError> {
+        // The preface's first line, the rest (`SM`) follows the empty line after it.
+        if meta == "PRI * HTTP/2.0" {
+            return Err(HttpError::Http2Preface);
+        }
+
+        if strict && (meta.split(' ').count() != 3 || meta.split(' ').any(str::is_empty)) {
+            return Err(HttpError::ParseMetaWhitespace);
+        }
+
+        let mut meta_parts = meta.split(' ').filter(|part| !part.is_empty());
+
+        let method = HttpMethod::from_str(
+            meta_parts
+                .next()
+                .ok_or(HttpError::ParseMetaMissingMethod)?
+                .trim(),
+        )?;
+
+        let url = meta_parts
+            .next()
+            .ok_or(HttpError::ParseMetaMissingUri)?
+            .trim();
+
+        let (target, url) = split_absolute_form(url);
+        let url = url.as_str();
+
+        let (url, query) = url.split_at(url.find('?').unwrap_or_else(|| url.len()));
+
+        check_target(url, query, strict)?;
+
+        let query_params = parse_query(query);
+
+        let version = HttpVersion::from_str(
+            meta_parts
+                .next()
+                .ok_or(HttpError::ParseMetaMissingVersion)?
+                .trim(),
+        )?;
+
+        Ok((
+            method,
+            target,
+            url.to_string(),
+            query.to_string(),
+            query_params,
+            version,
+        ))
+    }
+
+    fn finish(self) -> Result<HttpHeaderData, HttpError> {
+        let (method, target, url, query, query_params, version) =
+            self.meta.ok_or(HttpError::ParseMissingMeta)?;
+
+        let (scheme, authority) = match target {
+            Some((scheme, authority)) => (Some(scheme), Some(authority)),
+            None => (None, None),
         };
 
-        if compress && !pre_compressed {
-            write!(stream, "Content-Encoding: deflate\r\n")?;
+        Ok(HttpHeaderData {
+            method,
+            scheme,
+            authority,
+            url,
+            query,
+            query_params,
+            version,
+            headers: self.headers,
+        })
+    }
+}
+
+/// Rejects a target with control characters, which are a way to forge log lines or sneak a null
+/// byte into a file path, and, when parsing strictly, backslashes, which some platforms treat as
+/// path separators.
+///
+/// The path is also checked once decoded, the query is not, as form values can hold any byte.
+fn check_target(path: &str, query: &str, strict: bool) -> Result<(), HttpError> {
+    let raw = path.bytes().chain(query.bytes());
+    let decoded = encoding::percent::percent_decode(path.as_bytes());
+
+    for byte in raw.chain(decoded) {
+        if byte.is_ascii_control() {
+            return Err(HttpError::InvalidTargetControl);
+        }
+
+        if strict && byte == b'\\' {
+            return Err(HttpError::InvalidTargetBackslash);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the single length of a `Content-Length` value, which can be a list (`5, 5`), and
+/// the value of an earlier `Content-Length` header if there was one, if they are all the same.
+fn content_length(previous: Option<&str>, value: &str) -> Result<String, HttpError> {
+    let mut lengths = previous
+        .into_iter()
+        .chain(value.split(','))
+        .map(parse_content_length);
+
+    let first = lengths
+        .next()
+        .unwrap_or(Err(HttpError::InvalidContentLength))?;
+
+    for length in lengths {
+        if length? != first {
+            return Err(HttpError::ConflictingContentLength);
+        }
+    }
+
+    Ok(first.to_string())
+}
+
+/// Parses a single `Content-Length` value, which can only be digits, unlike `u64::from_str`
+/// which also takes a sign (`+3`) that a proxy in front of the server may read differently.
+pub(crate) fn parse_content_length(value: &str) -> Result<u64, HttpError> {
+    let value = value.trim();
 
-            let compressed = miniz_oxide::deflate::compress_to_vec(bytes, 8);
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(HttpError::InvalidContentLength);
+    }
+
+    value.parse().map_err(|_| HttpError::InvalidContentLength)
+}
+
+/// Parses a query string, with or without its leading `?`.
+fn parse_query(query: &str) -> HttpParams {
+    let mut query_params = HttpParams::new();
+
+    // Pairs without a key (`?=1`) are skipped, like browsers do.
+    for (key, value) in encoding::form::parse(query.trim_start_matches('?').as_bytes())
+        .filter(|(key, _)| !key.is_empty())
+    {
+        query_params.insert(key.to_string(), value.to_string());
+    }
+
+    query_params
+}
+
+/// Splits an absolute-form request target into its authority and origin-form path.
+fn split_absolute_form(target: &str) -> (Option<(&'static str, String)>, String) {
+    let (scheme, rest) = match target.find("://") {
+        Some(index) if target[..index].eq_ignore_ascii_case("http") => {
+            ("http", &target[(index + 3)..])
+        }
+        Some(index) if target[..index].eq_ignore_ascii_case("https") => {
+            ("https", &target[(index + 3)..])
+        }
+        _ => return (None, target.to_string()),
+    };
+
+    let (authority, path) = rest.split_at(rest.find(&['/', '?'][..]).unwrap_or_else(|| rest.len()));
+
+    // `user:password@`, which has no place in a request target.
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
 
-            write!(stream, "Content-Length: {}\r\n", compressed.len())?;
+    (Some((scheme, authority.to_string())), path)
+}
+
+/// Writes `res` to `stream`, deflating the body if `compress` is set.
+///
+/// Compressed bodies are deflated as they are written, in `Transfer-Encoding: chunked` frames,
+/// so `compress` should only be set for HTTP/1.1 requests.
+///
+/// Streamed bodies (see [`HttpBody::stream`]) are chunked if the response's version is HTTP/1.1,
+/// otherwise they end when the connection is closed.
+pub fn write_response<W>(res: HttpResponse, compress: bool, stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    if res.status.is_bodiless() {
+        return write_bodiless_response(res, stream);
+    }
 
+    if res.body_bytes().map_or(false, |bytes| bytes.is_empty()) {
+        return write_empty_response(&res, format_args!("Content-Length: 0\r\n\r\n"), stream);
+    }
+
+    if let HttpBody::Stream(producer) = &res.body {
+        let chunked = matches!(res.version, HttpVersion::Http11);
+
+        write_head(&res, res.version, stream)?;
+
+        if chunked {
+            write!(stream, "Transfer-Encoding: chunked\r\n\r\n")?;
+        } else {
             write!(stream, "\r\n")?;
+        }
+
+        return write_streamed(producer, chunked, stream);
+    }
+
+    let pre_compressed = match res.headers.get(&headers::CONTENT_ENCODING) {
+        Some(header) => matches!(header.as_str(), "deflate" | "gzip"),
+        None => false,
+    };
+
+    let bytes = res.body_bytes().unwrap_or_default();
+
+    let compress = compress && !pre_compressed && !bytes.is_empty();
+
+    // Small bodies are sent along with the head, larger ones are not worth copying.
+    let inline = !compress && bytes.len() <= INLINE_BODY;
 
-            stream.write_all(&compressed)?;
+    let mut head = Vec::with_capacity(head_capacity(&res) + if inline { bytes.len() } else { 0 });
+
+    // Chunked framing does not exist in HTTP/1.0.
+    write_head(
+        &res,
+        if compress {
+            HttpVersion::Http11
         } else {
-            write!(stream, "Content-Length: {}\r\n", bytes.len())?;
+            res.version
+        },
+        &mut head,
+    )?;
 
-            write!(stream, "\r\n")?;
+    if compress {
+        head.extend_from_slice(b"Content-Encoding: deflate\r\nTransfer-Encoding: chunked\r\n\r\n");
+
+        stream.write_all(&head)?;
+
+        write_deflated(bytes, stream)?;
+    } else {
+        write!(head, "Content-Length: {}\r\n\r\n", bytes.len())?;
 
+        if inline {
+            head.extend_from_slice(bytes);
+
+            stream.write_all(&head)?;
+        } else {
+            stream.write_all(&head)?;
             stream.write_all(bytes)?;
         }
+    }
 
-        Ok(())
+    Ok(())
+}
+
+/// The largest body that is copied in with the response's head, so both are sent in one write.
+const INLINE_BODY: usize = 16 * 1024;
+
+/// The length of the response's head, give or take the framing headers, so writing it into a
+/// buffer only allocates once.
+fn head_capacity(res: &HttpResponse) -> usize {
+    let reason = res.reason.as_ref().map_or(32, String::len);
+
+    let headers = res
+        .headers
+        .iter()
+        .map(|(key, value)| key.0.len() + value.len() + 4)
+        .sum::<usize>();
+
+    // The version, status, and framing headers.
+    64 + reason + headers
+}
+
+/// Writes `res` as the response to a `HEAD` request, everything but the body.
+///
+/// `Content-Length` is the length of the body the handler produced, as it would be for a `GET`,
+/// or if the body is empty, the `Content-Length` the handler set itself (if any).
+pub(crate) fn write_head_response<W>(res: HttpResponse, stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    if res.status.is_bodiless() {
+        return write_bodiless_response(res, stream);
+    }
+
+    if let HttpBody::Stream(_) = &res.body {
+        write_head(&res, res.version, stream)?;
+
+        return match res.version {
+            HttpVersion::Http11 => write!(stream, "Transfer-Encoding: chunked\r\n\r\n"),
+            _ => write!(stream, "\r\n"),
+        };
+    }
+
+    let bytes = res.body_bytes().unwrap_or_default();
+
+    match res.headers.get(&headers::CONTENT_LENGTH) {
+        Some(len) if bytes.is_empty() && len.trim().parse::<u64>().is_ok() => write_empty_response(
+            &res,
+            format_args!("Content-Length: {}\r\n\r\n", len.trim()),
+            stream,
+        ),
+        _ => write_empty_response(
+            &res,
+            format_args!("Content-Length: {}\r\n\r\n", bytes.len()),
+            stream,
+        ),
+    }
+}
+
+/// Writes a response with a status that can not have a body, leaving out the body and
+/// `Content-Length`/`Transfer-Encoding`, even if the handler set them.
+fn write_bodiless_response<W>(res: HttpResponse, stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    if !res.body_bytes().unwrap_or_default().is_empty()
+        || res.headers.contains(&headers::CONTENT_LENGTH)
+        || res.headers.contains(&headers::TRANSFER_ENCODING)
+    {
+        log::warn!(
+            "`{} {}` responses can not have a body, leaving it out",
+            res.status.0,
+            res.status.phrase()
+        );
     }
 
-    match res.body {
-        HttpBody::None => {
-            write!(stream, "Content-Length: 0\r\n")?;
+    write_empty_response(&res, format_args!("\r\n"), stream)
+}
+
+/// Writes the status line, headers and `framing` of a response without a body in a single write,
+/// formatting them on the stack rather than writing each line to the socket as it is formatted.
+fn write_empty_response<W>(
+    res: &HttpResponse,
+    framing: fmt::Arguments<'_>,
+    stream: &mut W,
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let mut buffer = [0; 1024];
+    let mut rest = &mut buffer[..];
+
+    if write_head(res, res.version, &mut rest)
+        .and_then(|_| rest.write_fmt(framing))
+        .is_ok()
+    {
+        let remaining = rest.len();
+        let len = buffer.len() - remaining;
+
+        return stream.write_all(&buffer[..len]);
+    }
+
+    // Too many headers to fit, written as they are formatted instead.
+    write_head(res, res.version, stream)?;
+
+    stream.write_fmt(framing)
+}
+
+/// Writes the status line and headers, apart from the framing headers, which are up to the
+/// caller.
+fn write_head<W>(res: &HttpResponse, version: HttpVersion, stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let reason = res
+        .reason
+        .as_deref()
+        .filter(|reason| headers::is_valid_value(reason))
+        .unwrap_or_else(|| res.status.phrase());
+
+    write!(stream, "{} {} {}\r\n", version, res.status.0, reason)?;
+
+    for (key, value) in &res.headers {
+        if *key == headers::CONTENT_LENGTH
+            || (*key == headers::TRANSFER_ENCODING && res.status.is_bodiless())
+        {
+            continue;
         }
-        HttpBody::Bytes(bytes) => {
-            write_bytes(&res.headers, bytes, compress, stream)?;
+
+        if !key.is_valid() || !headers::is_valid_value(value) {
+            log::warn!("not writing invalid response header `{}: {:?}`", key, value);
+
+            continue;
         }
-        HttpBody::Vector(bytes) => {
-            write_bytes(&res.headers, bytes.as_slice(), compress, stream)?;
+
+        write!(stream, "{}: {}\r\n", key, value)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the chunks of a streamed body as they are produced.
+///
+/// Writes block while the client is behind, so the next chunk is not produced until the client
+/// has made room for it.
+fn write_streamed<W>(producer: &BodyProducer, chunked: bool, stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let mut chunks = producer
+        .chunks
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+
+    // An empty chunk would end a chunked body early.
+    for chunk in chunks.by_ref().filter(|chunk| !chunk.is_empty()) {
+        if chunked {
+            write!(stream, "{:X}\r\n", chunk.len())?;
+            stream.write_all(&chunk)?;
+            write!(stream, "\r\n")?;
+        } else {
+            stream.write_all(&chunk)?;
         }
     }
 
+    if chunked {
+        write!(stream, "0\r\n\r\n")?;
+    }
+
     Ok(())
 }
+
+/// Deflates `bytes` a chunk at a time, writing each chunk as soon as it is compressed.
+fn write_deflated<W>(mut bytes: &[u8], stream: &mut W) -> std::io::Result<()>
+where
+    W: Write,
+{
+    use miniz_oxide::{
+        deflate::{
+            core::{create_comp_flags_from_zip_params, CompressorOxide},
+            stream::deflate,
+        },
+        MZFlush, MZStatus,
+    };
+
+    let mut compressor = CompressorOxide::new(create_comp_flags_from_zip_params(8, 0, 0));
+    let mut buffer = vec![0; 8 * 1024];
+
+    loop {
+        let result = deflate(&mut compressor, bytes, &mut buffer, MZFlush::Finish);
+
+        bytes = &bytes[result.bytes_consumed..];
+
+        if result.bytes_written != 0 {
+            write!(stream, "{:X}\r\n", result.bytes_written)?;
+            stream.write_all(&buffer[..result.bytes_written])?;
+            write!(stream, "\r\n")?;
+        }
+
+        match result.status {
+            Ok(MZStatus::StreamEnd) => break,
+            Ok(_) if result.bytes_consumed != 0 || result.bytes_written != 0 => {}
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "unable to deflate the response body",
+                ))
+            }
+        }
+    }
+
+    write!(stream, "0\r\n\r\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn request(head: &str) -> HttpRequest {
+        let (header_data, body) = read_head(&mut head.as_bytes(), false).unwrap();
+
+        HttpRequest {
+            header_data,
+            body,
+            payload: None,
+            params: ArrayMap::new(),
+            pattern: None,
+            data: Arc::new(Extensions::new()),
+            extensions: Extensions::new(),
+            peer_addr: None,
+            typed: TypedHeaders::default(),
+        }
+    }
+
+    #[test]
+    fn test_method_classes() {
+        for (method, safe, idempotent) in [
+            (HttpMethod::Get, true, true),
+            (HttpMethod::Head, true, true),
+            (HttpMethod::Post, false, false),
+            (HttpMethod::Put, false, true),
+            (HttpMethod::Delete, false, true),
+            (HttpMethod::Connect, false, false),
+            (HttpMethod::Options, true, true),
+            (HttpMethod::Trace, true, true),
+            (HttpMethod::Patch, false, false),
+        ] {
+            assert_eq!(method.is_safe(), safe, "{}", method);
+            assert_eq!(method.is_idempotent(), idempotent, "{}", method);
+        }
+    }
+
+    #[test]
+    fn test_error_responses() {
+        let errors = vec![
+            HttpError::InvalidRequest,
+            HttpError::ParseMissingMeta,
+            HttpError::ParseMetaMissingMethod,
+            HttpError::ParseMetaMissingUri,
+            HttpError::ParseMetaMissingVersion,
+            HttpError::ParseUnknownMethod,
+            HttpError::ParseUnknownVersion,
+            HttpError::ParseMetaWhitespace,
+            HttpError::ParseHeaderWhitespace,
+            HttpError::ParseHeaderObsoleteFold,
+            HttpError::ParseHeaderMissingColon,
+            HttpError::InvalidHeaderEncoding { offset: usize::MAX },
+            HttpError::InvalidMediaType,
+            HttpError::InvalidContentLength,
+            HttpError::InvalidHeaderName,
+            HttpError::InvalidHeaderValue,
+            HttpError::InvalidPercentEncoding { offset: 0 },
+            HttpError::Incomplete(ReadOutcome::Complete),
+            HttpError::Incomplete(ReadOutcome::Eof),
+            HttpError::Incomplete(ReadOutcome::TimedOut),
+            HttpError::Incomplete(ReadOutcome::TooLarge),
+            HttpError::Io(io::Error::new(io::ErrorKind::Other, "\u{fffd}\0\r\n")),
+            HttpError::ParseInt("\u{fffd}".parse::<u64>().unwrap_err()),
+        ];
+
+        for err in errors {
+            assert_response(&err);
+        }
+
+        // Pseudo-random heads, some with a valid request line in front.
+        let mut seed = 7u32;
+
+        for i in 0..512 {
+            let mut head = if i % 2 == 0 {
+                b"GET / HTTP/1.1\r\n".to_vec()
+            } else {
+                Vec::new()
+            };
+
+            head.extend((0..(i % 64 + 1)).map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+
+                (seed >> 16) as u8
+            }));
+            head.extend_from_slice(b"\r\n\r\n");
+
+            for strict in [false, true] {
+                if let Err(err) = read_head(&mut &head[..], strict) {
+                    assert_response(&err);
+                }
+            }
+        }
+    }
+
+    fn assert_response(err: &HttpError) {
+        let res = err.to_response();
+
+        assert!(res.status.0 >= 400, "{:?}", err);
+        assert!(!res.body_bytes().unwrap().is_empty(), "{:?}", err);
+
+        let mut written = Vec::new();
+
+        write_response(res, false, &mut written).unwrap();
+
+        assert!(
+            std::str::from_utf8(&written).is_ok(),
+            "{:?}: {:?}",
+            err,
+            written
+        );
+    }
+
+    #[test]
+    fn test_preferred_language() {
+        let supported = ["en", "fr", "de"];
+
+        let preferred = |header: &str| {
+            request(&format!(
+                "GET / HTTP/1.1\r\nAccept-Language: {}\r\n\r\n",
+                header
+            ))
+            .preferred_language(&supported)
+        };
+
+        assert_eq!(preferred("fr;q=0.5, de;q=0.9, en;q=0.1"), "de");
+        assert_eq!(preferred("es, fr-CA;q=0.8, en;q=0.7"), "fr");
+        assert_eq!(preferred("de, fr"), "de");
+        assert_eq!(preferred("es, *;q=0.1"), "en");
+        assert_eq!(preferred("es, *;q=0.1, fr;q=0.5"), "fr");
+        assert_eq!(preferred("*, en;q=0"), "fr");
+        assert_eq!(preferred("es, it"), "en");
+
+        assert_eq!(
+            request("GET / HTTP/1.1\r\n\r\n").preferred_language(&supported),
+            "en"
+        );
+    }
+
+    #[test]
+    fn test_write_empty_response() {
+        struct Writes(Vec<u8>, usize);
+
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.1 += 1;
+
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut written = Writes(Vec::new(), 0);
+
+        write_response(
+            HttpResponse::new(StatusCode::NO_CONTENT),
+            false,
+            &mut written,
+        )
+        .unwrap();
+
+        assert_eq!(written.0, b"HTTP/1.0 204 No Content\r\n\r\n");
+        assert_eq!(written.1, 1);
+
+        let mut written = Writes(Vec::new(), 0);
+
+        write_response(
+            HttpResponse::new(StatusCode::FOUND).header(headers::LOCATION, "/login"),
+            false,
+            &mut written,
+        )
+        .unwrap();
+
+        assert_eq!(
+            written.0,
+            &b"HTTP/1.0 302 Found\r\nLocation: /login\r\nContent-Length: 0\r\n\r\n"[..]
+        );
+        assert_eq!(written.1, 1);
+
+        let mut written = Writes(Vec::new(), 0);
+        let long = "a".repeat(2048);
+
+        write_response(
+            HttpResponse::new(StatusCode::NO_CONTENT).header(headers::SERVER, &long),
+            false,
+            &mut written,
+        )
+        .unwrap();
+
+        assert_eq!(
+            written.0,
+            format!("HTTP/1.0 204 No Content\r\nServer: {}\r\n\r\n", long).as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_write_response_bytes() {
+        // What writing the head as it is formatted produces.
+        fn expected(res: &HttpResponse, body: &[u8]) -> Vec<u8> {
+            let mut expected = format!(
+                "{} {} {}\r\n",
+                res.version,
+                res.status.0,
+                res.reason.as_deref().unwrap_or_else(|| res.status.phrase())
+            );
+
+            for (key, value) in &res.headers {
+                expected.push_str(&format!("{}: {}\r\n", key, value));
+            }
+
+            expected.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+            let mut expected = expected.into_bytes();
+
+            expected.extend_from_slice(body);
+
+            expected
+        }
+
+        for body in [
+            vec![b'a'; 12],
+            vec![b'b'; INLINE_BODY],
+            vec![b'c'; INLINE_BODY + 1],
+        ] {
+            let mut res = HttpResponse::ok()
+                .header(headers::CONTENT_TYPE, "text/plain; charset=utf-8")
+                .header(headers::SERVER, "enrgy")
+                .reason("Fine")
+                .body(body.clone());
+
+            res.version = HttpVersion::Http11;
+
+            let mut written = Vec::new();
+
+            write_response(res.clone(), false, &mut written).unwrap();
+
+            assert_eq!(written, expected(&res, &body));
+        }
+    }
+
+    #[test]
+    fn test_read_outcome() {
+        struct Stalled<'b>(&'b [u8]);
+
+        impl<'b> Read for Stalled<'b> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(std::io::ErrorKind::WouldBlock.into());
+                }
+
+                self.0.read(buf)
+            }
+        }
+
+        assert!(read_head(
+            &mut &b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"[..],
+            false
+        )
+        .is_ok());
+
+        let outcome = |result: Result<(HttpHeaderData, Vec<u8>), HttpError>| match result {
+            Err(HttpError::Incomplete(outcome)) => Some(outcome),
+            _ => None,
+        };
+
+        assert_eq!(
+            outcome(read_head(
+                &mut &b"GET / HTTP/1.1\r\nHost: localhost\r\n"[..],
+                false
+            )),
+            Some(ReadOutcome::Eof)
+        );
+        assert_eq!(
+            outcome(read_head(
+                &mut Stalled(b"GET / HTTP/1.1\r\nHost: localhost\r\n"),
+                false
+            )),
+            Some(ReadOutcome::TimedOut)
+        );
+
+        let large = format!(
+            "GET / HTTP/1.1\r\nX-Large: {}\r\n\r\n",
+            "a".repeat(2 * MAX_BYTES)
+        );
+
+        assert_eq!(
+            outcome(read_head(&mut large.as_bytes(), false)),
+            Some(ReadOutcome::TooLarge)
+        );
+    }
+
+    #[test]
+    fn test_read_head_with() {
+        // A buffer left with a partial line from a connection that hung up.
+        let mut line = b"GET /stale HTTP/1.1\r\nX-Par".to_vec();
+
+        let (header_data, body) = read_head_with(
+            &mut &b"POST /fresh HTTP/1.1\r\nHost: localhost\r\n\r\nbody"[..],
+            false,
+            &mut line,
+        )
+        .unwrap();
+
+        assert_eq!(header_data.method, HttpMethod::Post);
+        assert_eq!(header_data.url, "/fresh");
+        assert_eq!(header_data.headers.len(), 1);
+        assert_eq!(body, b"body");
+    }
+
+    #[test]
+    fn test_method_parse() {
+        for method in [
+            HttpMethod::Get,
+            HttpMethod::Head,
+            HttpMethod::Post,
+            HttpMethod::Put,
+            HttpMethod::Delete,
+            HttpMethod::Connect,
+            HttpMethod::Options,
+            HttpMethod::Trace,
+            HttpMethod::Patch,
+        ] {
+            assert_eq!(method.as_str().parse::<HttpMethod>().unwrap(), method);
+            assert_eq!(HttpMethod::try_from(method.as_str()).unwrap(), method);
+        }
+
+        for token in ["get", "Get", "", "GET ", "BREW"] {
+            assert!(
+                matches!(
+                    HttpMethod::try_from(token),
+                    Err(HttpError::ParseUnknownMethod)
+                ),
+                "{:?}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn test_status_class() {
+        for (status, class) in [
+            (StatusCode::CONTINUE, StatusClass::Informational),
+            (StatusCode::NO_CONTENT, StatusClass::Success),
+            (StatusCode::MOVED_PERMANENTLY, StatusClass::Redirection),
+            (StatusCode::NOT_FOUND, StatusClass::ClientError),
+            (StatusCode::INTERNAL_SERVER_ERROR, StatusClass::ServerError),
+        ] {
+            assert_eq!(status.class(), class, "{}", status.0);
+        }
+    }
+
+    #[test]
+    fn test_bodiless_status() {
+        for (status, bodiless) in [
+            (StatusCode::CONTINUE, true),
+            (StatusCode::OK, false),
+            (StatusCode::NO_CONTENT, true),
+            (StatusCode::NOT_MODIFIED, true),
+            (StatusCode::NOT_FOUND, false),
+        ] {
+            assert_eq!(status.is_bodiless(), bodiless, "{}", status.0);
+        }
+    }
+
+    #[test]
+    fn test_content_length() {
+        let req = request("POST / HTTP/1.1\r\nContent-Length: 42\r\n\r\n");
+
+        assert_eq!(req.content_length().unwrap(), Some(42));
+        assert_eq!(req.content_length().unwrap(), Some(42));
+
+        let req = request("POST / HTTP/1.1\r\n\r\n");
+
+        assert_eq!(req.content_length().unwrap(), None);
+
+        for value in ["lots", "+3", "-1", "3 4", ""] {
+            let req = HttpRequest::builder()
+                .header(headers::CONTENT_LENGTH, value)
+                .build();
+
+            assert!(
+                matches!(req.content_length(), Err(HttpError::InvalidContentLength)),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_content_length() {
+        for value in ["+3", "-1", "abc", "3 4", "", "5, +5", "5, "] {
+            let head = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", value);
+
+            assert!(
+                matches!(
+                    read_head(&mut head.as_bytes(), false),
+                    Err(HttpError::InvalidContentLength)
+                ),
+                "{:?}",
+                value
+            );
+        }
+
+        let (header_data, _) = read_head(
+            &mut &b"POST / HTTP/1.1\r\nContent-Length: 05, 5\r\n\r\n"[..],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            header_data.headers.get(&headers::CONTENT_LENGTH).unwrap(),
+            "5"
+        );
+    }
+
+    #[test]
+    fn test_content_type() {
+        let req = request("POST / HTTP/1.1\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n");
+
+        assert_eq!(req.content_type().unwrap().essence(), "text/plain");
+        assert_eq!(req.content_type().unwrap().param("charset"), Some("utf-8"));
+
+        let req = request("POST / HTTP/1.1\r\n\r\n");
+
+        assert!(req.content_type().is_none());
+
+        let req = request("POST / HTTP/1.1\r\nContent-Type: text\r\n\r\n");
+
+        assert!(req.content_type().is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    io::{self, Cursor, Read},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// A request body that has not been read yet, reading from it pulls the body off of the socket.
+///
+/// Only created when the server is set to stream request bodies, see
+/// [`HttpServer::stream_bodies`](crate::HttpServer::stream_bodies).
+pub struct Payload {
+    /// Body bytes that were read along with the request head.
+    buffered: Cursor<Vec<u8>>,
+    stream: TcpStream,
+    /// Bytes that are still to be read from `stream`.
+    remaining: u64,
+    length: u64,
+    limit: u64,
+    deadline: Option<Instant>,
+}
+
+impl Payload {
+    pub(crate) fn new(
+        mut buffered: Vec<u8>,
+        stream: TcpStream,
+        length: u64,
+        limit: u64,
+        timeout: Option<Duration>,
+    ) -> Self {
+        buffered.truncate(length as usize);
+
+        Self {
+            remaining: length - buffered.len() as u64,
+            buffered: Cursor::new(buffered),
+            stream,
+            length,
+            limit,
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// The length of the body, as stated by the request's `Content-Length`.
+    pub const fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The maximum body size the server accepts.
+    pub const fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// How much of the body is still to be read off of the socket.
+    pub(crate) const fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns `true` if the body is larger than the server's limit.
+    pub const fn exceeds_limit(&self) -> bool {
+        self.length > self.limit
+    }
+
+    /// Reads and discards what is left of the body, returning `false` if it could not be read
+    /// (in which case the connection should not be reused).
+    pub(crate) fn drain(&mut self) -> bool {
+        if self.exceeds_limit() {
+            return false;
+        }
+
+        io::copy(self, &mut io::sink()).is_ok() && self.remaining == 0
+    }
+}
+
+impl Read for Payload {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.exceeds_limit() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "request body is larger than the server's limit",
+            ));
+        }
+
+        if (self.buffered.position() as usize) < self.buffered.get_ref().len() {
+            return self.buffered.read(buf);
+        }
+
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(deadline) = self.deadline {
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out reading the request body",
+                ));
+            }
+
+            self.stream.set_read_timeout(Some(deadline - now))?;
+        }
+
+        let max = (buf.len() as u64).min(self.remaining) as usize;
+        let read = self.stream.read(&mut buf[..max])?;
+
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed before the request body was read",
+            ));
+        }
+
+        self.remaining -= read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Drop for Payload {
+    fn drop(&mut self) {
+        // The clone shares its socket options with the connection.
+        if self.deadline.is_some() {
+            let _ = self.stream.set_read_timeout(None);
+        }
+    }
+}
@@ -88,6 +88,41 @@ impl_status! {
     NETWORK_AUTHENTICATION_REQUIRED => [511, "Network Authentication Required"],
 }
 
+/// The class of a status code, its first digit (RFC 7231 section 6).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusClass {
+    /// `1xx`
+    Informational,
+    /// `2xx`
+    Success,
+    /// `3xx`
+    Redirection,
+    /// `4xx`
+    ClientError,
+    /// `5xx`
+    ServerError,
+}
+
+impl StatusCode {
+    /// Returns the status' class, codes outside of `100` to `599` (which are not valid) are
+    /// put in the nearest class.
+    pub const fn class(&self) -> StatusClass {
+        match self.0 {
+            0..=199 => StatusClass::Informational,
+            200..=299 => StatusClass::Success,
+            300..=399 => StatusClass::Redirection,
+            400..=499 => StatusClass::ClientError,
+            _ => StatusClass::ServerError,
+        }
+    }
+
+    /// Whether responses with this status can not have a body (`1xx`, `204 No Content`, and
+    /// `304 Not Modified`), RFC 7230 section 3.3.3.
+    pub const fn is_bodiless(&self) -> bool {
+        matches!(self.0, 100..=199 | 204 | 304)
+    }
+}
+
 macro_rules! impl_status_from {
     ($( $num:ident )*) => {
         $(
@@ -1,6 +1,7 @@
 #![allow(incomplete_features)]
 #![warn(nonstandard_style, rust_2018_idioms, future_incompatible)]
 #![feature(
+    associated_type_defaults,
     box_syntax,
     const_btree_new,
     const_fn_trait_bound,
@@ -13,6 +14,7 @@
     const_slice_from_raw_parts,
     const_trait_impl,
     decl_macro,
+    once_cell,
     option_result_unwrap_unchecked,
     slice_ptr_get
 )]
@@ -21,38 +23,63 @@ mod extractor;
 mod utils;
 
 mod app;
+mod clock;
 mod extensions;
+mod files;
 mod handler;
 mod responder;
 mod route;
 mod server;
 mod service;
+mod template;
 
 pub mod http;
 
 pub mod error;
 pub mod middleware;
 
-pub use crate::{app::App, responder::Responder, server::HttpServer};
+pub use crate::{
+    app::App,
+    clock::{Clock, MockClock, SystemClock},
+    responder::Responder,
+    server::{
+        HostMismatch, HttpServer, RequestSummary, RunError, RunSummary, ServerHandle, Unbound,
+    },
+    utils::thread_pool::PoolStats,
+};
 
 #[doc(inline)]
 pub use crate::error::Error;
 
 pub mod dev {
     pub use crate::{
+        app::{BuiltApp, RouteInfo},
         extensions::Extensions,
-        service::{BoxedService, Service},
+        service::{wrap_fn, BoxedService, Service, WrapFn},
+        utils::path_tree::PathTree,
     };
 }
 
 pub mod web {
     pub use crate::{
         extractor::{
-            Body, Data, Header, OptionalHeader, OptionalParam, OptionalQuery, Param, ParseHeader,
-            ParseParam, ParseQuery, Query, RawQuery,
+            Accept, Body, BodyStream, BufferedField, Bytes, Conditionals, Data, DefaultQuery,
+            DeserializeError, EntityTag, EntityTags, Fields, Form, FormList, Header, Multipart,
+            MultipartField, OptionalHeader, OptionalParam, OptionalQuery, Param, ParseForm,
+            ParseHeader, ParseParam, ParseQuery, Path, PayloadConfig, Query, QueryConfig, RawQuery,
+            SavedField, SecurePath, SecurePathConfig, Session, SessionData, Signed, SignedConfig,
+            Text,
         },
-        route::{connect, delete, get, head, options, patch, post, put, to, trace},
+        files::Files,
+        middleware::CsrfToken,
+        route::{
+            any, connect, delete, get, head, options, patch, post, put, to, trace, RouteToken,
+        },
+        template::{Template, TemplateEngine},
     };
+
+    #[cfg(feature = "json")]
+    pub use crate::extractor::{Json, JsonConfig, JsonRef};
 }
 
 // A module for testing different route handlers.
@@ -97,6 +124,46 @@ mod test_compile {
         App::new().service(web::get("/:name").to(index));
     }
 
+    #[test]
+    fn test_status_tuple() {
+        fn index() -> (http::StatusCode, &'static str) {
+            (http::StatusCode::NOT_FOUND, "Not Found")
+        }
+
+        App::new().service(web::get("/").to(index));
+    }
+
+    #[test]
+    fn test_routes() {
+        use crate::{dev::RouteInfo, http::HttpMethod};
+
+        let routes = App::new()
+            .service(web::get("/").to(|| "Hello World!"))
+            .service(web::post("/users").to(|| "Created"))
+            .service(web::get("/users").to(|| "Users"))
+            .service(web::delete("/users/:id").to(|| "Deleted"))
+            .service(web::any("/users/:id").to(|| "Any"))
+            .mount("/api", App::new().service(web::get("/status").to(|| "Ok")))
+            .build()
+            .routes();
+
+        let route = |pattern: &str, methods: &[HttpMethod]| RouteInfo {
+            pattern: pattern.to_string(),
+            methods: methods.to_vec(),
+        };
+
+        assert_eq!(
+            routes,
+            [
+                route("/", &[HttpMethod::Get]),
+                route("/api/status", &[HttpMethod::Get]),
+                route("/users", &[HttpMethod::Get, HttpMethod::Post]),
+                route("/users/:id", &[HttpMethod::Delete]),
+                route("/users/:id", &[]),
+            ]
+        );
+    }
+
     #[derive(Debug)]
     struct TestError {}
 
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use crate::{
+    http::{
+        headers::{
+            ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS,
+            ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+        },
+        HttpMethod, HttpRequest, HttpResponse, StatusCode,
+    },
+    middleware::Middleware,
+};
+
+/// Adds CORS headers to responses for allowed origins, and answers preflight requests
+/// (`OPTIONS` requests with an `Access-Control-Request-Method`) with `204 No Content`.
+///
+/// Preflight requests are answered without calling the route.
+pub struct Cors {
+    /// Empty allows any origin.
+    origins: Vec<String>,
+    methods: Vec<HttpMethod>,
+    max_age: Option<Duration>,
+}
+
+/// A request from an allowed origin.
+pub struct CorsRequest {
+    origin: String,
+    preflight: bool,
+    headers: Option<String>,
+}
+
+impl Cors {
+    /// Allows any origin to use `GET`, `HEAD`, and `POST`.
+    pub const fn new() -> Self {
+        Self {
+            origins: Vec::new(),
+            methods: Vec::new(),
+            max_age: None,
+        }
+    }
+
+    /// Only allows the given origins, this can be called multiple times.
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.to_string());
+
+        self
+    }
+
+    pub fn allow_methods(mut self, methods: &[HttpMethod]) -> Self {
+        self.methods.extend_from_slice(methods);
+
+        self
+    }
+
+    /// How long browsers can cache preflight responses for, sent in `Access-Control-Max-Age`.
+    ///
+    /// A zero duration leaves the header out, as does not setting it.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+
+        self
+    }
+
+    fn allowed(&self, origin: &str) -> bool {
+        self.origins.is_empty() || self.origins.iter().any(|allowed| allowed == origin)
+    }
+
+    fn methods(&self) -> String {
+        if self.methods.is_empty() {
+            return "GET, HEAD, POST".to_string();
+        }
+
+        self.methods
+            .iter()
+            .map(HttpMethod::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl const Default for Cors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for Cors {
+    type Context = Option<CorsRequest>;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        let headers = &req.header_data.headers;
+
+        let origin = headers.get(&ORIGIN).filter(|origin| self.allowed(origin))?;
+
+        Some(CorsRequest {
+            origin: origin.clone(),
+            preflight: req.header_data.method == HttpMethod::Options
+                && headers.contains(&ACCESS_CONTROL_REQUEST_METHOD),
+            headers: headers.get(&ACCESS_CONTROL_REQUEST_HEADERS).cloned(),
+        })
+    }
+
+    fn respond(&self, _req: &HttpRequest, ctx: &Self::Context) -> Option<HttpResponse> {
+        let ctx = ctx.as_ref().filter(|ctx| ctx.preflight)?;
+
+        let mut res = HttpResponse::new(StatusCode::NO_CONTENT)
+            .header(ACCESS_CONTROL_ALLOW_METHODS, self.methods());
+
+        if let Some(headers) = &ctx.headers {
+            res = res.header(ACCESS_CONTROL_ALLOW_HEADERS, headers.clone());
+        }
+
+        Some(match self.max_age {
+            Some(max_age) if max_age.as_secs() > 0 => {
+                res.header(ACCESS_CONTROL_MAX_AGE, max_age.as_secs())
+            }
+            _ => res,
+        })
+    }
+
+    fn after(&self, _req: &HttpRequest, mut res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+        let ctx = match ctx {
+            Some(ctx) => ctx,
+            None => return res,
+        };
+
+        res.headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, ctx.origin);
+
+        // The response depends on the origin whatever else it varies on.
+        let vary = match res.headers.get(&VARY) {
+            Some(vary)
+                if vary
+                    .split(',')
+                    .map(str::trim)
+                    .any(|name| name == "*" || name.eq_ignore_ascii_case("Origin")) =>
+            {
+                None
+            }
+            Some(vary) => Some(format!("{}, Origin", vary)),
+            None => Some("Origin".to_string()),
+        };
+
+        if let Some(vary) = vary {
+            res.headers.insert(VARY, vary);
+        }
+
+        res
+    }
+}
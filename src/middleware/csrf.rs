@@ -0,0 +1,209 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    ops::Deref,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::InternalError,
+    extractor::Extractor,
+    http::{
+        encoding::form,
+        headers::{HttpHeaderName, COOKIE, SET_COOKIE},
+        HttpRequest, HttpResponse, StatusCode,
+    },
+    middleware::Middleware,
+    utils::hmac,
+    Error,
+};
+
+const COOKIE_NAME: &str = "csrf_token";
+const FIELD_NAME: &str = "csrf_token";
+const HEADER_NAME: HttpHeaderName = HttpHeaderName::new("X-CSRF-Token");
+
+/// Protects against cross-site request forgery with signed double-submit tokens.
+///
+/// Every response carries a `csrf_token` cookie (unless the client already has a valid one),
+/// and requests with a method that is not idempotent (ex. `POST`) have to send the same token
+/// back in an `X-CSRF-Token` header or a `csrf_token` form field, or they are rejected with
+/// `403 Forbidden` before reaching the route. The [`CsrfToken`] extractor gives handlers the
+/// token to put in their forms.
+///
+/// Tokens are signed with `secret`, so a client can only use tokens the server issued.
+///
+/// Form fields can not be checked when the server streams request bodies, only the header.
+pub struct Csrf {
+    secret: Vec<u8>,
+}
+
+/// The token for a request, see [`Csrf`].
+pub struct CsrfRequest {
+    token: String,
+    issued: bool,
+    valid: bool,
+}
+
+impl Csrf {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+
+    fn sign(&self, nonce: &str) -> String {
+        hmac::to_hex(&hmac::hmac_sha256(&self.secret, nonce.as_bytes()))
+    }
+
+    fn issue(&self) -> String {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or_default();
+
+        let mut nonce = [0u8; 16];
+
+        for chunk in nonce.chunks_exact_mut(8) {
+            let mut hasher = RandomState::new().build_hasher();
+
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+            hasher.write_u128(time);
+
+            chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+        }
+
+        let nonce = hmac::to_hex(&nonce);
+        let signature = self.sign(&nonce);
+
+        format!("{}.{}", nonce, signature)
+    }
+
+    fn verify(&self, token: &str) -> bool {
+        match token.split_once('.') {
+            Some((nonce, signature)) => {
+                hmac::constant_time_eq(self.sign(nonce).as_bytes(), signature.as_bytes())
+            }
+            None => false,
+        }
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for Csrf {
+    type Context = CsrfRequest;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        let cookie = req
+            .header_data
+            .headers
+            .get(&COOKIE)
+            .and_then(|cookies| cookie(cookies, COOKIE_NAME))
+            .filter(|token| self.verify(token))
+            .map(str::to_string);
+
+        let valid = req.header_data.method.is_idempotent()
+            || match (&cookie, submitted(req)) {
+                (Some(cookie), Some(submitted)) => {
+                    hmac::constant_time_eq(cookie.as_bytes(), submitted.as_bytes())
+                }
+                _ => false,
+            };
+
+        let (token, issued) = match cookie {
+            Some(token) => (token, false),
+            None => (self.issue(), true),
+        };
+
+        req.extensions.insert(CsrfToken {
+            value: token.clone(),
+        });
+
+        CsrfRequest {
+            token,
+            issued,
+            valid,
+        }
+    }
+
+    fn respond(&self, _req: &HttpRequest, ctx: &Self::Context) -> Option<HttpResponse> {
+        if ctx.valid {
+            None
+        } else {
+            Some(HttpResponse::new(StatusCode::FORBIDDEN).body("CSRF token is missing or invalid"))
+        }
+    }
+
+    fn after(&self, _req: &HttpRequest, res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+        if ctx.issued {
+            res.append_header(
+                SET_COOKIE,
+                format!("{}={}; Path=/; SameSite=Strict", COOKIE_NAME, ctx.token),
+            )
+        } else {
+            res
+        }
+    }
+}
+
+pub(crate) fn cookie<'c>(cookies: &'c str, name: &str) -> Option<&'c str> {
+    cookies.split(';').find_map(|cookie| {
+        let (key, value) = cookie.split_once('=')?;
+
+        if key.trim() == name {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// The token the client sent back, from the header or a form field.
+fn submitted(req: &HttpRequest) -> Option<String> {
+    if let Some(token) = req.header_data.headers.get(&HEADER_NAME) {
+        return Some(token.trim().to_string());
+    }
+
+    if !req
+        .content_type()
+        .map(|media_type| media_type.is("application/x-www-form-urlencoded"))
+        .unwrap_or(false)
+    {
+        return None;
+    }
+
+    form::parse(&req.body)
+        .find(|(key, _)| key == FIELD_NAME)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// The request's CSRF token, to put in a form's `csrf_token` field.
+///
+/// Requires the [`Csrf`] middleware.
+pub struct CsrfToken {
+    value: String,
+}
+
+impl const Deref for CsrfToken {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl Extractor for CsrfToken {
+    type Error = Error;
+
+    fn extract(req: &mut HttpRequest) -> Result<Self, Self::Error> {
+        match req.extensions.get::<CsrfToken>() {
+            Some(token) => Ok(CsrfToken {
+                value: token.value.clone(),
+            }),
+            None => Err(InternalError::InternalServerError(
+                "`CsrfToken` was used without the `Csrf` middleware",
+            )),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    http::{
+        headers::{
+            HttpHeaderName, REFERRER_POLICY, SERVER, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+        },
+        HttpRequest, HttpResponse,
+    },
+    middleware::Middleware,
+    utils::ArrayMap,
+};
+
+/// Adds headers to every response that does not already have them.
+pub struct DefaultHeaders {
+    headers: ArrayMap<HttpHeaderName, String, 16>,
+}
+
+impl DefaultHeaders {
+    pub const fn new() -> Self {
+        Self {
+            headers: ArrayMap::new(),
+        }
+    }
+
+    /// Conservative security headers, which stop content sniffing, framing, and leaking the
+    /// full URL to other origins.
+    pub fn security() -> Self {
+        Self::new()
+            .header(X_CONTENT_TYPE_OPTIONS, "nosniff")
+            .header(X_FRAME_OPTIONS, "DENY")
+            .header(REFERRER_POLICY, "strict-origin-when-cross-origin")
+    }
+
+    /// Adds a `Server` header naming this crate and its version.
+    pub fn server(self) -> Self {
+        self.header(SERVER, concat!("enrgy/", env!("CARGO_PKG_VERSION")))
+    }
+
+    pub fn header<V>(mut self, key: HttpHeaderName, value: V) -> Self
+    where
+        V: ToString,
+    {
+        self.headers.insert(key, value.to_string());
+
+        self
+    }
+}
+
+impl const Default for DefaultHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for DefaultHeaders {
+    fn before(&self, _req: &mut HttpRequest) -> Self::Context {}
+
+    fn after(
+        &self,
+        _req: &HttpRequest,
+        mut res: HttpResponse,
+        _ctx: Self::Context,
+    ) -> HttpResponse {
+        for (key, value) in self.headers.iter() {
+            if !res.headers.contains(key) {
+                res.headers.insert(key.clone(), value.clone());
+            }
+        }
+
+        res
+    }
+}
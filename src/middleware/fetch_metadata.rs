@@ -0,0 +1,99 @@
+use crate::{
+    http::{
+        headers::{SEC_FETCH_MODE, SEC_FETCH_SITE},
+        HttpRequest, HttpResponse, StatusCode,
+    },
+    middleware::Middleware,
+};
+
+/// Rejects state-changing requests (any method that is not safe) that a browser says were
+/// made by another site, with `403 Forbidden`.
+///
+/// Browsers say where a request came from in `Sec-Fetch-Site`, requests without it (from older
+/// browsers, or not from a browser at all) are let through, so this works alongside
+/// [`Csrf`](crate::middleware::Csrf) rather than replacing it.
+pub struct FetchMetadata {
+    reject_same_site: bool,
+    allow_navigation: bool,
+}
+
+impl FetchMetadata {
+    pub const fn new() -> Self {
+        Self {
+            reject_same_site: false,
+            allow_navigation: false,
+        }
+    }
+
+    /// Also rejects requests from other subdomains of the same site (`same-site`), defaults to
+    /// `false`.
+    pub const fn reject_same_site(mut self, reject: bool) -> Self {
+        self.reject_same_site = reject;
+
+        self
+    }
+
+    /// Lets through cross-site navigations (`Sec-Fetch-Mode: navigate`), ex. a form on another
+    /// site posting to this one, defaults to `false`.
+    pub const fn allow_navigation(mut self, allow: bool) -> Self {
+        self.allow_navigation = allow;
+
+        self
+    }
+
+    fn allowed(&self, req: &HttpRequest) -> bool {
+        if req.header_data.method.is_safe() {
+            return true;
+        }
+
+        let headers = &req.header_data.headers;
+
+        let cross_site = match headers.get(&SEC_FETCH_SITE).map(|site| site.trim()) {
+            Some(site) if site.eq_ignore_ascii_case("cross-site") => true,
+            Some(site) if site.eq_ignore_ascii_case("same-site") => self.reject_same_site,
+            // `same-origin`, `none` (typed by the user), or missing.
+            _ => false,
+        };
+
+        !cross_site
+            || (self.allow_navigation
+                && headers
+                    .get(&SEC_FETCH_MODE)
+                    .map(|mode| mode.trim().eq_ignore_ascii_case("navigate"))
+                    .unwrap_or(false))
+    }
+}
+
+impl const Default for FetchMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for FetchMetadata {
+    type Context = bool;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        self.allowed(req)
+    }
+
+    fn respond(&self, _req: &HttpRequest, allowed: &Self::Context) -> Option<HttpResponse> {
+        if *allowed {
+            None
+        } else {
+            Some(
+                HttpResponse::new(StatusCode::FORBIDDEN)
+                    .body("Cross-site requests are not allowed to change state"),
+            )
+        }
+    }
+
+    fn after(
+        &self,
+        _req: &HttpRequest,
+        res: HttpResponse,
+        _allowed: Self::Context,
+    ) -> HttpResponse {
+        res
+    }
+}
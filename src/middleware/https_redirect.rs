@@ -0,0 +1,85 @@
+use std::net::IpAddr;
+
+use crate::{
+    http::{
+        headers::{HOST, X_FORWARDED_PROTO},
+        HttpRequest, HttpResponse, StatusCode,
+    },
+    middleware::Middleware,
+};
+
+/// Redirects requests that reached the proxy in front of the server over plain HTTP to the same
+/// URL over HTTPS, with `301 Moved Permanently`.
+///
+/// The proxy says which protocol the client used in `X-Forwarded-Proto`, which is only trusted
+/// on connections from the proxies added with [`HttpsRedirect::trust_proxy`], as anyone else
+/// could send it too.
+pub struct HttpsRedirect {
+    proxies: Vec<IpAddr>,
+}
+
+impl HttpsRedirect {
+    pub const fn new() -> Self {
+        Self {
+            proxies: Vec::new(),
+        }
+    }
+
+    /// Trusts `X-Forwarded-Proto` on connections from `proxy`, this can be called multiple
+    /// times.
+    pub fn trust_proxy<A: Into<IpAddr>>(mut self, proxy: A) -> Self {
+        self.proxies.push(proxy.into());
+
+        self
+    }
+
+    fn trusted(&self, req: &HttpRequest) -> bool {
+        req.peer_addr
+            .map(|addr| self.proxies.contains(&addr.ip()))
+            .unwrap_or(false)
+    }
+}
+
+impl const Default for HttpsRedirect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for HttpsRedirect {
+    /// Where to redirect the request to, if it was made over plain HTTP.
+    type Context = Option<String>;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        if !self.trusted(req) {
+            return None;
+        }
+
+        let headers = &req.header_data.headers;
+
+        // A proxy behind another proxy appends to the list, the first is the client's.
+        let proto = headers.get(&X_FORWARDED_PROTO)?.split(',').next()?.trim();
+
+        if !proto.eq_ignore_ascii_case("http") {
+            return None;
+        }
+
+        let host = headers.get(&HOST)?;
+        let query = req.query_string();
+
+        Some(if query.is_empty() {
+            format!("https://{}{}", host, req.header_data.url)
+        } else {
+            format!("https://{}{}?{}", host, req.header_data.url, query)
+        })
+    }
+
+    fn respond(&self, _req: &HttpRequest, ctx: &Self::Context) -> Option<HttpResponse> {
+        ctx.as_ref()
+            .map(|location| HttpResponse::redirect(StatusCode::MOVED_PERMANENTLY, location))
+    }
+
+    fn after(&self, _req: &HttpRequest, res: HttpResponse, _ctx: Self::Context) -> HttpResponse {
+        res
+    }
+}
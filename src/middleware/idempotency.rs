@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clock,
+    http::{
+        headers::{HttpHeaderName, IDEMPOTENCY_KEY},
+        HttpMethod, HttpRequest, HttpResponse, StatusClass, StatusCode,
+    },
+    middleware::Middleware,
+};
+
+/// Set on responses that were replayed from the cache rather than handled.
+pub const IDEMPOTENT_REPLAYED: HttpHeaderName = HttpHeaderName::new("Idempotent-Replayed");
+
+/// Handles each `Idempotency-Key` once, so a client can retry a `POST` (or `PATCH`) without it
+/// being processed twice.
+///
+/// Keys are scoped to the method and path of the request they were sent with, the same key sent
+/// to another route is handled as a new request.
+///
+/// The first response for a key is kept for the [TTL](Idempotency::ttl) and sent back for any
+/// later request with the same key, without calling the route. A request sent while the first
+/// one is still being handled gets `409 Conflict`. Requests without the header, or with a method
+/// that is idempotent already, are let through.
+///
+/// Server errors and streamed responses are not kept, so the request can be retried.
+pub struct Idempotency {
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+/// The method, path, and `Idempotency-Key` of a request.
+type Key = (HttpMethod, String, String);
+
+struct Entries {
+    map: HashMap<Key, Entry>,
+    /// When each entry was added or last updated, oldest first, so the ones that have expired
+    /// can be removed without going through the whole map.
+    ///
+    /// An entry that was updated is in here more than once, only its latest time counts.
+    added: VecDeque<(Instant, Key)>,
+}
+
+impl Entries {
+    fn insert(&mut self, key: Key, entry: Entry) {
+        self.added.push_back((entry.at(), key.clone()));
+        self.map.insert(key, entry);
+    }
+
+    fn remove_expired(&mut self, now: Instant, ttl: Duration) {
+        while let Some((at, _)) = self.added.front() {
+            if now.saturating_duration_since(*at) < ttl {
+                break;
+            }
+
+            if let Some((at, key)) = self.added.pop_front() {
+                if self.map.get(&key).map(Entry::at) == Some(at) {
+                    self.map.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+enum Entry {
+    InProgress(Instant),
+    Done(Instant, Box<HttpResponse>),
+}
+
+impl Entry {
+    fn at(&self) -> Instant {
+        match self {
+            Entry::InProgress(at) | Entry::Done(at, _) => *at,
+        }
+    }
+}
+
+/// What to do with a request, see [`Idempotency`].
+pub enum IdempotencyRequest {
+    Ignored,
+    /// The first request with the key, its response is kept.
+    First(String),
+    InProgress,
+    Replay(Box<HttpResponse>),
+}
+
+impl Idempotency {
+    /// Keeps responses for 24 hours.
+    pub fn new() -> Self {
+        Self {
+            ttl: Duration::from_secs(24 * 60 * 60),
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                added: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Sets how long a response is kept for, and how long a request can take before its key is
+    /// let go (ex. after it panicked).
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+
+        self
+    }
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for Idempotency {
+    type Context = IdempotencyRequest;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        if req.header_data.method.is_idempotent() {
+            return IdempotencyRequest::Ignored;
+        }
+
+        let header = match req.header_data.headers.get(&IDEMPOTENCY_KEY) {
+            Some(header) if !header.is_empty() => header,
+            _ => return IdempotencyRequest::Ignored,
+        };
+
+        let key = key(req, header.clone());
+
+        let now = clock::clock(&req.data).now();
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+
+        entries.remove_expired(now, self.ttl);
+
+        match entries.map.get(&key) {
+            Some(Entry::InProgress(_)) => IdempotencyRequest::InProgress,
+            Some(Entry::Done(_, res)) => match res.try_clone() {
+                Some(res) => {
+                    IdempotencyRequest::Replay(box res.header(IDEMPOTENT_REPLAYED, "true"))
+                }
+                None => IdempotencyRequest::Ignored,
+            },
+            None => {
+                entries.insert(key, Entry::InProgress(now));
+
+                IdempotencyRequest::First(header.clone())
+            }
+        }
+    }
+
+    fn respond(&self, _req: &HttpRequest, ctx: &Self::Context) -> Option<HttpResponse> {
+        match ctx {
+            IdempotencyRequest::InProgress => Some(
+                HttpResponse::new(StatusCode::CONFLICT)
+                    .body("A request with this `Idempotency-Key` is still being processed"),
+            ),
+            IdempotencyRequest::Replay(res) => res.try_clone(),
+            IdempotencyRequest::Ignored | IdempotencyRequest::First(_) => None,
+        }
+    }
+
+    fn after(&self, req: &HttpRequest, res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+        if let IdempotencyRequest::First(header) = ctx {
+            let key = key(req, header);
+
+            let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+
+            match res.try_clone() {
+                Some(copy) if !matches!(copy.status.class(), StatusClass::ServerError) => {
+                    entries.insert(key, Entry::Done(clock::clock(&req.data).now(), box copy));
+                }
+                _ => {
+                    entries.map.remove(&key);
+                }
+            }
+        }
+
+        res
+    }
+}
+
+fn key(req: &HttpRequest, header: String) -> Key {
+    (req.header_data.method, req.header_data.url.clone(), header)
+}
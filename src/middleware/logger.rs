@@ -0,0 +1,51 @@
+use std::time::Instant;
+
+use crate::{
+    clock,
+    http::{HttpRequest, HttpResponse},
+    middleware::Middleware,
+    utils::log,
+};
+
+/// Logs every request at the `info` level once its response is ready, ex.
+/// `GET /index.html 200 3ms`.
+///
+/// The time is measured with the app's [`Clock`](crate::Clock).
+///
+/// Does nothing without the `log` feature.
+pub struct Logger;
+
+impl Logger {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl const Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware<HttpRequest, HttpResponse> for Logger {
+    type Context = Instant;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        clock::clock(&req.data).now()
+    }
+
+    fn after(&self, req: &HttpRequest, res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+        log::info!(
+            "{} {} {} {}ms",
+            req.header_data.method,
+            req.header_data.url,
+            res.status.0,
+            clock::clock(&req.data)
+                .now()
+                .saturating_duration_since(ctx)
+                .as_millis()
+        );
+
+        res
+    }
+}
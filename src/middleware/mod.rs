@@ -0,0 +1,115 @@
+mod cors;
+mod csrf;
+mod default_headers;
+mod fetch_metadata;
+mod https_redirect;
+mod idempotency;
+mod logger;
+mod session;
+
+pub use self::{
+    cors::{Cors, CorsRequest},
+    csrf::{Csrf, CsrfRequest, CsrfToken},
+    default_headers::DefaultHeaders,
+    fetch_metadata::FetchMetadata,
+    https_redirect::HttpsRedirect,
+    idempotency::{Idempotency, IdempotencyRequest, IDEMPOTENT_REPLAYED},
+    logger::Logger,
+    session::{MemoryStore, Session, SessionRequest, SessionStore},
+};
+
+use std::any::Any;
+
+pub trait Middleware<Req, Res> {
+    /// State created in [`Middleware::before`] and handed back to [`Middleware::after`].
+    type Context = ();
+
+    fn before(&self, req: &mut Req) -> Self::Context;
+
+    /// Called once every middleware's [`Middleware::before`] has run, returning a response
+    /// stops the route from being called, ex. to reject a request.
+    ///
+    /// [`Middleware::after`] is still called with the returned response.
+    fn respond(&self, _req: &Req, _ctx: &Self::Context) -> Option<Res> {
+        None
+    }
+
+    fn after(&self, req: &Req, res: Res, ctx: Self::Context) -> Res;
+
+    /// The name the middleware is listed under when the server starts, defaults to its type's
+    /// name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+trait ErasedMiddleware<Req, Res> {
+    fn name(&self) -> &str;
+    fn before(&self, req: &mut Req) -> Box<dyn Any>;
+    fn respond(&self, req: &Req, ctx: &dyn Any) -> Option<Res>;
+    fn after(&self, req: &Req, res: Res, ctx: Box<dyn Any>) -> Res;
+}
+
+impl<T, Req, Res> ErasedMiddleware<Req, Res> for T
+where
+    T: Middleware<Req, Res>,
+    T::Context: 'static,
+{
+    fn name(&self) -> &str {
+        Middleware::name(self)
+    }
+
+    fn before(&self, req: &mut Req) -> Box<dyn Any> {
+        box Middleware::before(self, req)
+    }
+
+    fn respond(&self, req: &Req, ctx: &dyn Any) -> Option<Res> {
+        match ctx.downcast_ref::<T::Context>() {
+            Some(ctx) => Middleware::respond(self, req, ctx),
+            None => unreachable!("middleware context was not created by its own `before`"),
+        }
+    }
+
+    fn after(&self, req: &Req, res: Res, ctx: Box<dyn Any>) -> Res {
+        match ctx.downcast::<T::Context>() {
+            Ok(ctx) => Middleware::after(self, req, res, *ctx),
+            Err(_) => unreachable!("middleware context was not created by its own `before`"),
+        }
+    }
+}
+
+pub struct BoxedMiddleware<Req, Res> {
+    inner: Box<dyn ErasedMiddleware<Req, Res> + Send + Sync + 'static>,
+}
+
+impl<Req, Res> BoxedMiddleware<Req, Res> {
+    pub fn new<T>(middleware: T) -> Self
+    where
+        T: Middleware<Req, Res> + Send + Sync + 'static,
+        T::Context: 'static,
+    {
+        Self {
+            inner: box middleware,
+        }
+    }
+}
+
+impl<Req, Res> Middleware<Req, Res> for BoxedMiddleware<Req, Res> {
+    type Context = Box<dyn Any>;
+
+    fn before(&self, req: &mut Req) -> Self::Context {
+        self.inner.before(req)
+    }
+
+    fn respond(&self, req: &Req, ctx: &Self::Context) -> Option<Res> {
+        self.inner.respond(req, &**ctx)
+    }
+
+    fn after(&self, req: &Req, res: Res, ctx: Self::Context) -> Res {
+        self.inner.after(req, res, ctx)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    extractor::session::{Session as SessionHandle, SessionData},
+    http::{
+        headers::{COOKIE, SET_COOKIE},
+        HttpRequest, HttpResponse,
+    },
+    middleware::{csrf::cookie, Middleware},
+    utils::{hmac, random},
+};
+
+const COOKIE_NAME: &str = "session_id";
+
+/// Where [`Session`] keeps sessions, implement it to keep them in ex. Redis or a database.
+pub trait SessionStore: Send + Sync {
+    fn load(&self, id: &str) -> Option<SessionData>;
+
+    fn save(&self, id: &str, data: SessionData);
+
+    fn remove(&self, id: &str);
+}
+
+/// Keeps sessions in memory, they are lost when the server stops.
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, SessionData>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(id)
+            .cloned()
+    }
+
+    fn save(&self, id: &str, data: SessionData) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(id.to_string(), data);
+    }
+
+    fn remove(&self, id: &str) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(id);
+    }
+}
+
+/// Keeps a session for each client, identified by a random id in a `session_id` cookie, which
+/// handlers read and change with the [`Session`](crate::web::Session) extractor.
+///
+/// A session is only created (and the cookie set) once something is stored in it, ids the
+/// store does not know are replaced rather than reused.
+pub struct Session {
+    store: Arc<dyn SessionStore>,
+    secure: bool,
+}
+
+/// The session for a request, see [`Session`].
+pub struct SessionRequest {
+    id: Option<String>,
+    session: SessionHandle,
+}
+
+impl Session {
+    /// Keeps sessions in a [`MemoryStore`].
+    pub fn new() -> Self {
+        Self::with_store(MemoryStore::new())
+    }
+
+    pub fn with_store<S>(store: S) -> Self
+    where
+        S: SessionStore + 'static,
+    {
+        Self {
+            store: Arc::new(store),
+            secure: false,
+        }
+    }
+
+    /// Marks the cookie as `Secure`, so it is only sent over HTTPS, defaults to `false`.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+
+        self
+    }
+
+    fn cookie(&self, value: &str, max_age: Option<u64>) -> String {
+        let mut cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Lax", COOKIE_NAME, value);
+
+        if let Some(max_age) = max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age));
+        }
+
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+
+        cookie
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A new session id, 128 bits from the OS's random number generator.
+fn new_id() -> String {
+    let mut bytes = [0; 16];
+
+    random::fill(&mut bytes).expect("unable to read random bytes for a session id");
+
+    hmac::to_hex(&bytes)
+}
+
+impl Middleware<HttpRequest, HttpResponse> for Session {
+    type Context = SessionRequest;
+
+    fn before(&self, req: &mut HttpRequest) -> Self::Context {
+        let loaded = req
+            .header_data
+            .headers
+            .get(&COOKIE)
+            .and_then(|cookies| cookie(cookies, COOKIE_NAME))
+            .and_then(|id| Some((id.to_string(), self.store.load(id)?)));
+
+        let (id, data) = match loaded {
+            Some((id, data)) => (Some(id), data),
+            None => (None, SessionData::new()),
+        };
+
+        let session = SessionHandle::new(data);
+
+        req.extensions.insert(session.clone());
+
+        SessionRequest { id, session }
+    }
+
+    fn after(&self, _req: &HttpRequest, res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+        let mut state = ctx.session.state();
+
+        if state.cleared {
+            return match ctx.id {
+                Some(id) => {
+                    self.store.remove(&id);
+
+                    res.append_header(SET_COOKIE, self.cookie("", Some(0)))
+                }
+                None => res,
+            };
+        }
+
+        let id = match ctx.id {
+            Some(id) if !state.renewed => {
+                if state.changed {
+                    self.store.save(&id, std::mem::take(&mut state.data));
+                }
+
+                return res;
+            }
+            Some(id) => {
+                self.store.remove(&id);
+
+                new_id()
+            }
+            None if state.changed || state.renewed => new_id(),
+            None => return res,
+        };
+
+        self.store.save(&id, std::mem::take(&mut state.data));
+
+        res.append_header(SET_COOKIE, self.cookie(&id, None))
+    }
+}
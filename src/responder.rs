@@ -1,6 +1,9 @@
+use std::{borrow::Cow, fmt};
+
 use crate::{
-    http::{HttpRequest, HttpResponse},
-    Error,
+    error::{InternalError, ResponseError},
+    http::{HttpBody, HttpHeaderName, HttpRequest, HttpResponse, StatusCode},
+    route, Error,
 };
 
 pub trait Responder {
@@ -13,6 +16,50 @@ impl Responder for HttpResponse {
     }
 }
 
+/// `None` leaves the request to the app's default service (see
+/// [`App::default_service`](crate::App::default_service)), as if the route had not matched,
+/// which responds with an empty `404 Not Found` when none has been set.
+impl<T> Responder for Option<T>
+where
+    T: Responder,
+{
+    fn respond_to(self, req: &HttpRequest) -> Result<HttpResponse, Error> {
+        match self {
+            Some(res) => res.respond_to(req),
+            None => Err(Unhandled.into()),
+        }
+    }
+}
+
+/// What a route responding with `None` fails with, the server hands the request on to the
+/// default service instead of responding with it.
+#[derive(Debug)]
+pub(crate) struct Unhandled;
+
+impl fmt::Display for Unhandled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HTTP request was not handled by its route")
+    }
+}
+
+impl ResponseError for Unhandled {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        route::not_found()
+    }
+}
+
+/// `Ok` responds with `T`, `Err` with the error's [`ResponseError::error_response`], so a
+/// handler can end with `?` or return its own error type instead of matching on it.
+///
+/// Errors are logged like any other handler error (see [`ResponseError`]), a warning for a
+/// `4xx` response and an error for anything else.
+///
+/// [`ResponseError`]: crate::error::ResponseError
+/// [`ResponseError::error_response`]: crate::error::ResponseError::error_response
 impl<T, E> Responder for Result<T, E>
 where
     T: Responder,
@@ -28,7 +75,7 @@ where
 
 impl Responder for &'static str {
     fn respond_to(self, _req: &HttpRequest) -> Result<HttpResponse, Error> {
-        Ok(HttpResponse::ok().body(self))
+        Ok(self.into())
     }
 }
 
@@ -40,7 +87,7 @@ impl Responder for &'static [u8] {
 
 impl Responder for String {
     fn respond_to(self, _req: &HttpRequest) -> Result<HttpResponse, Error> {
-        Ok(HttpResponse::ok().body(self))
+        Ok(self.into())
     }
 }
 
@@ -49,3 +96,41 @@ impl Responder for Vec<u8> {
         Ok(HttpResponse::ok().body(self))
     }
 }
+
+impl<B> Responder for (StatusCode, B)
+where
+    B: Into<HttpBody>,
+{
+    fn respond_to(self, _req: &HttpRequest) -> Result<HttpResponse, Error> {
+        Ok(self.into())
+    }
+}
+
+/// Responds with the status, the listed headers, and the body, for handlers that only need to
+/// set a header or two.
+///
+/// A header that can not be written (ex. a value with a line break) responds with
+/// `500 Internal Server Error` instead.
+impl<'h, B> Responder for (StatusCode, Vec<(&'h str, &'h str)>, B)
+where
+    B: Into<HttpBody>,
+{
+    fn respond_to(self, _req: &HttpRequest) -> Result<HttpResponse, Error> {
+        let (status, headers, body) = self;
+
+        let mut res = HttpResponse::new(status);
+
+        for (name, value) in headers {
+            res = res
+                .with_header(HttpHeaderName(Cow::Owned(name.to_string())), value)
+                .map_err(|_| {
+                    InternalError::InternalServerError(format!(
+                        "handler responded with an invalid `{}` header",
+                        name
+                    ))
+                })?;
+        }
+
+        Ok(res.body(body))
+    }
+}
@@ -1,8 +1,16 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use crate::{
-    extractor::Extractor,
+    extractor::{Extractor, PayloadConfig},
     handler::{Handler, HandlerService},
     http::{HttpMethod, HttpRequest, HttpResponse},
-    service::BoxedService,
+    service::{wrap_fn, BoxedService, Service},
     Error, Responder,
 };
 
@@ -13,23 +21,33 @@ where
     R: Responder + Send + Sync + 'static,
 {
     Route {
-        method: HttpMethod::Get,
+        method: Some(HttpMethod::Get),
         path: "/<to>",
         service: BoxedService::new(HandlerService::new(handler)),
+        timeout: None,
+        payload_config: None,
+        token: None,
     }
 }
 
 macro_rules! route {
-    ($($fn:ident[$method:expr],)*) => {
+    ($($(#[$meta:meta])* $fn:ident[$method:expr],)*) => {
         $(
+            $(#[$meta])*
             pub fn $fn(path: &str) -> Route<'_> {
-                Route::new($method, path)
+                Route::new(Some($method), path)
             }
         )*
     };
 }
 
 route![
+    /// A `GET` route, which also answers `HEAD` requests unless there is a `HEAD` route for the
+    /// path.
+    ///
+    /// For `HEAD` requests the handler is run as usual, the response keeps its headers and a
+    /// `Content-Length` of the body's length, but the body itself is not written. Handlers that
+    /// respond with an empty body can set `Content-Length` themselves, which is then kept.
     get[HttpMethod::Get],
     head[HttpMethod::Head],
     post[HttpMethod::Post],
@@ -41,23 +59,46 @@ route![
     patch[HttpMethod::Patch],
 ];
 
+/// A route that matches any method, it is only used when no route for the request's method
+/// matches.
+pub fn any(path: &str) -> Route<'_> {
+    Route::new(None, path)
+}
+
 pub(crate) fn not_found() -> HttpResponse {
     HttpResponse::not_found()
 }
 
+/// A route for a path pattern, where `:name` matches a segment and `*name` the rest of the path.
+///
+/// Parameters can also be written as `{name}`, `{name:*}`, or `{name:u32}` to only match values
+/// that parse as the integer type, so `/users/{id:u32}` does not match `/users/bob` and it falls
+/// through to the next route that does (ex. `/users/*rest` or a route for any method).
+///
+/// The last segment can be an optional parameter, `{name?}`, so `/docs/{section}/{page?}` matches
+/// both `/docs/intro` and `/docs/intro/setup`, use [`OptionalParam`](crate::web::OptionalParam)
+/// to extract it.
 pub struct Route<'s> {
-    pub(crate) method: HttpMethod,
+    /// `None` matches any method.
+    pub(crate) method: Option<HttpMethod>,
     pub(crate) path: &'s str,
     pub(crate) service: BoxedService<HttpRequest, HttpResponse, Error>,
+    /// `None` uses the server's request timeout.
+    pub(crate) timeout: Option<Option<Duration>>,
+    pub(crate) payload_config: Option<PayloadConfig>,
+    pub(crate) token: Option<RouteToken>,
 }
 
 impl<'s> Route<'s> {
     #[inline]
-    pub(crate) fn new(method: HttpMethod, path: &'s str) -> Self {
+    pub(crate) fn new(method: Option<HttpMethod>, path: &'s str) -> Self {
         Self {
             method,
             path,
             service: BoxedService::new(HandlerService::new(not_found)),
+            timeout: None,
+            payload_config: None,
+            token: None,
         }
     }
 
@@ -71,4 +112,91 @@ impl<'s> Route<'s> {
 
         self
     }
+
+    /// Uses `service` for the route instead of a handler, like [`Files`](crate::web::Files).
+    pub fn service<S>(mut self, service: S) -> Self
+    where
+        S: Service<HttpRequest, Response = HttpResponse, Error = Error> + Send + Sync + 'static,
+    {
+        self.service = BoxedService::new(service);
+
+        self
+    }
+
+    /// Wraps the route's service with `f`, which is given the request and the service to call
+    /// (see [`wrap_fn`](crate::dev::wrap_fn)).
+    ///
+    /// This must come after [`Route::to`], as that replaces the service being wrapped.
+    pub fn wrap_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(
+                &mut HttpRequest,
+                &BoxedService<HttpRequest, HttpResponse, Error>,
+            ) -> Result<HttpResponse, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.service = BoxedService::new(wrap_fn(self.service, f));
+
+        self
+    }
+
+    /// Overrides the server's request timeout (see
+    /// [`HttpServer::request_timeout`](crate::HttpServer::request_timeout)) for this route,
+    /// `None` disables it.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    /// Overrides the app's [`PayloadConfig`] for this route.
+    pub fn payload_config(mut self, config: PayloadConfig) -> Self {
+        self.payload_config = Some(config);
+
+        self
+    }
+
+    /// Ties the route to `token`, so it can be removed while the server is running with
+    /// [`ServerHandle::remove_route`](crate::ServerHandle::remove_route).
+    pub fn removable(mut self, token: &RouteToken) -> Self {
+        self.token = Some(token.clone());
+
+        self
+    }
+
+    /// Calls the route's handler (or service) with `req` directly, without routing or any of
+    /// the app's middleware, ex. to unit test a handler with a request from
+    /// [`HttpRequest::builder`].
+    pub fn call(&self, req: &mut HttpRequest) -> Result<HttpResponse, Error> {
+        if let Some(config) = self.payload_config {
+            req.extensions.insert(config);
+        }
+
+        self.service.call(req)
+    }
+}
+
+/// A token for removing routes from a running server, see [`Route::removable`].
+///
+/// The routes tied to a token stay in the app's routing table, requests they would have matched
+/// get the default service once they are removed.
+#[derive(Debug, Clone, Default)]
+pub struct RouteToken {
+    removed: Arc<AtomicBool>,
+}
+
+impl RouteToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn remove(&self) {
+        self.removed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::SeqCst)
+    }
 }
@@ -1,27 +1,50 @@
 use std::{
     fmt, io,
-    net::{SocketAddr, TcpListener, TcpStream},
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crate::{
-    app::BuiltApp,
+    app::{BuiltApp, DataFactory, Endpoint},
+    error::InternalError,
     extensions::Extensions,
-    http::{self, headers::ACCEPT_ENCODING, HttpRequest},
+    http::{
+        self,
+        headers::{
+            ACCEPT_ENCODING, ALLOW, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, HOST, KEEP_ALIVE,
+            RETRY_AFTER, SERVER, TRANSFER_ENCODING,
+        },
+        HttpMethod, HttpRequest, HttpResponse, Payload, ReadOutcome, StatusCode,
+    },
     middleware::Middleware as _,
+    responder::Unhandled,
+    route::RouteToken,
     service::Service,
-    utils::{signal, thread_pool::ThreadPool, ArrayMap},
-    App, Error,
+    utils::{
+        buffer_pool::BufferPool,
+        log, signal,
+        thread_pool::{PoolState, PoolStats, Priority, SendTimeoutError, ThreadPool},
+        watchdog::Watchdog,
+        ArrayMap,
+    },
+    App,
 };
 
+/// The number of worker threads connections are handled on.
+const WORKERS: usize = 4;
+
 #[derive(Debug)]
 pub enum RunError {
     Io(std::io::Error),
     Signal(signal::Error),
+    /// One of the app's [`App::data_factory`]s failed.
+    DataFactory(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl const From<std::io::Error> for RunError {
@@ -41,6 +64,7 @@ impl fmt::Display for RunError {
         match self {
             RunError::Io(err) => err.fmt(f),
             RunError::Signal(err) => err.fmt(f),
+            RunError::DataFactory(err) => write!(f, "unable to build the app's data: {}", err),
         }
     }
 }
@@ -50,29 +74,604 @@ impl std::error::Error for RunError {
         match self {
             RunError::Io(err) => Some(err),
             RunError::Signal(err) => Some(err),
+            RunError::DataFactory(err) => Some(&**err),
         }
     }
 }
 
+/// A server that has not been bound to an address yet, see [`HttpServer::bind`].
 pub struct Unbound;
 
+/// What to do when an absolute-form request target's authority does not match the `Host` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostMismatch {
+    /// Respond with `400 Bad Request`.
+    Reject,
+    /// Replace the `Host` header with the request line's authority, as described by RFC 7230.
+    PreferRequestLine,
+}
+
+/// A handle to a listening server that can be used to inspect it while it runs.
+#[derive(Clone)]
+pub struct ServerHandle {
+    close: Arc<AtomicBool>,
+    pool: Arc<PoolState>,
+}
+
+impl ServerHandle {
+    /// Tells the server to shut down, as if it had received Ctrl-C.
+    ///
+    /// [`HttpServer::run`] returns once the workers have finished with their connections and the
+    /// listener has been closed.
+    pub fn stop(&self) {
+        self.close.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns which of the server's workers are currently handling a connection.
+    ///
+    /// A worker stays busy for as long as the connection is kept alive, not just while a
+    /// request is being handled.
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// Removes the routes tied to `token` (see [`RouteToken`](crate::web::RouteToken)), requests
+    /// already being handled by them are unaffected.
+    pub fn remove_route(&self, token: &RouteToken) {
+        token.remove();
+    }
+}
+
+/// What the server did while it ran, returned by [`HttpServer::run_with_summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Requests whose response was written in full.
+    pub requests_served: u64,
+    pub connections_accepted: u64,
+    /// The time from the server starting to it having shut down.
+    pub uptime: Duration,
+}
+
+type FinalizeHook = dyn Fn(&RequestSummary, Result<(), &io::Error>) + Send + Sync;
+
+/// A handled request, given to the [`HttpServer::finalize`] hook.
+#[derive(Debug, Clone)]
+pub struct RequestSummary {
+    pub method: HttpMethod,
+    pub path: String,
+    /// The pattern of the route that handled the request, `None` for the default service.
+    pub pattern: Option<Arc<str>>,
+    /// `None` if the request failed before there was a response, ex. a middleware's `after`
+    /// panicked.
+    pub status: Option<StatusCode>,
+    /// The time since the request head was read.
+    pub elapsed: Duration,
+    /// The bytes of the response (head and body) that were written to the socket, which is less
+    /// than the whole response if writing it failed part way.
+    pub bytes_sent: u64,
+    /// The client closed the connection before the response was written, the error the hook is
+    /// given is then a `BrokenPipe` or `ConnectionReset` rather than a problem with the server.
+    pub disconnected: bool,
+}
+
+/// Calls the finalize hook when dropped, so it is called even if handling the request panics.
+struct Finalize<'c> {
+    hook: &'c FinalizeHook,
+    summary: RequestSummary,
+    start: Instant,
+    done: bool,
+}
+
+impl<'c> Finalize<'c> {
+    fn finish(mut self, outcome: Result<(), &io::Error>) {
+        self.done = true;
+        self.summary.elapsed = self.start.elapsed();
+
+        (self.hook)(&self.summary, outcome);
+    }
+}
+
+impl<'c> Drop for Finalize<'c> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.summary.elapsed = self.start.elapsed();
+
+            (self.hook)(
+                &self.summary,
+                Err(&io::Error::new(
+                    io::ErrorKind::Other,
+                    "the response was not written",
+                )),
+            );
+        }
+    }
+}
+
+pub(crate) struct Config {
+    pub(crate) signals: bool,
+    pub(crate) host_mismatch: HostMismatch,
+    pub(crate) stream_bodies: bool,
+    pub(crate) body_limit: u64,
+    pub(crate) body_timeout: Option<Duration>,
+    pub(crate) strict_parsing: bool,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) max_pipelined: Option<usize>,
+    pub(crate) worker_init: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    pub(crate) worker_builder: Option<Arc<dyn Fn(usize) -> thread::Builder + Send + Sync>>,
+    pub(crate) on_ready: Option<Arc<dyn Fn() + Send + Sync>>,
+    pub(crate) acceptors: usize,
+    pub(crate) finalize: Option<Arc<FinalizeHook>>,
+    pub(crate) write_timeout: Option<Duration>,
+    pub(crate) cork: bool,
+    pub(crate) nodelay_below: Option<usize>,
+    pub(crate) overflow_inline: Option<usize>,
+    pub(crate) max_queued: Option<usize>,
+    pub(crate) queue_timeout: Duration,
+    pub(crate) min_rate: Option<MinRate>,
+    pub(crate) header_read_timeout: Option<Duration>,
+    pub(crate) keep_alive_timeout: Option<Duration>,
+    pub(crate) max_requests: Option<usize>,
+    pub(crate) drain_limit: u64,
+    pub(crate) defer_body: bool,
+    pub(crate) max_streaming: Option<usize>,
+    pub(crate) retry_after: Option<Duration>,
+    /// `None` leaves the app's `Server` header as is, see [`HttpServer::server_header`].
+    pub(crate) server_header: Option<Option<String>>,
+    /// Path prefixes of connections that are handed to a worker first.
+    pub(crate) priority_prefixes: Vec<String>,
+    /// Requests with a streamed body that are being handled, counted against `max_streaming`.
+    pub(crate) streaming: AtomicUsize,
+    pub(crate) requests_served: AtomicU64,
+    pub(crate) connections_accepted: AtomicU64,
+    pub(crate) read_buffer_size: usize,
+    pub(crate) max_header_line_length: Option<usize>,
+    /// Reused for reading request heads.
+    pub(crate) read_buffers: BufferPool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            signals: true,
+            host_mismatch: HostMismatch::Reject,
+            stream_bodies: false,
+            body_limit: 4 * 1024 * 1024,
+            body_timeout: Some(Duration::from_secs(30)),
+            strict_parsing: false,
+            request_timeout: None,
+            max_pipelined: None,
+            worker_init: None,
+            worker_builder: None,
+            on_ready: None,
+            acceptors: 1,
+            finalize: None,
+            write_timeout: None,
+            cork: false,
+            nodelay_below: None,
+            overflow_inline: None,
+            max_queued: None,
+            queue_timeout: Duration::from_millis(100),
+            min_rate: None,
+            header_read_timeout: None,
+            keep_alive_timeout: None,
+            max_requests: None,
+            drain_limit: 1024 * 1024,
+            defer_body: false,
+            max_streaming: None,
+            retry_after: Some(Duration::from_secs(1)),
+            server_header: None,
+            priority_prefixes: Vec::new(),
+            streaming: AtomicUsize::new(0),
+            requests_served: AtomicU64::new(0),
+            connections_accepted: AtomicU64::new(0),
+            read_buffer_size: 512,
+            max_header_line_length: None,
+            read_buffers: BufferPool::new(WORKERS),
+        }
+    }
+}
+
 pub struct HttpServer<Addr> {
     close: Arc<AtomicBool>,
 
     workers: Vec<JoinHandle<()>>,
 
+    pool: Arc<PoolState>,
+
     addr: Addr,
 
     app: Arc<BuiltApp>,
+
+    data_factories: Vec<DataFactory>,
+
+    on_shutdown: Option<Box<dyn FnOnce() + Send>>,
+
+    config: Config,
+}
+
+impl<Addr> HttpServer<Addr> {
+    /// Stops the server from installing a Ctrl-C handler when it is run.
+    ///
+    /// Only one handler can be installed per process, so this is needed when
+    /// running multiple servers, or when the application handles signals itself.
+    pub fn disable_signals(mut self) -> Self {
+        self.config.signals = false;
+
+        self
+    }
+
+    /// Sets how requests whose absolute-form target disagrees with their `Host` header are
+    /// handled, defaults to [`HostMismatch::Reject`].
+    pub fn host_mismatch(mut self, policy: HostMismatch) -> Self {
+        self.config.host_mismatch = policy;
+
+        self
+    }
+
+    /// Stops request bodies from being read before the handler is called, instead they are left
+    /// on the socket as a [`Payload`](crate::http::Payload) for extractors like
+    /// [`BodyStream`](crate::web::BodyStream) to read as they arrive.
+    ///
+    /// Without this only bodies too large to be read along with the head are left on the
+    /// socket.
+    pub fn stream_bodies(mut self) -> Self {
+        self.config.stream_bodies = true;
+
+        self
+    }
+
+    /// Sets how many requests with a streamed body (see [`HttpServer::stream_bodies`]) can be
+    /// handled at once, further ones are responded to with `503 Service Unavailable` and the
+    /// connection is closed, defaults to no limit.
+    ///
+    /// Streamed bodies can hold on to a worker for as long as the client takes to send them,
+    /// this keeps enough workers free for requests that are answered straight away.
+    pub fn max_streaming_connections(mut self, max: Option<usize>) -> Self {
+        self.config.max_streaming = max;
+
+        self
+    }
+
+    /// Sets the `Retry-After` of the `503 Service Unavailable` responses sent when the server
+    /// is shedding load (see [`HttpServer::overflow_inline`],
+    /// [`HttpServer::max_queued_connections`] and [`HttpServer::max_streaming_connections`]),
+    /// rounded up to whole seconds, defaults to 1
+    /// second. `None` leaves the header out.
+    pub fn retry_after(mut self, delay: Option<Duration>) -> Self {
+        self.config.retry_after = delay;
+
+        self
+    }
+
+    /// Sets the `Server` header of every response from the app, replacing any set by a handler
+    /// or middleware (ex. [`DefaultHeaders::server`](crate::middleware::DefaultHeaders::server)),
+    /// `None` removes it so the server software is not given away.
+    pub fn server_header(mut self, name: Option<String>) -> Self {
+        self.config.server_header = Some(name);
+
+        self
+    }
+
+    /// Hands connections whose first request is for a path starting with `prefix` (ex. a
+    /// health check) to the next free worker, ahead of any others waiting for one.
+    ///
+    /// The acceptor peeks at the request line for up to 10ms to find the path, connections that
+    /// have not sent it by then wait their turn.
+    pub fn high_priority(mut self, prefix: &str) -> Self {
+        self.config.priority_prefixes.push(prefix.to_string());
+
+        self
+    }
+
+    /// Sets the starting capacity of the buffers request heads are read into, defaults to 512
+    /// bytes.
+    ///
+    /// Buffers are kept in a pool shared by the workers and reused by later connections, one
+    /// that grew to fit a larger head is shrunk back to this size when it is returned.
+    pub fn read_buffer_size(mut self, bytes: usize) -> Self {
+        self.config.read_buffer_size = bytes;
+
+        self
+    }
+
+    /// Sets the longest a single line of a request head can be, including its line ending,
+    /// defaults to no limit other than the one on the whole head.
+    ///
+    /// A request with a longer line gets a `431 Request Header Fields Too Large` response,
+    /// which is sent as soon as the line passes the limit rather than once it ends.
+    pub fn max_header_line_length(mut self, bytes: Option<usize>) -> Self {
+        self.config.max_header_line_length = bytes;
+
+        self
+    }
+
+    /// Sets the largest request body the server accepts without buffering it along with the
+    /// head, defaults to 4 MiB.
+    ///
+    /// These bodies are read by the extractors as the handler needs them, larger ones are
+    /// answered with `413 Payload Too Large`. The one exception is a handler that runs past its
+    /// [`HttpServer::request_timeout`], the client has then already been sent a
+    /// `503 Service Unavailable` and the connection is reset instead.
+    pub fn body_limit(mut self, bytes: u64) -> Self {
+        self.config.body_limit = bytes;
+
+        self
+    }
+
+    /// Sets how much of a streamed request body that is over the limit is read (and discarded)
+    /// after responding with `413 Payload Too Large`, so the client, which is most likely still
+    /// sending it, gets to see the response instead of a reset connection, defaults to 1 MiB.
+    ///
+    /// The connection is closed either way, this only bounds how much is read before it is.
+    pub fn drain_limit(mut self, bytes: u64) -> Self {
+        self.config.drain_limit = bytes;
+
+        self
+    }
+
+    /// Leaves request bodies on the socket until the middleware has had a chance to reject the
+    /// request (with [`Middleware::respond`](crate::middleware::Middleware::respond)), so a
+    /// rejection does not have to wait for the whole upload.
+    ///
+    /// The body of a rejected request is skipped up to the [`HttpServer::drain_limit`], if there
+    /// is more of it the connection is closed instead. A middleware's `before` does not see the
+    /// body, as it is only read after.
+    pub fn defer_body(mut self) -> Self {
+        self.config.defer_body = true;
+
+        self
+    }
+
+    /// Sets how long a streamed request body has to be read in, defaults to 30 seconds.
+    pub fn body_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.body_timeout = timeout;
+
+        self
+    }
+
+    /// Sets the slowest a client can send a request head (and a body that is not streamed), at
+    /// least `bytes` in every `window`, otherwise the server responds with
+    /// `408 Request Timeout` and closes the connection, defaults to no minimum.
+    ///
+    /// This stops clients from holding on to a worker by sending a request a byte at a time,
+    /// which a read timeout alone does not, as every read returns in time. Streamed bodies are
+    /// bounded by [`HttpServer::body_timeout`] instead.
+    pub fn min_request_rate(mut self, bytes: u64, window: Duration) -> Self {
+        self.config.min_rate = Some(MinRate { bytes, window });
+
+        self
+    }
+
+    /// Sets how long a client has to send a whole request head, from its first byte to the
+    /// empty line ending it, otherwise the server responds with `408 Request Timeout` and closes
+    /// the connection before the body is read, defaults to no limit.
+    ///
+    /// Unlike [`HttpServer::min_request_rate`] this also stops a client that trickles the head
+    /// just fast enough, the wait for the first byte is bounded by
+    /// [`HttpServer::keep_alive_timeout`].
+    pub fn header_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.header_read_timeout = timeout;
+
+        self
+    }
+
+    /// Sets how long a single write to a client can block before the response is abandoned
+    /// and the connection closed, so a client that stops reading can not hold on to a worker,
+    /// defaults to no limit.
+    ///
+    /// The [`HttpServer::finalize`] hook is called with an [`io::ErrorKind::TimedOut`] error.
+    pub fn write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.write_timeout = timeout;
+
+        self
+    }
+
+    /// Sets whether responses are written with `TCP_CORK` set, which holds back partial TCP
+    /// segments until the whole response has been written, so a small response's head and body
+    /// are sent together instead of in separate segments, defaults to `false`.
+    ///
+    /// This only has an effect on Linux, elsewhere responses are written as usual.
+    pub fn cork_responses(mut self, cork: bool) -> Self {
+        self.config.cork = cork;
+
+        self
+    }
+
+    /// Sets `TCP_NODELAY` for responses with a body smaller than `bytes`, so they are sent
+    /// straight away, and clears it for larger ones, so Nagle's algorithm can batch them into
+    /// full segments, defaults to leaving the socket's setting alone.
+    ///
+    /// Bodies are measured before they are compressed.
+    pub fn nodelay_below(mut self, bytes: Option<usize>) -> Self {
+        self.config.nodelay_below = bytes;
+
+        self
+    }
+
+    /// Sets whether request heads are parsed strictly, rejecting obsolete line folding and stray
+    /// whitespace with `400 Bad Request` instead of working around them, defaults to `false`.
+    ///
+    /// See [`parse_header`](crate::http::parse_header) for what is rejected.
+    pub fn strict_parsing(mut self, strict: bool) -> Self {
+        self.config.strict_parsing = strict;
+
+        self
+    }
+
+    /// Sets how long a handler has to respond before the server responds with
+    /// `503 Service Unavailable` and closes the connection, defaults to no timeout.
+    ///
+    /// Routes can override this with `Route::timeout`.
+    pub fn request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.request_timeout = timeout;
+
+        self
+    }
+
+    /// Sets how long a connection can sit idle between requests before it is closed, defaults
+    /// to no limit.
+    ///
+    /// Responses advertise it in a `Keep-Alive` header (rounded down to whole seconds).
+    pub fn keep_alive_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.keep_alive_timeout = timeout;
+
+        self
+    }
+
+    /// Sets how many requests are handled on a connection before it is closed, defaults to no
+    /// limit.
+    ///
+    /// Responses advertise how many are left in a `Keep-Alive` header, the last one has
+    /// `Connection: close` instead.
+    pub fn max_requests_per_connection(mut self, max: Option<usize>) -> Self {
+        self.config.max_requests = max.map(|max| max.max(1));
+
+        self
+    }
+
+    /// Sets how many requests a client can pipeline (send before reading the responses) before
+    /// the connection is closed, so one client can not keep a worker to itself, defaults to no
+    /// limit.
+    ///
+    /// Requests only count while there is already another one waiting on the connection.
+    pub fn max_pipelined_requests(mut self, max: Option<usize>) -> Self {
+        self.config.max_pipelined = max;
+
+        self
+    }
+
+    /// Sets how many requests an acceptor handles itself when every worker already has a
+    /// connection, defaults to `None`, where connections wait in an unbounded queue for a worker
+    /// instead.
+    ///
+    /// Requests handled this way (one per connection, which is then closed) hold up accepting
+    /// new connections, past `max` they are answered with `503 Service Unavailable`, until a
+    /// worker is free again. This is meant to keep a small server responsive during short bursts.
+    pub fn overflow_inline(mut self, max: Option<usize>) -> Self {
+        self.config.overflow_inline = max;
+
+        self
+    }
+
+    /// Sets how many connections can wait for a worker at once, defaults to no limit.
+    ///
+    /// When the queue is full an acceptor waits (see [`HttpServer::queue_timeout`]) for a worker
+    /// to take a connection, if none does the new connection is answered with
+    /// `503 Service Unavailable`, rather than holding up every connection after it.
+    pub fn max_queued_connections(mut self, max: Option<usize>) -> Self {
+        self.config.max_queued = max;
+
+        self
+    }
+
+    /// Sets how long an acceptor waits for room in a full queue (see
+    /// [`HttpServer::max_queued_connections`]) before rejecting a connection, defaults to 100
+    /// milliseconds.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.config.queue_timeout = timeout;
+
+        self
+    }
+
+    /// Sets how many threads accept connections (and hand them to the workers), defaults to
+    /// one, which is only a bottleneck at very high connection rates.
+    ///
+    /// All of the threads accept from the same listener.
+    pub fn acceptors(mut self, acceptors: usize) -> Self {
+        self.config.acceptors = acceptors.max(1);
+
+        self
+    }
+
+    /// Sets a function that is called once the response to a request has been written, or
+    /// writing it failed, with a summary of the request and the outcome.
+    ///
+    /// Unlike a middleware's `after`, it is also called if the response could not be written,
+    /// or if handling the request panicked, which makes it suited to access logs. Requests that
+    /// are rejected before being routed (ex. malformed ones) are not passed to it.
+    pub fn finalize<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RequestSummary, Result<(), &io::Error>) + Send + Sync + 'static,
+    {
+        self.config.finalize = Some(Arc::new(hook));
+
+        self
+    }
+
+    /// Sets a function each worker thread calls with its id (`0` up to the number of workers)
+    /// when it starts, before handling any connections, ex. to set up thread locals.
+    pub fn worker_init<F>(mut self, init: F) -> Self
+    where
+        F: Fn(usize) + Clone + Send + Sync + 'static,
+    {
+        self.config.worker_init = Some(Arc::new(init));
+
+        self
+    }
+
+    /// Sets a function that returns the [`thread::Builder`] each worker thread is spawned with,
+    /// given its id, ex. to name the threads or give them a larger stack.
+    ///
+    /// ```no_run
+    /// use std::thread;
+    ///
+    /// use enrgy::{App, HttpServer};
+    ///
+    /// HttpServer::new(App::new()).worker_builder(|id| {
+    ///     thread::Builder::new()
+    ///         .name(format!("worker-{}", id))
+    ///         .stack_size(8 * 1024 * 1024)
+    /// });
+    /// ```
+    pub fn worker_builder<F>(mut self, builder: F) -> Self
+    where
+        F: Fn(usize) -> thread::Builder + Send + Sync + 'static,
+    {
+        self.config.worker_builder = Some(Arc::new(builder));
+
+        self
+    }
+
+    /// Sets a function that is called once the server is accepting connections, after every
+    /// worker has started and run its [`HttpServer::worker_init`].
+    ///
+    /// Connections are not accepted until then either, so the first requests do not wait on
+    /// workers that are still starting.
+    pub fn on_ready<F>(mut self, ready: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.config.on_ready = Some(Arc::new(ready));
+
+        self
+    }
+
+    /// Sets a function that is called once the server has shut down, after every worker has
+    /// finished its last request and stopped, and before `run` returns, ex. to flush buffered logs
+    /// or export final metrics.
+    pub fn on_shutdown<F>(mut self, shutdown: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.on_shutdown = Some(box shutdown);
+
+        self
+    }
 }
 
 impl HttpServer<Unbound> {
-    pub fn new(app: App) -> Self {
+    pub fn new(mut app: App) -> Self {
+        let data_factories = std::mem::take(&mut app.data_factories);
+
         Self {
             close: Arc::new(AtomicBool::new(false)),
             workers: Vec::with_capacity(4),
+            pool: Arc::new(PoolState::new(WORKERS)),
             addr: Unbound,
             app: Arc::new(app.build()),
+            data_factories,
+            on_shutdown: None,
+            config: Config::default(),
         }
     }
 
@@ -83,56 +682,307 @@ impl HttpServer<Unbound> {
         HttpServer {
             close: self.close,
             workers: self.workers,
+            pool: self.pool,
             addr: addr.into(),
             app: self.app,
+            data_factories: self.data_factories,
+            on_shutdown: self.on_shutdown,
+            config: self.config,
         }
     }
 }
 
 impl HttpServer<SocketAddr> {
+    /// Binds the listener without starting the server.
+    ///
+    /// Useful when binding to port `0`, as [`HttpServer::local_addr`] will return
+    /// the address that the OS assigned.
+    pub fn listen(self) -> io::Result<HttpServer<TcpListener>> {
+        let listener = TcpListener::bind(self.addr)?;
+
+        log::info!("listening on {}", listener.local_addr()?);
+
+        if !self.app.middleware.is_empty() {
+            log::info!(
+                "middleware, in order: {}",
+                self.app
+                    .middleware
+                    .iter()
+                    .map(|middleware| middleware.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        Ok(HttpServer {
+            close: self.close,
+            workers: self.workers,
+            pool: self.pool,
+            addr: listener,
+            app: self.app,
+            data_factories: self.data_factories,
+            on_shutdown: self.on_shutdown,
+            config: self.config,
+        })
+    }
+
+    pub fn run(self) -> Result<(), RunError> {
+        self.listen()?.run()
+    }
+
+    pub fn run_with_summary(self) -> Result<RunSummary, RunError> {
+        self.listen()?.run_with_summary()
+    }
+}
+
+impl HttpServer<TcpListener> {
+    /// Returns the address the server's listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.addr.local_addr()
+    }
+
+    /// Returns a handle that can be used to inspect the server after it has started running.
+    pub fn handle(&self) -> ServerHandle {
+        ServerHandle {
+            close: Arc::clone(&self.close),
+            pool: Arc::clone(&self.pool),
+        }
+    }
+
     pub fn run(self) -> Result<(), RunError> {
-        signal::set_handler({
-            let close = Arc::clone(&self.close);
+        self.run_with_summary().map(|_| ())
+    }
+
+    /// Like [`HttpServer::run`], returning how many requests and connections the server handled
+    /// once it has shut down.
+    pub fn run_with_summary(mut self) -> Result<RunSummary, RunError> {
+        let start = Instant::now();
+
+        if !self.data_factories.is_empty() {
+            // Nothing else has the app until the acceptors are started.
+            let data = Arc::get_mut(&mut self.app)
+                .and_then(|app| Arc::get_mut(&mut app.data))
+                .expect("the app's data is shared before the server started");
 
-            move || {
-                close.store(true, Ordering::SeqCst);
+            for factory in self.data_factories.drain(..) {
+                factory(data).map_err(RunError::DataFactory)?;
             }
-        })?;
+        }
 
-        let listener = TcpListener::bind(self.addr)?;
+        if self.config.signals {
+            signal::set_handler({
+                let close = Arc::clone(&self.close);
+
+                move || {
+                    close.store(true, Ordering::SeqCst);
+                }
+            })?;
+        }
+
+        let local_addr = self.addr.local_addr()?;
+
+        let mut listeners = (1..self.config.acceptors)
+            .map(|_| self.addr.try_clone())
+            .collect::<io::Result<Vec<_>>>()?;
+
+        listeners.push(self.addr);
+
+        let state = Arc::clone(&self.pool);
+        let init = self.config.worker_init.clone();
+        let builder = self.config.worker_builder.clone();
+
+        let (pool, sender) = ThreadPool::with_builder(
+            self.pool,
+            Arc::clone(&self.close),
+            self.config.max_queued,
+            move |id| match &builder {
+                Some(builder) => builder(id),
+                None => thread::Builder::new(),
+            },
+            move |id| {
+                if let Some(init) = &init {
+                    init(id);
+                }
+            },
+            Self::thread_pool_handler,
+        )?;
+
+        pool.wait_started();
+
+        log::debug!("workers started");
+
+        let app = self.app;
+        let close = self.close;
+        let config = Arc::new(self.config);
+
+        let acceptors = listeners
+            .into_iter()
+            .map(|listener| {
+                let app = Arc::clone(&app);
+                let config = Arc::clone(&config);
+                let close = Arc::clone(&close);
+                let state = Arc::clone(&state);
+                let sender = sender.clone();
+
+                // Requests handled inline since a worker was last free.
+                let mut inline = 0;
 
-        let (pool, sender) = ThreadPool::new(4, Arc::clone(&self.close), Self::thread_pool_handler);
+                thread::spawn(move || loop {
+                    match listener.accept() {
+                        // Either a connection that raced the shutdown, or the wake up below.
+                        Ok((mut stream, _)) if close.load(Ordering::SeqCst) => {
+                            shutting_down(&config, &mut stream);
 
-        thread::spawn({
-            let app = Arc::clone(&self.app);
+                            break;
+                        }
+                        Ok((mut stream, addr)) => {
+                            log::trace!("accepted connection from {}", addr);
 
-            move || {
-                while let Ok((stream, addr)) = listener.accept() {
-                    if sender.send((Arc::clone(&app), stream, addr)).is_err() {
-                        break;
+                            config.connections_accepted.fetch_add(1, Ordering::Relaxed);
+
+                            if let Some(max) = config.overflow_inline {
+                                if !state.is_saturated() {
+                                    inline = 0;
+                                } else if inline < max {
+                                    log::debug!("workers are busy, handling {} inline", addr);
+
+                                    inline += 1;
+
+                                    Self::inline_handler(Arc::clone(&app), &config, stream);
+
+                                    continue;
+                                } else {
+                                    log::warn!("workers are busy, rejecting {}", addr);
+
+                                    let _ = http::write_response(
+                                        overloaded(
+                                            &config,
+                                            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE),
+                                        ),
+                                        false,
+                                        &mut stream,
+                                    );
+
+                                    linger_close(&mut stream);
+
+                                    continue;
+                                }
+                            }
+
+                            state.assign();
+
+                            let priority = priority(&config, &stream);
+
+                            let job = (Arc::clone(&app), Arc::clone(&config), stream, addr);
+
+                            let sent = match config.max_queued {
+                                Some(_) => sender.send_timeout(job, priority, config.queue_timeout),
+                                None => sender
+                                    .send(job, priority)
+                                    .map_err(SendTimeoutError::Disconnected),
+                            };
+
+                            match sent {
+                                Ok(()) => {}
+                                Err(SendTimeoutError::Timeout((_, config, mut stream, addr))) => {
+                                    log::warn!("connection queue is full, rejecting {}", addr);
+
+                                    state.unassign();
+
+                                    let _ = http::write_response(
+                                        overloaded(
+                                            &config,
+                                            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE),
+                                        ),
+                                        false,
+                                        &mut stream,
+                                    );
+
+                                    // Lingering here would hold up accepting the connections
+                                    // that are waiting.
+                                    reject_close(&mut stream);
+                                }
+                                // The workers have all stopped.
+                                Err(SendTimeoutError::Disconnected((_, config, mut stream, _))) => {
+                                    shutting_down(&config, &mut stream);
+
+                                    close.store(true, Ordering::SeqCst);
+
+                                    break;
+                                }
+                            }
+                        }
+                        Err(_) if close.load(Ordering::SeqCst) => break,
+                        Err(err) => {
+                            log::error!("unable to accept connection, stopping: {}", err);
+
+                            close.store(true, Ordering::SeqCst);
+
+                            break;
+                        }
                     }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(ready) = &config.on_ready {
+            ready();
+        }
+
+        // Only the acceptors hold on to senders, so the workers also stop if they all have.
+        drop(sender);
+
+        pool.join();
+
+        // The acceptors are most likely blocked in `accept`, so connect to them to wake them up,
+        // making sure the listener is closed by the time `run` returns. Each connection wakes
+        // one of them, whichever accepts it.
+        close.store(true, Ordering::SeqCst);
+
+        let mut woken = true;
+
+        for _ in 0..acceptors.len() {
+            match wake(local_addr) {
+                Ok(()) => {}
+                // Refused means the acceptors have all stopped and dropped the listener.
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => break,
+                Err(err) => {
+                    log::warn!("unable to wake the listener, it will be left open: {}", err);
+
+                    woken = false;
+
+                    break;
                 }
             }
-        });
+        }
 
-        pool.join();
+        if woken {
+            for acceptor in acceptors {
+                let _ = acceptor.join();
+            }
+        }
 
-        Ok(())
+        if let Some(shutdown) = self.on_shutdown {
+            shutdown();
+        }
+
+        log::info!("server shut down");
+
+        Ok(RunSummary {
+            requests_served: config.requests_served.load(Ordering::Relaxed),
+            connections_accepted: config.connections_accepted.load(Ordering::Relaxed),
+            uptime: start.elapsed(),
+        })
     }
 }
 
 enum ThreadError {
-    Enrgy(Error),
     Http(http::HttpError),
     Io(io::Error),
     ParseInt(std::num::ParseIntError),
     Utf8(std::string::FromUtf8Error),
-}
-
-impl const From<Error> for ThreadError {
-    fn from(v: Error) -> Self {
-        Self::Enrgy(v)
-    }
+    /// The connection has to be reset, it is dropped without being shut down or read from.
+    Reset,
 }
 
 impl const From<http::HttpError> for ThreadError {
@@ -159,90 +1009,1262 @@ impl const From<std::string::FromUtf8Error> for ThreadError {
     }
 }
 
-impl HttpServer<SocketAddr> {
-    fn thread_pool_handler((app, mut stream, _addr): (Arc<BuiltApp>, TcpStream, SocketAddr)) {
-        fn run(app: Arc<BuiltApp>, stream: &mut TcpStream) {
-            if let Err(err) = HttpServer::thread_handle(app, stream) {
-                log::error!("unable to handle thread");
-
-                match err {
-                    ThreadError::Enrgy(err) => log::error!("route handler error: {:?}", err),
-                    ThreadError::Http(err) => log::error!("invalid http: {:?}", err),
-                    ThreadError::Io(err) => log::error!("{}", err),
-                    ThreadError::ParseInt(err) => log::error!("{}", err),
-                    ThreadError::Utf8(err) => log::error!("{}", err),
-                }
-            }
-        }
-
-        if let Err(err) = stream.set_read_timeout(None) {
-            log::error!(
-                "internal tcp stream error, unable to make `read` blocking: {}",
-                err
-            );
-        }
+impl HttpServer<TcpListener> {
+    fn thread_pool_handler(
+        (app, config, mut stream, _addr): (Arc<BuiltApp>, Arc<Config>, TcpStream, SocketAddr),
+    ) {
+        prepare(&config, &stream);
 
-        run(app.clone(), &mut stream);
+        // Requests that can still be handled on the connection, including the next one.
+        let remaining = |served: usize| config.max_requests.map(|max| max - served);
 
         let mut byte = [0u8; 1];
+        // Requests in a row that were sent before the client had the previous response.
+        let mut pipelined = match run(app.clone(), &config, &mut stream, remaining(0)) {
+            Some(pipelined) => pipelined as usize,
+            None => return,
+        };
+        let mut served = 1;
 
         loop {
-            match stream.peek(&mut byte) {
-                Ok(_bytes) => {
-                    run(app.clone(), &mut stream);
-                }
-                Err(err) if err.kind() == io::ErrorKind::ConnectionAborted => break,
-                Err(err) => {
-                    log::error!("{}", err)
-                }
+            if remaining(served) == Some(0) {
+                log::debug!("connection handled its last request, closing it");
+
+                linger_close(&mut stream);
+
+                break;
             }
-        }
-    }
 
-    fn thread_handle(app: Arc<BuiltApp>, stream: &mut TcpStream) -> Result<(), ThreadError> {
-        let (header_data, body) = http::read_request(stream)?;
+            if let Some(max) = config.max_pipelined {
+                if pipelined >= max {
+                    log::debug!(
+                        "client pipelined more than {} requests, closing the connection",
+                        max
+                    );
 
-        let (service, params) = app
-            .tree
-            .get(&header_data.method)
-            .and_then(|tree| tree.find(&header_data.url))
-            .map(|(service, params)| {
-                let mut map: ArrayMap<String, String, 32> = ArrayMap::new();
+                    linger_close(&mut stream);
 
-                for (key, value) in params.into_iter() {
-                    map.insert(key.to_string(), value.to_string());
+                    break;
                 }
+            }
 
-                (Arc::clone(service), map)
-            })
-            .unwrap_or_else(|| (app.default_service.clone(), ArrayMap::new()));
+            if let Some(timeout) = config.keep_alive_timeout {
+                if let Err(err) = stream.set_read_timeout(Some(timeout)) {
+                    log::error!(
+                        "internal tcp stream error, unable to set the keep-alive timeout: {}",
+                        err
+                    );
+                }
+            }
 
-        let compress = if let Some(header) = header_data.headers.get(&ACCEPT_ENCODING) {
-            header.contains("deflate")
-        } else {
-            false
+            // Wait for the next request, a zero length peek means the client has closed
+            // (or half-closed) its side and will not send anything else.
+            let peeked = stream.peek(&mut byte);
+
+            if config.keep_alive_timeout.is_some() {
+                let _ = stream.set_read_timeout(None);
+            }
+
+            match peeked {
+                Ok(0) => {
+                    log::debug!("connection closed by the client");
+
+                    break;
+                }
+                Ok(_bytes) => {
+                    match run(app.clone(), &config, &mut stream, remaining(served)) {
+                        Some(true) => pipelined += 1,
+                        Some(false) => pipelined = 0,
+                        None => break,
+                    }
+
+                    served += 1;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                // What a read timing out looks like on unix.
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    log::debug!("connection was idle for too long, closing it");
+
+                    break;
+                }
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    break
+                }
+                Err(err) => {
+                    log::error!("{}", err);
+
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Answers a request that was sent slower than [`HttpServer::min_request_rate`] allows, and
+    /// closes the connection.
+    fn too_slow(stream: &mut TcpStream) -> Result<(), ThreadError> {
+        http::write_response(
+            HttpResponse::new(StatusCode::REQUEST_TIMEOUT).body("HTTP request was sent too slowly"),
+            false,
+            stream,
+        )?;
+
+        linger_close(stream);
+
+        Ok(())
+    }
+
+    /// Handles a single request on the acceptor's thread, closing the connection afterwards.
+    fn inline_handler(app: Arc<BuiltApp>, config: &Config, mut stream: TcpStream) {
+        prepare(config, &stream);
+
+        if run(app, config, &mut stream, Some(1)).is_some() {
+            linger_close(&mut stream);
+        }
+    }
+
+    /// Handles a request, `remaining` being how many more (including this one) can be handled
+    /// on the connection, returning if the next request had arrived before the response was
+    /// written (see [`HttpServer::max_pipelined_requests`]).
+    fn thread_handle(
+        app: Arc<BuiltApp>,
+        config: &Config,
+        stream: &mut TcpStream,
+        remaining: Option<usize>,
+    ) -> Result<bool, ThreadError> {
+        let head = http::read_head_limited(
+            &mut RateGuard::new(http::HeadReader::new(stream), stream, config.min_rate)
+                .timeout(config.header_read_timeout),
+            config.strict_parsing,
+            &mut config.read_buffers.take(config.read_buffer_size),
+            config.max_header_line_length.unwrap_or(usize::MAX),
+        );
+
+        let (mut header_data, mut body) = match head {
+            Ok(request) => request,
+            Err(http::HttpError::Incomplete(ReadOutcome::TimedOut)) => {
+                log::debug!("timed out reading the request head");
+
+                return Self::too_slow(stream).map(|_| false);
+            }
+            // Nothing can be sent back to a client that has gone, or half-closed its side
+            // without finishing the request.
+            Err(http::HttpError::Incomplete(ReadOutcome::Eof | ReadOutcome::Complete)) => {
+                log::debug!("connection closed before the request head was read");
+
+                let _ = stream.shutdown(Shutdown::Both);
+
+                return Ok(false);
+            }
+            Err(http::HttpError::Io(err)) => return Err(err.into()),
+            Err(err) => {
+                log::debug!("invalid request head: {}", err);
+
+                http::write_response(err.to_response(), false, stream)?;
+
+                // The rest of a head that was too large is still being sent, as are the frames
+                // that follow the HTTP/2 preface, and without a length there is no telling where
+                // the body ends and the next request starts.
+                if matches!(
+                    err,
+                    http::HttpError::Incomplete(ReadOutcome::TooLarge)
+                        | http::HttpError::HeaderLineTooLong { .. }
+                        | http::HttpError::Http2Preface
+                        | http::HttpError::InvalidContentLength
+                        | http::HttpError::ConflictingContentLength
+                ) {
+                    linger_close(stream);
+                }
+
+                return Ok(false);
+            }
+        };
+
+        // Chunked (or otherwise encoded) request bodies are not supported, rather than leave one
+        // on the connection to be read as the next request, the request is refused and the
+        // connection closed.
+        if header_data.headers.contains(&TRANSFER_ENCODING) {
+            log::debug!("request has a `Transfer-Encoding`, closing the connection");
+
+            let res = if header_data.headers.contains(&CONTENT_LENGTH) {
+                HttpResponse::bad_request()
+                    .body("HTTP request has both `Transfer-Encoding` and `Content-Length`")
+            } else {
+                HttpResponse::new(StatusCode::NOT_IMPLEMENTED)
+                    .body("HTTP request `Transfer-Encoding` is not supported")
+            };
+
+            http::write_response(res.close_connection(), false, stream)?;
+
+            linger_close(stream);
+
+            return Ok(false);
+        }
+
+        let start = Instant::now();
+
+        let length = match header_data
+            .headers
+            .get(&CONTENT_LENGTH)
+            .map(|length| http::parse_content_length(length))
+            .transpose()
+        {
+            Ok(length) => length,
+            Err(err) => {
+                log::debug!("invalid request head: {}", err);
+
+                http::write_response(err.to_response(), false, stream)?;
+
+                linger_close(stream);
+
+                return Ok(false);
+            }
+        };
+
+        // Bodies that would be buffered, but are left to be read after the middleware.
+        let deferred = config.defer_body
+            && !config.stream_bodies
+            && matches!(length, Some(length) if length < http::MAX_BYTES as u64);
+
+        // Bodies too large to buffer with the head are left on the socket, for the extractors to
+        // read up to their own limits, or skipped after the response if nothing reads them.
+        let payload = match length {
+            Some(length)
+                if config.stream_bodies || deferred || length >= http::MAX_BYTES as u64 =>
+            {
+                Some(Payload::new(
+                    std::mem::take(&mut body),
+                    stream.try_clone()?,
+                    length,
+                    config.body_limit,
+                    config.body_timeout,
+                ))
+            }
+            Some(length) => {
+                let read = http::read_body_to(
+                    &mut RateGuard::new(&*stream, stream, config.min_rate),
+                    &mut body,
+                    length as usize,
+                );
+
+                match read {
+                    Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                        log::debug!("{}", err);
+
+                        return Self::too_slow(stream).map(|_| false);
+                    }
+                    read => read?,
+                }
+
+                None
+            }
+            None => {
+                body.clear();
+
+                None
+            }
+        };
+
+        // Held until the response is written.
+        let _streaming = match config.max_streaming {
+            Some(max) if config.stream_bodies && payload.is_some() => {
+                match StreamingSlot::acquire(&config.streaming, max) {
+                    Some(slot) => Some(slot),
+                    None => {
+                        log::warn!("too many streamed request bodies, rejecting the request");
+
+                        http::write_response(
+                            overloaded(
+                                config,
+                                HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+                                    .body("Too many streaming requests")
+                                    .close_connection(),
+                            ),
+                            false,
+                            stream,
+                        )?;
+
+                        drain_close(stream, config.drain_limit);
+
+                        return Ok(false);
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(authority) = &header_data.authority {
+            match header_data.headers.get(&HOST) {
+                Some(host) if !same_authority(host, authority, header_data.scheme) => {
+                    match config.host_mismatch {
+                        HostMismatch::Reject => {
+                            http::write_response(
+                            HttpResponse::bad_request().body(
+                                "HTTP request target authority does not match the `Host` header",
+                            ),
+                            false,
+                            stream,
+                        )?;
+
+                            return Ok(false);
+                        }
+                        HostMismatch::PreferRequestLine => {
+                            let authority = authority.clone();
+
+                            header_data.headers.insert(HOST, authority);
+                        }
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    let authority = authority.clone();
+
+                    header_data.headers.insert(HOST, authority);
+                }
+            }
+        }
+
+        // RFC 7230 section 5.4, an HTTP/1.1 request without a `Host` is a bad request.
+        if matches!(header_data.version, http::HttpVersion::Http11)
+            && !header_data.headers.contains(&HOST)
+        {
+            http::write_response(
+                HttpResponse::bad_request().body("HTTP/1.1 request is missing a `Host` header"),
+                false,
+                stream,
+            )?;
+
+            return Ok(false);
+        }
+
+        if app.decode_slashes {
+            header_data.url = decode_slashes(&header_data.url);
+        }
+
+        let method = header_data.method;
+
+        let route = |url: &str| {
+            let live = |(endpoint, _): &(&Arc<Endpoint>, _)| !endpoint.is_removed();
+
+            app.tree
+                .get(&method)
+                .and_then(|tree| tree.find(url).filter(live))
+                // `HEAD` is answered by the `GET` route, with the body left out when it is
+                // written.
+                .or_else(|| match method {
+                    HttpMethod::Head => app
+                        .tree
+                        .get(&HttpMethod::Get)
+                        .and_then(|tree| tree.find(url).filter(live)),
+                    _ => None,
+                })
+                .or_else(|| app.any.find(url).filter(live))
+                .map(|(endpoint, params)| {
+                    let mut map: ArrayMap<String, String, 32> = ArrayMap::new();
+
+                    for (key, value) in params.into_iter() {
+                        map.insert(key.to_string(), decode_slashes(value));
+                    }
+
+                    (Arc::clone(endpoint), map)
+                })
+        };
+
+        let found = route(&header_data.url);
+
+        // Where to redirect a request that only misses a route by its trailing slash.
+        let redirect = match &found {
+            None if app.redirect_trailing_slash => {
+                let url = &header_data.url;
+
+                let toggled = match url.strip_suffix('/') {
+                    Some(trimmed) if !trimmed.is_empty() => trimmed.to_string(),
+                    Some(_) => url.clone(),
+                    None => format!("{}/", url),
+                };
+
+                Some(toggled).filter(|toggled| toggled != url && route(toggled).is_some())
+            }
+            _ => None,
         };
 
+        // The response to an `OPTIONS` or `TRACE` request without a route of its own.
+        let automatic = match (&found, method) {
+            (None, HttpMethod::Options) if app.auto_options => {
+                let methods = app.allowed_methods(&header_data.url);
+
+                (!methods.is_empty()).then(|| {
+                    let allow = methods
+                        .iter()
+                        .map(HttpMethod::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    HttpResponse::new(StatusCode::NO_CONTENT).header(ALLOW, allow)
+                })
+            }
+            (None, HttpMethod::Trace) if app.trace => Some(
+                HttpResponse::ok()
+                    .header(CONTENT_TYPE, "message/http")
+                    .body(echo_head(&header_data)),
+            ),
+            (None, HttpMethod::Trace) => {
+                Some(HttpResponse::new(StatusCode::NOT_IMPLEMENTED).body("TRACE is not enabled"))
+            }
+            _ => None,
+        };
+
+        let (endpoint, params) =
+            found.unwrap_or_else(|| (app.default_service.clone(), ArrayMap::new()));
+
+        // Compressed responses are chunked, which HTTP/1.0 clients do not understand.
+        let compress = matches!(header_data.version, http::HttpVersion::Http11)
+            && header_data
+                .headers
+                .get(&ACCEPT_ENCODING)
+                .map(|header| header.contains("deflate"))
+                .unwrap_or(false);
+
         let mut request = HttpRequest {
             header_data,
             body,
+            payload,
             params,
+            pattern: endpoint.pattern.clone(),
             data: Arc::clone(&app.data),
             extensions: Extensions::new(),
+            peer_addr: stream.peer_addr().ok(),
+            typed: Default::default(),
         };
 
-        for middleware in &*app.middleware {
-            middleware.before(&mut request);
+        if let Some(config) = endpoint.payload_config {
+            request.extensions.insert(config);
         }
 
-        let mut response = service.call(&mut request)?;
+        let mut finalize = config.finalize.as_deref().map(|hook| Finalize {
+            hook,
+            summary: RequestSummary {
+                method: request.header_data.method,
+                path: request.header_data.url.clone(),
+                pattern: request.pattern.clone(),
+                status: None,
+                elapsed: Duration::ZERO,
+                bytes_sent: 0,
+                disconnected: false,
+            },
+            start,
+            done: false,
+        });
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "request",
+            method = request.header_data.method.as_str(),
+            path = request.header_data.url.as_str(),
+            pattern = request.pattern.as_deref().unwrap_or(""),
+            status = tracing::field::Empty,
+        );
+
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        // A body that is too large is known from its length before the handler runs, so it is
+        // only still unread after a response if the handler timed out.
+        let oversized = request
+            .payload
+            .as_ref()
+            .map_or(false, Payload::exceeds_limit);
+
+        let watchdog = match endpoint.timeout.unwrap_or(config.request_timeout) {
+            Some(timeout) => Some(Watchdog::start(stream.try_clone()?, timeout, !oversized)),
+            None => None,
+        };
+
+        let contexts = app
+            .middleware
+            .iter()
+            .map(|middleware| middleware.before(&mut request))
+            .collect::<Vec<_>>();
+
+        let early = if app.allows_body(&request) {
+            app.middleware
+                .iter()
+                .zip(&contexts)
+                .find_map(|(middleware, context)| middleware.respond(&request, context))
+        } else {
+            Some(
+                app.error_format.apply(
+                    HttpResponse::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                        .body("HTTP request body has a content type that is not allowed"),
+                ),
+            )
+        };
+
+        let rejected = early.is_some();
+
+        if deferred && !rejected {
+            if let Some(mut payload) = request.payload.take() {
+                // Left in place so it is answered as too large below.
+                if payload.exceeds_limit() {
+                    request.payload = Some(payload);
+                } else {
+                    match io::Read::read_to_end(&mut payload, &mut request.body) {
+                        Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                            log::debug!("{}", err);
 
-        for middleware in &*app.middleware {
-            response = middleware.after(&request, response);
+                            return Self::too_slow(stream).map(|_| false);
+                        }
+                        read => {
+                            read?;
+                        }
+                    }
+                }
+            }
         }
 
-        http::write_response(response, compress, stream)?;
+        let call = |endpoint: &Endpoint, request: &mut HttpRequest| {
+            if !(app.catch_panics || app.debug_errors()) {
+                return endpoint.service.call(request);
+            }
 
-        Ok(())
+            panic::catch_unwind(AssertUnwindSafe(|| endpoint.service.call(request))).unwrap_or_else(
+                |payload| {
+                    #[cfg(feature = "dev")]
+                    if app.debug_errors() {
+                        return Ok(crate::error::panic_page(&*payload));
+                    }
+
+                    drop(payload);
+
+                    Err(InternalError::InternalServerError(
+                        "HTTP request handler panicked",
+                    ))
+                },
+            )
+        };
+
+        // Only responses from the routes themselves are rendered in the app's error format.
+        let routed = early.is_none() && redirect.is_none();
+
+        let result = if let Some(response) = early {
+            Ok(response)
+        } else if let Some(location) = &redirect {
+            let response = HttpResponse::redirect_keeping_query(&request, location);
+
+            Ok(if request.header_data.method.is_safe() {
+                response.status(StatusCode::MOVED_PERMANENTLY)
+            } else {
+                response
+            })
+        } else if let Some(response) = automatic {
+            Ok(response)
+        } else {
+            call(&endpoint, &mut request)
+        };
+
+        let mut default_service = Arc::ptr_eq(&endpoint, &app.default_service);
+
+        // A route that responded with `None` leaves the request to the default service, as if
+        // the route had not matched.
+        let result = match result {
+            Err(err) if !default_service && err.downcast_ref::<Unhandled>().is_some() => {
+                default_service = true;
+
+                call(&app.default_service, &mut request)
+            }
+            result => result,
+        };
+
+        let mut response = match result {
+            Ok(response) if routed && default_service => app.error_format.apply(response),
+            Ok(response) => response,
+            // The default service responded with `None` as well.
+            Err(err) if err.downcast_ref::<Unhandled>().is_some() => {
+                app.error_format.apply(err.error_response())
+            }
+            // The default service is the last resort, so an error from it gets a fixed response
+            // rather than one that could be wrong in the same way.
+            Err(err) if default_service => {
+                log::error!("default service error: {:?}", err);
+
+                app.error_format
+                    .apply(HttpResponse::internal_server_error().body("Internal Server Error"))
+            }
+            Err(err) => app.error_format.apply(err.report()),
+        };
+
+        for (middleware, context) in app.middleware.iter().zip(contexts) {
+            response = middleware.after(&request, response, context);
+        }
+
+        if let Some(watchdog) = watchdog {
+            if !watchdog.finish() {
+                if !oversized {
+                    // The client has already been sent a timeout and the connection is closed.
+                    return Ok(false);
+                }
+
+                // The client has been sent a timeout, but the connection was left open, it is now
+                // reset rather than closed, so the client does not take the response for one to
+                // its whole request.
+                let err = io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    format!(
+                        "request body is larger than the limit of {} bytes, but a response was already sent",
+                        request.payload.as_ref().map(Payload::limit).unwrap_or_default()
+                    ),
+                );
+
+                log::error!("{}, resetting the connection", err);
+
+                if let Some(mut finalize) = finalize {
+                    finalize.summary.status = Some(StatusCode::SERVICE_UNAVAILABLE);
+
+                    finalize.finish(Err(&err));
+                }
+
+                return Err(ThreadError::Reset);
+            }
+        }
+
+        // Nothing has been written yet, so a body that is too large can still be answered
+        // properly, even if the handler never looked at it.
+        let too_large = match &request.payload {
+            Some(payload) if payload.exceeds_limit() => {
+                if response.status != StatusCode::PAYLOAD_TOO_LARGE {
+                    response = HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE).body(format!(
+                        "HTTP request body is larger than the limit of {} bytes",
+                        payload.limit()
+                    ));
+                }
+
+                true
+            }
+            _ => false,
+        };
+
+        // What is left of a rejected request's body is only skipped up to the drain limit,
+        // past that it is cheaper to close the connection.
+        let abandon = config.defer_body
+            && rejected
+            && request
+                .payload
+                .as_ref()
+                .map(|payload| payload.remaining() > config.drain_limit)
+                .unwrap_or(false);
+
+        // HTTP/1.1 connections are persistent unless either side says otherwise, HTTP/1.0
+        // connections only if both sides ask for it.
+        let persistent = !abandon
+            && remaining != Some(1)
+            && (response.body_bytes().is_some()
+                || matches!(request.header_data.version, http::HttpVersion::Http11))
+            && match request.header_data.headers.get(&CONNECTION) {
+                Some(connection) if has_token(connection, "close") => false,
+                Some(connection) if has_token(connection, "keep-alive") => true,
+                _ => matches!(request.header_data.version, http::HttpVersion::Http11),
+            }
+            && !response
+                .headers
+                .get(&CONNECTION)
+                .map(|connection| has_token(connection, "close"))
+                .unwrap_or(false);
+
+        // Streamed bodies are chunked for HTTP/1.1 clients, older ones only see the end of the
+        // body when the connection closes.
+        if matches!(response.body, http::HttpBody::Stream(_))
+            && matches!(request.header_data.version, http::HttpVersion::Http11)
+        {
+            response.version = http::HttpVersion::Http11;
+        }
+
+        if !persistent {
+            response.headers.insert(CONNECTION, "close".to_string());
+        } else {
+            if !matches!(request.header_data.version, http::HttpVersion::Http11)
+                && !response.headers.contains(&CONNECTION)
+            {
+                response
+                    .headers
+                    .insert(CONNECTION, "keep-alive".to_string());
+            }
+
+            if !response.headers.contains(&KEEP_ALIVE) {
+                let hints = config
+                    .keep_alive_timeout
+                    .map(|timeout| format!("timeout={}", timeout.as_secs()))
+                    .into_iter()
+                    .chain(remaining.map(|remaining| format!("max={}", remaining)))
+                    .collect::<Vec<_>>();
+
+                if !hints.is_empty() {
+                    response.headers.insert(KEEP_ALIVE, hints.join(", "));
+                }
+            }
+        }
+
+        if let Some(name) = &config.server_header {
+            response.headers.remove_all(&SERVER);
+
+            if let Some(name) = name {
+                response.headers.insert(SERVER, name.clone());
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        span.record("status", &response.status.0);
+
+        if let Some(finalize) = &mut finalize {
+            finalize.summary.status = Some(response.status);
+        }
+
+        if let Some(limit) = config.nodelay_below {
+            let small = response
+                .body_bytes()
+                .map(|body| body.len() < limit)
+                .unwrap_or(true);
+
+            if let Err(err) = stream.set_nodelay(small) {
+                log::debug!("unable to set `TCP_NODELAY`: {}", err);
+            }
+        }
+
+        // Checked before the response is written, as a client that is not pipelining sends its
+        // next request as soon as it has the response. Whatever is left of the request's body
+        // would look like the next request.
+        let pipelined = config.max_pipelined.is_some()
+            && request
+                .payload
+                .as_ref()
+                .map_or(true, |payload| payload.remaining() == 0)
+            && is_pending(stream);
+
+        if config.cork {
+            set_cork(stream, true);
+        }
+
+        let mut counted = CountingWriter {
+            inner: &mut *stream,
+            written: 0,
+        };
+
+        let written = if request.header_data.method == HttpMethod::Head {
+            http::write_head_response(response, &mut counted)
+        } else {
+            http::write_response(response, compress, &mut counted)
+        }
+        .map_err(|err| match err.kind() {
+            // What a write timing out looks like on unix.
+            io::ErrorKind::WouldBlock => io::Error::new(
+                io::ErrorKind::TimedOut,
+                "client did not read the response within the write timeout",
+            ),
+            _ => err,
+        });
+
+        let bytes_sent = counted.written;
+        let disconnected = matches!(&written, Err(err) if is_disconnect(err));
+
+        // Uncorking sends whatever is still held back.
+        if config.cork {
+            set_cork(stream, false);
+        }
+
+        if let Some(mut finalize) = finalize {
+            finalize.summary.bytes_sent = bytes_sent;
+            finalize.summary.disconnected = disconnected;
+
+            finalize.finish(written.as_ref().map(|_| ()));
+        }
+
+        if disconnected {
+            log::debug!("client disconnected before the response was written");
+
+            let _ = stream.shutdown(Shutdown::Both);
+
+            return Ok(false);
+        }
+
+        written?;
+
+        config.requests_served.fetch_add(1, Ordering::Relaxed);
+
+        if too_large {
+            // The client is most likely still sending the body, reading it lets it see the
+            // response rather than having the connection reset under it.
+            let left = request
+                .payload
+                .as_ref()
+                .map(Payload::remaining)
+                .unwrap_or_default();
+
+            drain_close(stream, left.min(config.drain_limit));
+        } else if abandon {
+            drain_close(stream, config.drain_limit);
+        } else if !persistent {
+            linger_close(stream);
+        } else if let Some(mut payload) = request.payload.take() {
+            // Whatever the handler left unread has to be skipped to get to the next request.
+            if !payload.drain() {
+                stream.shutdown(Shutdown::Both)?;
+            }
+        }
+
+        Ok(pipelined)
+    }
+}
+
+/// Writes a request head back out, for the body of a `TRACE` response.
+fn echo_head(header_data: &http::HttpHeaderData) -> String {
+    let mut head = format!(
+        "{} {}{} {}\r\n",
+        header_data.method, header_data.url, header_data.query, header_data.version
+    );
+
+    for (name, value) in header_data.headers.iter() {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+
+    head
+}
+
+/// A request counted against [`HttpServer::max_streaming_connections`] until it is dropped.
+struct StreamingSlot<'c> {
+    count: &'c AtomicUsize,
+}
+
+impl<'c> StreamingSlot<'c> {
+    fn acquire(count: &'c AtomicUsize, max: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                (current < max).then(|| current + 1)
+            })
+            .ok()
+            .map(|_| Self { count })
+    }
+}
+
+impl<'c> Drop for StreamingSlot<'c> {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_cork(stream: &TcpStream, cork: bool) {
+    use std::os::unix::io::AsRawFd as _;
+
+    let value = cork as libc::c_int;
+
+    let res = unsafe {
+        libc::setsockopt(
+            stream.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_CORK,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if res != 0 {
+        log::debug!("unable to set `TCP_CORK`: {}", io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+const fn set_cork(_stream: &TcpStream, _cork: bool) {}
+
+/// The slowest a client can send a request, see [`HttpServer::min_request_rate`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MinRate {
+    bytes: u64,
+    window: Duration,
+}
+
+/// Reads from `inner` with a `TimedOut` error once a window passes in which the client sent
+/// less than the minimum rate, even if it never stopped sending entirely, or once the deadline
+/// has passed.
+struct RateGuard<'s, R> {
+    inner: R,
+    /// The connection `inner` reads from, for setting its read timeout.
+    stream: &'s TcpStream,
+    rate: Option<MinRate>,
+    window_start: Instant,
+    window_read: u64,
+    deadline: Option<Instant>,
+}
+
+impl<'s, R> RateGuard<'s, R> {
+    fn new(inner: R, stream: &'s TcpStream, rate: Option<MinRate>) -> Self {
+        Self {
+            inner,
+            stream,
+            rate,
+            window_start: Instant::now(),
+            window_read: 0,
+            deadline: None,
+        }
+    }
+
+    /// Fails reads once `timeout` has passed from now, see [`HttpServer::header_read_timeout`].
+    fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        self
+    }
+}
+
+impl<'s, R> io::Read for RateGuard<'s, R>
+where
+    R: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.rate.is_none() && self.deadline.is_none() {
+            return self.inner.read(buf);
+        }
+
+        loop {
+            let mut timeout = None;
+
+            if let Some(deadline) = self.deadline {
+                let now = Instant::now();
+
+                if now >= deadline {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "client did not send the request head within the timeout",
+                    ));
+                }
+
+                timeout = Some(deadline - now);
+            }
+
+            if let Some(rate) = self.rate {
+                let elapsed = self.window_start.elapsed();
+
+                if elapsed >= rate.window {
+                    if self.window_read < rate.bytes {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!(
+                                "client sent {} bytes of the request in {:?}, less than the minimum of {}",
+                                self.window_read, rate.window, rate.bytes
+                            ),
+                        ));
+                    }
+
+                    self.window_start = Instant::now();
+                    self.window_read = 0;
+
+                    continue;
+                }
+
+                let left = rate.window - elapsed;
+
+                timeout = Some(timeout.map_or(left, |timeout: Duration| timeout.min(left)));
+            }
+
+            self.stream.set_read_timeout(timeout)?;
+
+            match self.inner.read(buf) {
+                Ok(read) => {
+                    self.window_read += read as u64;
+
+                    return Ok(read);
+                }
+                // What a read timing out looks like on unix.
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err),
+            }
+        }
     }
 }
+
+impl<'s, R> Drop for RateGuard<'s, R> {
+    fn drop(&mut self) {
+        if self.rate.is_some() || self.deadline.is_some() {
+            let _ = self.stream.set_read_timeout(None);
+        }
+    }
+}
+
+/// Counts the bytes written to the socket.
+struct CountingWriter<'s> {
+    inner: &'s mut TcpStream,
+    written: u64,
+}
+
+impl<'s> io::Write for CountingWriter<'s> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        self.written += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Adds the configured `Retry-After` to a response rejecting a request while the server is
+/// shedding load.
+fn overloaded(config: &Config, res: HttpResponse) -> HttpResponse {
+    match config.retry_after {
+        Some(delay) => {
+            let secs = delay.as_secs() + (delay.subsec_nanos() > 0) as u64;
+
+            res.header(RETRY_AFTER, secs.to_string())
+        }
+        None => res,
+    }
+}
+
+/// Turns away a connection that was accepted while the server is shutting down, with a
+/// `503 Service Unavailable` rather than closing it without a response.
+fn shutting_down(config: &Config, stream: &mut TcpStream) {
+    let _ = http::write_response(
+        overloaded(
+            config,
+            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+                .close_connection()
+                .body("Service Unavailable"),
+        ),
+        false,
+        stream,
+    );
+
+    linger_close(stream);
+}
+
+/// Returns the priority of a new connection, from the path of its first request, see
+/// [`HttpServer::high_priority`].
+fn priority(config: &Config, stream: &TcpStream) -> Priority {
+    if config.priority_prefixes.is_empty() {
+        return Priority::Normal;
+    }
+
+    let mut buffer = [0; 256];
+
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(10)));
+    let peeked = stream.peek(&mut buffer);
+    let _ = stream.set_read_timeout(None);
+
+    let path = match peeked {
+        Ok(len) => buffer[..len]
+            .split(|byte| *byte == b' ')
+            .nth(1)
+            .and_then(|path| std::str::from_utf8(path).ok()),
+        Err(_) => None,
+    };
+
+    match path {
+        Some(path)
+            if config
+                .priority_prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str())) =>
+        {
+            Priority::High
+        }
+        _ => Priority::Normal,
+    }
+}
+
+/// Sets up a connection's timeouts before any requests are read from it.
+fn prepare(config: &Config, stream: &TcpStream) {
+    if let Err(err) = stream.set_read_timeout(None) {
+        log::error!(
+            "internal tcp stream error, unable to make `read` blocking: {}",
+            err
+        );
+    }
+
+    if let Err(err) = stream.set_write_timeout(config.write_timeout) {
+        log::error!(
+            "internal tcp stream error, unable to set the write timeout: {}",
+            err
+        );
+    }
+}
+
+/// Handles a request, logging why if it could not be, returning if it was pipelined (see
+/// [`HttpServer::thread_handle`]).
+fn run(
+    app: Arc<BuiltApp>,
+    config: &Config,
+    stream: &mut TcpStream,
+    remaining: Option<usize>,
+) -> Option<bool> {
+    let err = match HttpServer::thread_handle(app, config, stream, remaining) {
+        Ok(pipelined) => return Some(pipelined),
+        Err(ThreadError::Reset) => return None,
+        Err(err) => err,
+    };
+
+    if let ThreadError::Io(err) = &err {
+        if is_disconnect(err) {
+            log::debug!("client disconnected: {}", err);
+
+            let _ = stream.shutdown(Shutdown::Both);
+
+            return Some(false);
+        }
+    }
+
+    log::error!("unable to handle thread");
+
+    match err {
+        ThreadError::Http(err) => log::error!("invalid http: {}", err),
+        ThreadError::Io(err) => log::error!("{}", err),
+        ThreadError::ParseInt(err) => log::error!("{}", err),
+        ThreadError::Utf8(err) => log::error!("{}", err),
+        ThreadError::Reset => {}
+    }
+
+    Some(false)
+}
+
+/// Closes a connection that still has an unread request body, reading (and discarding) a bit
+/// of it first, as closing with unread data resets the connection, which can make the client
+/// lose the response.
+fn linger_close(stream: &mut TcpStream) {
+    const LINGER_BYTES: u64 = 64 * 1024;
+
+    drain_close(stream, LINGER_BYTES);
+}
+
+/// Like [`linger_close`], without waiting for the client, only what it has already sent is read
+/// (and discarded) before the connection is closed.
+fn reject_close(stream: &mut TcpStream) {
+    const LINGER_BYTES: u64 = 64 * 1024;
+
+    if stream.shutdown(Shutdown::Write).is_ok() && stream.set_nonblocking(true).is_ok() {
+        let _ = io::copy(
+            &mut io::Read::take(&mut *stream, LINGER_BYTES),
+            &mut io::sink(),
+        );
+    }
+
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Like [`linger_close`], reading up to `max` bytes, for as long as the client keeps sending
+/// (with gaps of at most 100ms), and no longer than `DRAIN_TIME` in total.
+fn drain_close(stream: &mut TcpStream, max: u64) {
+    const DRAIN_TIME: Duration = Duration::from_secs(5);
+
+    let start = Instant::now();
+
+    if stream.shutdown(Shutdown::Write).is_ok()
+        && stream
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .is_ok()
+    {
+        let mut buffer = [0; 8 * 1024];
+        let mut left = max;
+
+        while left > 0 && start.elapsed() < DRAIN_TIME {
+            let len = buffer.len().min(left as usize);
+
+            match io::Read::read(stream, &mut buffer[..len]) {
+                Ok(0) => break,
+                Ok(read) => left -= read as u64,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Returns if writing failed because the client has gone, which is not worth logging as an
+/// error.
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// Returns if a comma separated header value (ex. `Connection`) lists `token`.
+fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+/// Replaces percent-encoded slashes with slashes, see [`App::decode_slashes`].
+fn decode_slashes(value: &str) -> String {
+    if value.contains("%2F") || value.contains("%2f") {
+        value.replace("%2F", "/").replace("%2f", "/")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns if there is data waiting to be read on the connection.
+fn is_pending(stream: &TcpStream) -> bool {
+    let mut byte = [0u8; 1];
+
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+
+    let pending = matches!(stream.peek(&mut byte), Ok(read) if read > 0);
+
+    let _ = stream.set_nonblocking(false);
+
+    pending
+}
+
+/// Connects to the listener at `addr` to wake up a blocking `accept`.
+fn wake(mut addr: SocketAddr) -> io::Result<()> {
+    if addr.ip().is_unspecified() {
+        match addr {
+            SocketAddr::V4(_) => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+            SocketAddr::V6(_) => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+        }
+    }
+
+    TcpStream::connect_timeout(&addr, Duration::from_secs(1)).map(drop)
+}
+
+/// Compares two authorities case-insensitively, ignoring the default port of the target's
+/// `scheme`.
+fn same_authority(left: &str, right: &str, scheme: Option<&str>) -> bool {
+    fn strip<'a>(authority: &'a str, port: &str) -> &'a str {
+        let authority = authority.trim();
+
+        authority.strip_suffix(port).unwrap_or(authority)
+    }
+
+    let port = match scheme {
+        Some("https") => ":443",
+        _ => ":80",
+    };
+
+    strip(left, port).eq_ignore_ascii_case(strip(right, port))
+}
@@ -4,6 +4,12 @@ pub trait Service<Request> {
     type Error;
 
     fn call(&self, req: &mut Request) -> Result<Self::Response, Self::Error>;
+
+    /// Returns if the service, and anything it depends on, is able to handle requests, see
+    /// [`App::health_check`](crate::App::health_check).
+    fn poll_ready(&self) -> bool {
+        true
+    }
 }
 
 pub struct BoxedService<Request, Response, Error> {
@@ -27,4 +33,41 @@ impl<Request, Response, Error> const Service<Request> for BoxedService<Request,
     fn call(&self, req: &mut Request) -> Result<Self::Response, Self::Error> {
         (self.inner).call(req)
     }
+
+    fn poll_ready(&self) -> bool {
+        (self.inner).poll_ready()
+    }
+}
+
+/// Wraps `service` with `f`, which is given the request and the wrapped service, and decides
+/// if, and how, the wrapped service is called.
+///
+/// Unlike [`Middleware`](crate::middleware::Middleware), which is applied to every route, this
+/// can be used on a single service (see `Route::wrap_fn`).
+pub const fn wrap_fn<S, F>(service: S, f: F) -> WrapFn<S, F> {
+    WrapFn { service, f }
+}
+
+/// A service created by [`wrap_fn`].
+pub struct WrapFn<S, F> {
+    service: S,
+    f: F,
+}
+
+impl<S, F, Request> Service<Request> for WrapFn<S, F>
+where
+    S: Service<Request>,
+    F: Fn(&mut Request, &S) -> Result<S::Response, S::Error>,
+{
+    type Response = S::Response;
+
+    type Error = S::Error;
+
+    fn call(&self, req: &mut Request) -> Result<Self::Response, Self::Error> {
+        (self.f)(req, &self.service)
+    }
+
+    fn poll_ready(&self) -> bool {
+        self.service.poll_ready()
+    }
 }
@@ -0,0 +1,77 @@
+use std::error;
+
+use crate::{
+    error::InternalError,
+    extractor::Data,
+    http::{HttpRequest, HttpResponse},
+    Error, Responder,
+};
+
+type Render<C> =
+    dyn Fn(&str, &C) -> Result<String, Box<dyn error::Error + Send + Sync>> + Send + Sync;
+
+/// Renders the templates of [`Template`] responses with a context of type `C`, registered as app
+/// data, so any template engine can be plugged in.
+///
+/// ```no_run
+/// use std::{convert::Infallible, sync::Arc};
+///
+/// use enrgy::{web, App};
+///
+/// struct Page {
+///     title: String,
+/// }
+///
+/// App::new()
+///     .data(Arc::new(web::TemplateEngine::new(|name: &str, page: &Page| {
+///         Ok::<_, Infallible>(format!("<!-- {} --><h1>{}</h1>", name, page.title))
+///     })))
+///     .service(web::get("/").to(|| {
+///         web::Template("index.html", Page { title: "Home".to_string() })
+///     }));
+/// ```
+pub struct TemplateEngine<C> {
+    render: Box<Render<C>>,
+}
+
+impl<C> TemplateEngine<C> {
+    pub fn new<F, E>(render: F) -> Self
+    where
+        F: Fn(&str, &C) -> Result<String, E> + Send + Sync + 'static,
+        E: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Self {
+            render: box move |name, ctx| render(name, ctx).map_err(Into::into),
+        }
+    }
+}
+
+/// Responds with the template `.0` rendered with the context `.1`, as HTML, by the
+/// [`TemplateEngine`] for the context's type.
+///
+/// Responds with `500 Internal Server Error` if there is no engine for the context's type, or
+/// the template can not be rendered.
+pub struct Template<C>(pub &'static str, pub C);
+
+impl<C> Responder for Template<C>
+where
+    C: 'static,
+{
+    fn respond_to(self, req: &HttpRequest) -> Result<HttpResponse, Error> {
+        let Template(name, ctx) = self;
+
+        let engine = req.data.get::<Data<TemplateEngine<C>>>().ok_or_else(|| {
+            InternalError::InternalServerError(
+                "`Template` was used without registering a `TemplateEngine` for its context",
+            )
+        })?;
+
+        match (engine.render)(name, &ctx) {
+            Ok(body) => Ok(HttpResponse::html(body)),
+            Err(err) => Err(InternalError::InternalServerError(format!(
+                "template `{}` could not be rendered: {}",
+                name, err
+            ))),
+        }
+    }
+}
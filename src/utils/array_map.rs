@@ -75,6 +75,55 @@ impl<K, V, const SIZE: usize> ArrayMap<K, V, SIZE> {
         }
     }
 
+    /// Adds an entry even if the key already has one, returning the value back if the map is
+    /// full.
+    pub fn append(&mut self, key: K, value: V) -> Option<V> {
+        if self.len < SIZE {
+            unsafe {
+                std::ptr::write(
+                    self.map.as_mut_ptr().add(self.len),
+                    MaybeUninit::new((key, value)),
+                );
+            }
+
+            self.len += 1;
+
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Removes every entry for the key, keeping the order of the others.
+    pub fn remove_all<Q: ?Sized>(&mut self, key: &Q)
+    where
+        Q: PartialEq,
+        K: Borrow<Q>,
+    {
+        let len = self.len;
+
+        // A panicking `drop` leaks the entries after it rather than dropping them twice.
+        self.len = 0;
+
+        let mut kept = 0;
+
+        for index in 0..len {
+            let entry = unsafe { std::ptr::read(self.map[index].as_ptr()) };
+
+            if key.eq(entry.0.borrow()) {
+                drop(entry);
+            } else {
+                unsafe {
+                    std::ptr::write(self.map.as_mut_ptr().add(kept), MaybeUninit::new(entry));
+                }
+
+                kept += 1;
+            }
+        }
+
+        self.len = kept;
+    }
+
     #[must_use]
     pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
     where
@@ -109,6 +158,17 @@ impl<K, V, const SIZE: usize> ArrayMap<K, V, SIZE> {
         None
     }
 
+    /// Removes every entry, returning them in insertion order.
+    pub fn drain(&mut self) -> Vec<(K, V)> {
+        let len = self.len;
+
+        self.len = 0;
+
+        (0..len)
+            .map(|index| unsafe { std::ptr::read(self.map[index].as_ptr()) })
+            .collect()
+    }
+
     pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool
     where
         Q: PartialEq,
@@ -150,6 +210,31 @@ impl<'m, K, V, const SIZE: usize> IntoIterator for &'m ArrayMap<K, V, SIZE> {
     }
 }
 
+impl<K, V, const SIZE: usize> Clone for ArrayMap<K, V, SIZE>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        let mut map = Self::new();
+
+        for (key, value) in self.as_slice() {
+            unsafe {
+                std::ptr::write(
+                    map.map.as_mut_ptr().add(map.len),
+                    MaybeUninit::new((key.clone(), value.clone())),
+                );
+            }
+
+            // Only counting the entry once it is written keeps a panicking `clone` from
+            // dropping uninitialized memory.
+            map.len += 1;
+        }
+
+        map
+    }
+}
+
 impl<K, V, const SIZE: usize> fmt::Debug for ArrayMap<K, V, SIZE>
 where
     K: fmt::Debug,
@@ -0,0 +1,114 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+};
+
+/// A bounded pool of byte buffers, so connections can reuse the buffers of those before them
+/// instead of allocating their own.
+pub(crate) struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    /// The most buffers kept, any more that are returned are freed.
+    max: usize,
+}
+
+impl BufferPool {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max)),
+            max,
+        }
+    }
+
+    /// Takes a buffer out of the pool, or allocates one with a capacity of `size` if it is
+    /// empty, it is returned when dropped.
+    pub(crate) fn take(&self, size: usize) -> PooledBuffer<'_> {
+        let buffer = self
+            .buffers
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(size));
+
+        PooledBuffer {
+            pool: self,
+            size,
+            buffer,
+        }
+    }
+}
+
+/// A buffer taken from a [`BufferPool`].
+pub(crate) struct PooledBuffer<'p> {
+    pool: &'p BufferPool,
+    size: usize,
+    buffer: Vec<u8>,
+}
+
+impl<'p> Deref for PooledBuffer<'p> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl<'p> DerefMut for PooledBuffer<'p> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl<'p> Drop for PooledBuffer<'p> {
+    fn drop(&mut self) {
+        let mut buffer = std::mem::take(&mut self.buffer);
+
+        // Buffers that grew for one large request are not kept at that size.
+        buffer.clear();
+        buffer.shrink_to(self.size);
+
+        let mut buffers = self
+            .pool
+            .buffers
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+
+        if buffers.len() < self.pool.max {
+            buffers.push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reuse() {
+        let pool = BufferPool::new(1);
+
+        let mut first = pool.take(16);
+
+        first.extend_from_slice(b"leftover");
+
+        let ptr = first.as_ptr();
+
+        drop(first);
+
+        let second = pool.take(16);
+
+        // The same allocation, cleared.
+        assert_eq!(second.as_ptr(), ptr);
+        assert!(second.is_empty());
+        assert!(second.capacity() >= 16);
+
+        // The pool is empty until it is returned, and only keeps one.
+        let third = pool.take(16);
+
+        assert_ne!(third.as_ptr(), ptr);
+
+        drop(second);
+        drop(third);
+
+        assert_eq!(pool.buffers.lock().unwrap().len(), 1);
+    }
+}
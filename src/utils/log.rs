@@ -0,0 +1,42 @@
+//! Forwards to the `log` crate when the `log` feature is enabled, otherwise the arguments are
+//! only type checked.
+
+pub(crate) macro error($($arg:tt)+) {{
+    #[cfg(feature = "log")]
+    ::log::error!($($arg)+);
+
+    #[cfg(not(feature = "log"))]
+    let _ = format_args!($($arg)+);
+}}
+
+pub(crate) macro warn($($arg:tt)+) {{
+    #[cfg(feature = "log")]
+    ::log::warn!($($arg)+);
+
+    #[cfg(not(feature = "log"))]
+    let _ = format_args!($($arg)+);
+}}
+
+pub(crate) macro info($($arg:tt)+) {{
+    #[cfg(feature = "log")]
+    ::log::info!($($arg)+);
+
+    #[cfg(not(feature = "log"))]
+    let _ = format_args!($($arg)+);
+}}
+
+pub(crate) macro debug($($arg:tt)+) {{
+    #[cfg(feature = "log")]
+    ::log::debug!($($arg)+);
+
+    #[cfg(not(feature = "log"))]
+    let _ = format_args!($($arg)+);
+}}
+
+pub(crate) macro trace($($arg:tt)+) {{
+    #[cfg(feature = "log")]
+    ::log::trace!($($arg)+);
+
+    #[cfg(not(feature = "log"))]
+    let _ = format_args!($($arg)+);
+}}
@@ -1,9 +1,14 @@
 pub(crate) mod signal;
 
 pub(crate) mod array_map;
+pub(crate) mod buffer_pool;
+pub(crate) mod hmac;
+pub(crate) mod log;
 pub(crate) mod path_tree;
+pub(crate) mod random;
 pub(crate) mod string;
 pub(crate) mod thread_pool;
+pub(crate) mod watchdog;
 
 pub(crate) use self::{array_map::ArrayMap, path_tree::PathTree, string::StringExt};
 
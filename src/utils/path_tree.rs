@@ -18,9 +18,61 @@ pub enum NodeKind {
 pub struct Node<T> {
     kind: NodeKind,
     data: Option<T>,
-    indices: Option<String>,
+    /// The first character of each child, kept sorted (alongside `nodes`) for binary searching.
+    indices: Option<Vec<char>>,
     nodes: Option<Vec<Self>>,
     params: Option<Vec<String>>,
+    /// The types `params` have to parse as, see [`Constraint`].
+    constraints: Option<Vec<Option<Constraint>>>,
+}
+
+/// The type of an inline constrained parameter (ex. `{id:u32}`), a value that does not parse as
+/// it does not match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Constraint {
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+}
+
+impl Constraint {
+    fn parse(ty: &str) -> Option<Self> {
+        Some(match ty {
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "usize" => Self::Usize,
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "isize" => Self::Isize,
+            _ => return None,
+        })
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            Self::U8 => value.parse::<u8>().is_ok(),
+            Self::U16 => value.parse::<u16>().is_ok(),
+            Self::U32 => value.parse::<u32>().is_ok(),
+            Self::U64 => value.parse::<u64>().is_ok(),
+            Self::Usize => value.parse::<usize>().is_ok(),
+            Self::I8 => value.parse::<i8>().is_ok(),
+            Self::I16 => value.parse::<i16>().is_ok(),
+            Self::I32 => value.parse::<i32>().is_ok(),
+            Self::I64 => value.parse::<i64>().is_ok(),
+            Self::Isize => value.parse::<isize>().is_ok(),
+        }
+    }
 }
 
 impl<T> const Default for Node<T> {
@@ -39,24 +91,25 @@ impl<T> Node<T> {
             data: None,
             nodes: None,
             params: None,
+            constraints: None,
             indices: None,
         }
     }
 
     fn add_node(&mut self, c: char, kind: NodeKind) -> &mut Self {
-        let indices: &mut String = self.indices.get_or_insert_with(String::new);
+        let indices: &mut Vec<char> = self.indices.get_or_insert_with(Vec::new);
         let nodes: &mut Vec<Node<T>> = self.nodes.get_or_insert_with(Vec::new);
 
-        match position(indices, c) {
-            Some(i) => match kind {
+        match indices.binary_search(&c) {
+            Ok(i) => match kind {
                 NodeKind::Static(ref s) => nodes[i].insert(s),
                 _ => &mut nodes[i],
             },
-            None => {
-                indices.push(c);
-                nodes.push(Node::new(kind));
+            Err(i) => {
+                indices.insert(i, c);
+                nodes.insert(i, Node::new(kind));
 
-                nodes.last_mut().unwrap()
+                &mut nodes[i]
             }
         }
     }
@@ -92,8 +145,9 @@ impl<T> Node<T> {
                     let mut node = Node {
                         data: None,
                         params: None,
+                        constraints: None,
                         nodes: Some(Vec::new()),
-                        indices: s.chars().next().map(|c| c.to_string()),
+                        indices: s.chars().next().map(|c| vec![c]),
                         kind: NodeKind::Static(String::from(&p[0..l])),
                     };
 
@@ -113,9 +167,35 @@ impl<T> Node<T> {
         }
     }
 
-    /// Returns a reference to the node corresponding to the path.
+    /// Returns a reference to the node corresponding to the path, if its parameters meet their
+    /// constraints, otherwise the next alternative is tried (ex. a catch-all).
     #[inline]
-    fn find<'a>(&'a self, mut p: &'a str, params: &mut Vec<&'a str>) -> Option<&'a Self> {
+    fn find<'a>(&'a self, p: &'a str, params: &mut Vec<&'a str>) -> Option<&'a Self> {
+        let len = params.len();
+
+        let node = self
+            .find_node(p, params)
+            .filter(|node| node.accepts(params));
+
+        // The values of an alternative that did not match would be taken for the next one's.
+        if node.is_none() {
+            params.truncate(len);
+        }
+
+        node
+    }
+
+    fn accepts(&self, values: &[&str]) -> bool {
+        match &self.constraints {
+            Some(constraints) => constraints
+                .iter()
+                .zip(values)
+                .all(|(constraint, value)| constraint.map_or(true, |c| c.accepts(value))),
+            None => true,
+        }
+    }
+
+    fn find_node<'a>(&'a self, mut p: &'a str, params: &mut Vec<&'a str>) -> Option<&'a Self> {
         match self.kind {
             NodeKind::Static(ref s) => {
                 let l = loc_count(s, p);
@@ -236,7 +316,17 @@ impl<T> PathTree<T> {
     }
 
     /// Inserts a path and data into tree.
-    pub fn insert(&mut self, mut path: &str, data: T) -> &mut Self {
+    ///
+    /// Besides `:name` and `*name`, parameters can be written as `{name}`, `{name:*}` for a
+    /// catch-all, or `{name:u32}` to only match values that parse as the integer type.
+    ///
+    /// # Panics
+    ///
+    /// If a parameter has a type that is not an integer type.
+    pub fn insert(&mut self, path: &str, data: T) -> &mut Self {
+        let (path, constraints) = expand(path);
+        let mut path = path.as_str();
+
         let mut next = true;
         let mut node = &mut self.root;
         let mut params: Option<Vec<String>> = None;
@@ -250,6 +340,8 @@ impl<T> PathTree<T> {
             return self;
         }
 
+        let constraints = constraints.iter().any(Option::is_some).then(|| constraints);
+
         while next {
             match path.chars().position(has_colon_or_star) {
                 Some(i) => {
@@ -301,10 +393,28 @@ impl<T> PathTree<T> {
 
         node.data = Some(data);
         node.params = params;
+        node.constraints = constraints;
 
         self
     }
 
+    /// Returns the data of every path in the tree, in no particular order.
+    pub fn values(&self) -> Vec<&T> {
+        fn collect<'a, T>(node: &'a Node<T>, values: &mut Vec<&'a T>) {
+            values.extend(node.data.as_ref());
+
+            for node in node.nodes.iter().flatten() {
+                collect(node, values);
+            }
+        }
+
+        let mut values = Vec::new();
+
+        collect(&self.root, &mut values);
+
+        values
+    }
+
     /// Returns a reference to the node data and params corresponding to the path.
     pub fn find<'a>(&'a self, path: &'a str) -> Option<(&'a T, Vec<(&'a str, &'a str)>)> {
         let mut values = Vec::with_capacity(self.params);
@@ -326,6 +436,81 @@ impl<T> PathTree<T> {
     }
 }
 
+/// Returns the patterns a route's pattern is inserted as, both with and without its last segment
+/// if it is an optional parameter (ex. `/docs/{page?}`).
+pub(crate) fn expand_optional(pattern: &str) -> Vec<String> {
+    match pattern
+        .strip_suffix("?}")
+        .and_then(|pattern| pattern.rsplit_once("/{"))
+    {
+        Some((prefix, name)) if !name.contains(&['{', '}', '/'][..]) => vec![
+            format!("{}/{{{}}}", prefix, name),
+            if prefix.is_empty() { "/" } else { prefix }.to_string(),
+        ],
+        _ => vec![pattern.to_string()],
+    }
+}
+
+/// Rewrites `{name}` parameters to `:name` (or `*name`) and returns the constraint of each
+/// parameter, in order.
+fn expand(pattern: &str) -> (String, Vec<Option<Constraint>>) {
+    let mut path = String::with_capacity(pattern.len());
+    let mut constraints = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(i) = rest.find(&['{', ':', '*'][..]) {
+        path.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let end = match rest.find('}') {
+            Some(end) if rest.starts_with('{') => end,
+            _ => {
+                constraints.push(None);
+                path.push_str(&rest[..1]);
+                rest = &rest[1..];
+
+                continue;
+            }
+        };
+
+        if rest[1..end].ends_with('?') {
+            panic!(
+                "optional parameters can only be the last segment, in `{}`",
+                pattern
+            );
+        }
+
+        match rest[1..end].split_once(':') {
+            Some((name, "*")) => {
+                path.push('*');
+                path.push_str(name);
+                constraints.push(None);
+            }
+            Some((name, ty)) => {
+                path.push(':');
+                path.push_str(name);
+                constraints.push(Some(Constraint::parse(ty).unwrap_or_else(|| {
+                    panic!(
+                        "unknown type `{}` for parameter `{}` in `{}`",
+                        ty, name, pattern
+                    )
+                })));
+            }
+            None => {
+                path.push(':');
+                path.push_str(&rest[1..end]);
+                constraints.push(None);
+            }
+        }
+
+        rest = &rest[(end + 1)..];
+    }
+
+    path.push_str(rest);
+
+    (path, constraints)
+}
+
 #[inline]
 const fn has_colon_or_star(c: char) -> bool {
     (c == ':') | (c == '*')
@@ -337,15 +522,95 @@ const fn has_star_or_slash(c: char) -> bool {
 }
 
 #[inline]
-fn position(p: &str, c: char) -> Option<usize> {
-    p.chars().position(|x| x == c)
+fn position(p: &[char], c: char) -> Option<usize> {
+    p.binary_search(&c).ok()
 }
 
+/// Returns the length in bytes of the common prefix of `s` and `p`, ending on a char boundary.
 #[inline]
 fn loc_count(s: &str, p: &str) -> usize {
-    s.chars()
-        .zip(p.chars())
-        .take_while(|(a, b)| a == b)
-        .map(|(c, _)| c.len_utf8())
-        .sum()
+    let mut l = s.bytes().zip(p.bytes()).take_while(|(a, b)| a == b).count();
+
+    while !s.is_char_boundary(l) {
+        l -= 1;
+    }
+
+    l
+}
+
+#[cfg(test)]
+mod test {
+    use super::PathTree;
+
+    #[test]
+    fn test_find() {
+        let mut tree = PathTree::new();
+
+        tree.insert("/", 0);
+        tree.insert("/users", 1);
+        tree.insert("/users/:id", 2);
+        tree.insert("/users/new", 3);
+        tree.insert("/users/:id/posts/:post", 4);
+        tree.insert("/files/*path", 5);
+        tree.insert("/δt", 6);
+        tree.insert("/δ¶", 7);
+
+        assert_eq!(tree.find("/"), Some((&0, vec![])));
+        assert_eq!(tree.find("/users"), Some((&1, vec![])));
+        assert_eq!(tree.find("/users/42"), Some((&2, vec![("id", "42")])));
+        assert_eq!(tree.find("/users/new"), Some((&3, vec![])));
+        assert_eq!(
+            tree.find("/users/42/posts/7"),
+            Some((&4, vec![("id", "42"), ("post", "7")]))
+        );
+        assert_eq!(
+            tree.find("/files/css/site.css"),
+            Some((&5, vec![("path", "css/site.css")]))
+        );
+        assert_eq!(tree.find("/δt"), Some((&6, vec![])));
+        assert_eq!(tree.find("/δ¶"), Some((&7, vec![])));
+        assert_eq!(tree.find("/posts"), None);
+    }
+
+    #[test]
+    fn test_expand_optional() {
+        use super::expand_optional;
+
+        assert_eq!(
+            expand_optional("/docs/{section}/{page?}"),
+            ["/docs/{section}/{page}", "/docs/{section}"]
+        );
+        assert_eq!(expand_optional("/{page?}"), ["/{page}", "/"]);
+        assert_eq!(expand_optional("/docs/{page}"), ["/docs/{page}"]);
+    }
+
+    #[test]
+    fn test_constraints() {
+        let mut tree = PathTree::new();
+
+        tree.insert("/users/{id:u32}", 0);
+        tree.insert("/users/{id:u32}/posts/{post}", 1);
+        tree.insert("/files/{name:*}", 2);
+        tree.insert("/pages/{page:u8}", 3);
+        tree.insert("/pages/*slug", 4);
+        tree.insert("/posts/{id:u32}/comments", 5);
+        tree.insert("/posts/*rest", 6);
+
+        assert_eq!(tree.find("/users/42"), Some((&0, vec![("id", "42")])));
+        assert_eq!(tree.find("/users/bob"), None);
+        assert_eq!(
+            tree.find("/users/42/posts/hello"),
+            Some((&1, vec![("id", "42"), ("post", "hello")]))
+        );
+        assert_eq!(
+            tree.find("/files/css/site.css"),
+            Some((&2, vec![("name", "css/site.css")]))
+        );
+        assert_eq!(tree.find("/pages/7"), Some((&3, vec![("page", "7")])));
+        assert_eq!(tree.find("/pages/700"), Some((&4, vec![("slug", "700")])));
+        assert_eq!(
+            tree.find("/posts/new/comments"),
+            Some((&6, vec![("rest", "new/comments")]))
+        );
+    }
 }
@@ -0,0 +1,25 @@
+/// Fills `bytes` from the OS's cryptographically secure random number generator.
+#[cfg(unix)]
+pub(crate) fn fill(bytes: &mut [u8]) -> std::io::Result<()> {
+    use std::{fs::File, io::Read};
+
+    File::open("/dev/urandom")?.read_exact(bytes)
+}
+
+/// Fills `bytes` from std's hasher keys, which are seeded from the OS's cryptographically
+/// secure random number generator, each hash being keyed differently.
+#[cfg(not(unix))]
+pub(crate) fn fill(bytes: &mut [u8]) -> std::io::Result<()> {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+
+    for chunk in bytes.chunks_mut(8) {
+        let value = RandomState::new().build_hasher().finish().to_le_bytes();
+
+        chunk.copy_from_slice(&value[..chunk.len()]);
+    }
+
+    Ok(())
+}
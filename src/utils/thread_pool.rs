@@ -1,46 +1,374 @@
 use std::{
+    collections::VecDeque,
+    io,
     marker::PhantomData,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        mpsc::{self, Receiver, RecvTimeoutError, Sender},
-        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::RecvTimeoutError,
+        Arc, Condvar, Mutex,
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use crate::utils::log;
+
+/// Which of a pool's workers are currently handling a job, shared between the pool and any
+/// [`ServerHandle`](crate::ServerHandle)s.
+pub struct PoolState {
+    busy: Box<[AtomicBool]>,
+    /// Connections sent to the pool that are waiting for a worker or being handled.
+    assigned: AtomicUsize,
+}
+
+impl PoolState {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            busy: (0..size).map(|_| AtomicBool::new(false)).collect(),
+            assigned: AtomicUsize::new(0),
+        }
+    }
+
+    /// Counts a connection as sent to the pool, the worker that handles it uncounts it.
+    pub(crate) fn assign(&self) {
+        self.assigned.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Uncounts a connection that was never handed to the pool.
+    pub(crate) fn unassign(&self) {
+        self.assigned.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Returns if every worker already has a connection, so another one would have to wait.
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.assigned.load(Ordering::SeqCst) >= self.busy.len()
+    }
+
+    pub(crate) fn stats(&self) -> PoolStats {
+        PoolStats {
+            busy: self
+                .busy
+                .iter()
+                .map(|busy| busy.load(Ordering::SeqCst))
+                .collect(),
+        }
+    }
+}
+
+/// A snapshot of a server's worker pool, see [`ServerHandle::pool_stats`](crate::ServerHandle::pool_stats).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolStats {
+    busy: Vec<bool>,
+}
+
+impl PoolStats {
+    /// The number of workers in the pool.
+    pub fn size(&self) -> usize {
+        self.busy.len()
+    }
+
+    /// The number of workers handling a connection.
+    pub fn busy(&self) -> usize {
+        self.busy.iter().filter(|busy| **busy).count()
+    }
+
+    /// The number of workers waiting for a connection.
+    pub fn idle(&self) -> usize {
+        self.size() - self.busy()
+    }
+
+    /// Returns if the worker with the given id is busy, or `None` if there is no such worker.
+    pub fn is_busy(&self, worker: usize) -> Option<bool> {
+        self.busy.get(worker).copied()
+    }
+}
+
+/// How soon a job is handled, see [`JobSender::send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Normal,
+    /// Handled before any waiting [`Priority::Normal`] job.
+    High,
+}
+
+/// Why [`JobSender::send_timeout`] handed a job back.
+pub enum SendTimeoutError<Data> {
+    /// The queue stayed full.
+    Timeout(Data),
+    /// The workers have all stopped.
+    Disconnected(Data),
+}
+
+struct Jobs<Data> {
+    high: VecDeque<Data>,
+    normal: VecDeque<Data>,
+    senders: usize,
+    /// How many jobs can wait at once, see [`JobSender::send_timeout`].
+    capacity: Option<usize>,
+    /// Workers that are still taking jobs.
+    workers: usize,
+    /// Workers that have finished their `init`, see [`ThreadPool::wait_started`].
+    started: usize,
+}
+
+/// The jobs waiting for a worker, like a `mpsc` channel with a second lane for
+/// [`Priority::High`] jobs.
+struct Queue<Data> {
+    jobs: Mutex<Jobs<Data>>,
+    ready: Condvar,
+    /// Signalled when a job is taken, making room for another.
+    space: Condvar,
+    started: Condvar,
+}
+
+impl<Data> Queue<Data> {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Jobs<Data>> {
+        self.jobs.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<Data, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut jobs = self.lock();
+
+        loop {
+            if let Some(data) = jobs.high.pop_front().or_else(|| jobs.normal.pop_front()) {
+                drop(jobs);
+
+                self.space.notify_one();
+
+                return Ok(data);
+            }
+
+            if jobs.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            jobs = self
+                .ready
+                .wait_timeout(jobs, deadline - now)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+    }
+
+    /// Stops a worker from taking jobs, unless one was queued since it last looked, checked
+    /// under the same lock as [`JobSender::send`] so a job is never left without a worker.
+    fn retire(&self) -> bool {
+        let mut jobs = self.lock();
+
+        if jobs.high.is_empty() && jobs.normal.is_empty() {
+            jobs.workers -= 1;
+
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Sends jobs to a [`ThreadPool`]'s workers.
+pub struct JobSender<Data> {
+    queue: Arc<Queue<Data>>,
+}
+
+impl<Data> JobSender<Data> {
+    /// Queues `data` for the next free worker, handing it back if the workers have all stopped.
+    ///
+    /// Waits for as long as the queue is full.
+    pub fn send(&self, data: Data, priority: Priority) -> Result<(), Data> {
+        self.push(data, priority, None).map_err(|err| match err {
+            SendTimeoutError::Timeout(data) | SendTimeoutError::Disconnected(data) => data,
+        })
+    }
+
+    /// Like [`JobSender::send`], but gives up (handing `data` back) if the queue is still full
+    /// after `timeout`.
+    pub fn send_timeout(
+        &self,
+        data: Data,
+        priority: Priority,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<Data>> {
+        self.push(data, priority, Some(Instant::now() + timeout))
+    }
+
+    fn push(
+        &self,
+        data: Data,
+        priority: Priority,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<Data>> {
+        let mut jobs = self.queue.lock();
+
+        loop {
+            if jobs.workers == 0 {
+                return Err(SendTimeoutError::Disconnected(data));
+            }
+
+            let queued = jobs.high.len() + jobs.normal.len();
+
+            if jobs
+                .capacity
+                .map(|capacity| queued < capacity)
+                .unwrap_or(true)
+            {
+                break;
+            }
+
+            jobs = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        return Err(SendTimeoutError::Timeout(data));
+                    }
+
+                    self.queue
+                        .space
+                        .wait_timeout(jobs, deadline - now)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+                None => self
+                    .queue
+                    .space
+                    .wait(jobs)
+                    .unwrap_or_else(|err| err.into_inner()),
+            };
+        }
+
+        match priority {
+            Priority::High => jobs.high.push_back(data),
+            Priority::Normal => jobs.normal.push_back(data),
+        }
+
+        drop(jobs);
+
+        self.queue.ready.notify_one();
+
+        Ok(())
+    }
+}
+
+impl<Data> Clone for JobSender<Data> {
+    fn clone(&self) -> Self {
+        self.queue.lock().senders += 1;
+
+        Self {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<Data> Drop for JobSender<Data> {
+    fn drop(&mut self) {
+        let mut jobs = self.queue.lock();
+
+        jobs.senders -= 1;
+
+        if jobs.senders == 0 {
+            drop(jobs);
+
+            self.queue.ready.notify_all();
+        }
+    }
+}
+
+/// Marks a worker as started when dropped, see [`ThreadPool::wait_started`].
+struct Started<'q, Data>(&'q Queue<Data>);
+
+impl<'q, Data> Drop for Started<'q, Data> {
+    fn drop(&mut self) {
+        self.0.lock().started += 1;
+
+        self.0.started.notify_all();
+    }
+}
+
 pub struct ThreadPool<Data>
 where
     Data: Send + Sync + 'static,
 {
     workers: Vec<Worker<Data>>,
+    queue: Arc<Queue<Data>>,
 }
 
 impl<Data> ThreadPool<Data>
 where
     Data: Send + Sync + 'static,
 {
-    pub fn new<F>(size: usize, close: Arc<AtomicBool>, handler: F) -> (Self, Sender<Data>)
+    /// Starts the workers, each spawned with the [`thread::Builder`] `builder` returns for its
+    /// id (to set the thread's name or stack size), and calling `init` with its id before it
+    /// handles anything.
+    ///
+    /// At most `capacity` jobs wait for a worker at once, `None` has no limit.
+    pub fn with_builder<B, I, F>(
+        state: Arc<PoolState>,
+        close: Arc<AtomicBool>,
+        capacity: Option<usize>,
+        builder: B,
+        init: I,
+        handler: F,
+    ) -> io::Result<(Self, JobSender<Data>)>
     where
+        B: Fn(usize) -> thread::Builder,
+        I: Fn(usize) + Clone + Send + Sync + 'static,
         F: Fn(Data) + Clone + Send + Sync + 'static,
     {
-        let (sender, receiver) = mpsc::channel();
+        let queue = Arc::new(Queue {
+            jobs: Mutex::new(Jobs {
+                high: VecDeque::new(),
+                normal: VecDeque::new(),
+                senders: 1,
+                capacity,
+                workers: state.busy.len(),
+                started: 0,
+            }),
+            ready: Condvar::new(),
+            space: Condvar::new(),
+            started: Condvar::new(),
+        });
 
-        let receiver = Arc::new(Mutex::new(receiver));
+        let sender = JobSender {
+            queue: Arc::clone(&queue),
+        };
 
-        let workers = (0..size)
+        let workers = (0..state.busy.len())
             .into_iter()
             .map(|id| {
                 Worker::new(
                     id,
+                    builder(id),
+                    Arc::clone(&state),
                     Arc::clone(&close),
-                    Arc::clone(&receiver),
+                    Arc::clone(&queue),
+                    init.clone(),
                     handler.clone(),
                 )
             })
-            .collect();
+            .collect::<io::Result<_>>()?;
+
+        Ok((Self { workers, queue }, sender))
+    }
 
-        (Self { workers }, sender)
+    /// Waits until every worker has finished its `init` (or panicked in it), and is ready for
+    /// jobs.
+    pub fn wait_started(&self) {
+        let mut jobs = self.queue.lock();
+
+        while jobs.started < self.workers.len() {
+            jobs = self
+                .queue
+                .started
+                .wait(jobs)
+                .unwrap_or_else(|err| err.into_inner());
+        }
     }
 
     pub fn join(self) {
@@ -63,44 +391,70 @@ impl<Data> Worker<Data>
 where
     Data: Send + Sync + 'static,
 {
-    fn new<F>(
+    fn new<I, F>(
         id: usize,
+        builder: thread::Builder,
+        state: Arc<PoolState>,
         close: Arc<AtomicBool>,
-        receiver: Arc<Mutex<Receiver<Data>>>,
+        queue: Arc<Queue<Data>>,
+        init: I,
         handle: F,
-    ) -> Self
+    ) -> io::Result<Self>
     where
+        I: Fn(usize) + Send + 'static,
         F: Fn(Data) + Clone + Send + Sync + 'static,
     {
-        let thread = thread::spawn(move || Self::inner(id, close, receiver, handle));
+        let thread = builder.spawn(move || {
+            // Counts the worker as started even if `init` panics, so nothing waits on it forever.
+            let started = Started(&queue);
 
-        Self {
+            init(id);
+
+            drop(started);
+
+            Self::inner(id, state, close, &queue, handle);
+        })?;
+
+        Ok(Self {
             id,
             thread,
             _data: PhantomData,
-        }
+        })
     }
 
-    fn inner<F>(id: usize, close: Arc<AtomicBool>, receiver: Arc<Mutex<Receiver<Data>>>, handle: F)
-    where
+    fn inner<F>(
+        id: usize,
+        state: Arc<PoolState>,
+        close: Arc<AtomicBool>,
+        queue: &Queue<Data>,
+        handle: F,
+    ) where
         F: Fn(Data) + Clone + Send + Sync + 'static,
     {
         loop {
-            let received = {
-                let receiver = receiver.lock().unwrap();
-
-                receiver.recv_timeout(Duration::from_millis(100))
-            };
-
-            match received {
+            match queue.recv_timeout(Duration::from_millis(100)) {
                 Ok(data) => {
                     log::trace!("worker {} received a request", id);
 
-                    handle(data)
+                    state.busy[id].store(true, Ordering::SeqCst);
+
+                    handle(data);
+
+                    state.busy[id].store(false, Ordering::SeqCst);
+                    state.assigned.fetch_sub(1, Ordering::SeqCst);
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    queue.lock().workers -= 1;
+
+                    // Senders waiting for room see that there is no one left to take their jobs.
+                    queue.space.notify_all();
+
+                    break;
                 }
-                Err(RecvTimeoutError::Disconnected) => break,
                 Err(RecvTimeoutError::Timeout) => {
-                    if close.load(Ordering::SeqCst) {
+                    if close.load(Ordering::SeqCst) && queue.retire() {
+                        log::debug!("worker {} is idle, shutting down", id);
+
                         break;
                     }
                 }
@@ -114,3 +468,163 @@ where
         log::trace!("shutdown worker {}", self.id);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn test_with_builder() {
+        const STACK: usize = 4 * 1024 * 1024;
+
+        let state = Arc::new(PoolState::new(2));
+        let (results, received) = mpsc::channel();
+        let results = Arc::new(Mutex::new(results));
+
+        let (pool, sender) = ThreadPool::with_builder(
+            Arc::clone(&state),
+            Arc::new(AtomicBool::new(false)),
+            None,
+            |id| {
+                thread::Builder::new()
+                    .name(format!("pool-{}", id))
+                    .stack_size(2 * STACK)
+            },
+            |_| {},
+            move |len: usize| {
+                // Larger than the default stack, so it only fits in the builder's.
+                let buffer = [1u8; STACK];
+
+                let sum = buffer[..len]
+                    .iter()
+                    .map(|byte| *byte as usize)
+                    .sum::<usize>();
+
+                results
+                    .lock()
+                    .unwrap()
+                    .send((thread::current().name().map(str::to_string), sum))
+                    .unwrap();
+            },
+        )
+        .unwrap();
+
+        for len in [1, 2, 3] {
+            state.assign();
+            sender.send(len, Priority::Normal).unwrap();
+        }
+
+        let mut results = (0..3).map(|_| received.recv().unwrap()).collect::<Vec<_>>();
+
+        results.sort_by_key(|(_, sum)| *sum);
+
+        for ((name, sum), len) in results.into_iter().zip([1, 2, 3]) {
+            assert!(matches!(name.as_deref(), Some("pool-0" | "pool-1")));
+            assert_eq!(sum, len);
+        }
+
+        drop(sender);
+
+        pool.join();
+    }
+
+    #[test]
+    fn test_priority() {
+        let state = Arc::new(PoolState::new(1));
+        let (order, received) = mpsc::channel();
+        let order = Arc::new(Mutex::new(order));
+        let (release, blocked) = mpsc::channel::<()>();
+        let blocked = Arc::new(Mutex::new(blocked));
+
+        let (pool, sender) = ThreadPool::with_builder(
+            Arc::clone(&state),
+            Arc::new(AtomicBool::new(false)),
+            None,
+            |_| thread::Builder::new(),
+            |_| {},
+            move |job: usize| {
+                // The first job holds the only worker until the backlog is queued.
+                if job == 0 {
+                    blocked.lock().unwrap().recv().unwrap();
+                }
+
+                order.lock().unwrap().send(job).unwrap();
+            },
+        )
+        .unwrap();
+
+        sender.send(0, Priority::Normal).unwrap();
+
+        while state.stats().is_busy(0) != Some(true) {
+            thread::yield_now();
+        }
+
+        for job in 1..=16 {
+            sender.send(job, Priority::Normal).unwrap();
+        }
+
+        sender.send(100, Priority::High).unwrap();
+
+        release.send(()).unwrap();
+
+        let order = (0..18)
+            .map(|_| received.recv().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(order[..3], [0, 100, 1]);
+        assert_eq!(order[3..], (2..=16).collect::<Vec<_>>()[..]);
+
+        drop(sender);
+
+        pool.join();
+    }
+
+    #[test]
+    fn test_send_timeout() {
+        let state = Arc::new(PoolState::new(1));
+        let (release, blocked) = mpsc::channel::<()>();
+        let blocked = Arc::new(Mutex::new(blocked));
+
+        let (pool, sender) = ThreadPool::with_builder(
+            Arc::clone(&state),
+            Arc::new(AtomicBool::new(false)),
+            Some(1),
+            |_| thread::Builder::new(),
+            |_| {},
+            move |_: usize| {
+                blocked.lock().unwrap().recv().unwrap();
+            },
+        )
+        .unwrap();
+
+        sender.send(0, Priority::Normal).unwrap();
+
+        while state.stats().is_busy(0) != Some(true) {
+            thread::yield_now();
+        }
+
+        assert!(sender
+            .send_timeout(1, Priority::Normal, Duration::from_millis(10))
+            .is_ok());
+        assert!(matches!(
+            sender.send_timeout(2, Priority::High, Duration::from_millis(10)),
+            Err(SendTimeoutError::Timeout(2))
+        ));
+
+        release.send(()).unwrap();
+
+        // The worker takes the queued job, making room for another.
+        assert!(sender
+            .send_timeout(3, Priority::Normal, Duration::from_secs(5))
+            .is_ok());
+
+        release.send(()).unwrap();
+        release.send(()).unwrap();
+
+        drop(sender);
+
+        pool.join();
+    }
+}
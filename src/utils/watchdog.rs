@@ -0,0 +1,218 @@
+use std::{
+    collections::BTreeMap,
+    lazy::SyncLazy,
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    http::{self, HttpResponse, StatusCode},
+    utils::log,
+};
+
+/// The one thread that answers requests that time out, shared by every server.
+static TIMER: SyncLazy<Timer> = SyncLazy::new(|| {
+    thread::Builder::new()
+        .name("enrgy-watchdog".to_string())
+        .spawn(|| TIMER.run())
+        .expect("unable to start the watchdog thread");
+
+    Timer {
+        pending: Mutex::new(Pending {
+            next_id: 0,
+            entries: BTreeMap::new(),
+        }),
+        changed: Condvar::new(),
+    }
+});
+
+struct Timer {
+    pending: Mutex<Pending>,
+    changed: Condvar,
+}
+
+struct Pending {
+    next_id: u64,
+    /// Ordered by deadline, the id tells apart requests with the same one.
+    entries: BTreeMap<(Instant, u64), Entry>,
+}
+
+struct Entry {
+    stream: TcpStream,
+    timeout: Duration,
+    close: bool,
+    responded: Arc<AtomicBool>,
+}
+
+impl Timer {
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn run(&self) {
+        let mut pending = self.lock();
+
+        loop {
+            let now = Instant::now();
+
+            pending = match pending.entries.keys().next().copied() {
+                Some(key) if key.0 <= now => {
+                    if let Some(entry) = pending.entries.remove(&key) {
+                        drop(pending);
+
+                        entry.expire();
+                    }
+
+                    self.lock()
+                }
+                Some((deadline, _)) => {
+                    self.changed
+                        .wait_timeout(pending, deadline - now)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(pending)
+                    .unwrap_or_else(|err| err.into_inner()),
+            };
+        }
+    }
+}
+
+impl Entry {
+    fn expire(mut self) {
+        if self.responded.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        log::warn!("request handler timed out after {:?}", self.timeout);
+
+        // Every timeout waits on this write, so a client that is not reading can only hold it
+        // up for so long.
+        let _ = self.stream.set_write_timeout(Some(Duration::from_secs(1)));
+
+        let _ = http::write_response(
+            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE)
+                .body("HTTP request handler timed out"),
+            false,
+            &mut self.stream,
+        );
+
+        if self.close {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Responds with `503 Service Unavailable` and closes the connection if a request is not
+/// finished within its timeout, or leaves it open for the request's worker to reset if `close`
+/// is `false`.
+///
+/// Handlers can not be interrupted, one that times out keeps running but its response is
+/// thrown away.
+pub(crate) struct Watchdog {
+    key: (Instant, u64),
+    responded: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(stream: TcpStream, timeout: Duration, close: bool) -> Self {
+        let responded = Arc::new(AtomicBool::new(false));
+
+        let mut pending = TIMER.lock();
+
+        let key = (Instant::now() + timeout, pending.next_id);
+
+        pending.next_id += 1;
+        pending.entries.insert(
+            key,
+            Entry {
+                stream,
+                timeout,
+                close,
+                responded: Arc::clone(&responded),
+            },
+        );
+
+        let earliest = pending.entries.keys().next() == Some(&key);
+
+        drop(pending);
+
+        // The timer only has to wake up early if this is now the first deadline.
+        if earliest {
+            TIMER.changed.notify_one();
+        }
+
+        Self { key, responded }
+    }
+
+    /// Stops the watchdog, returning `false` if it has already responded to the request.
+    pub(crate) fn finish(self) -> bool {
+        let finished = !self.responded.swap(true, Ordering::SeqCst);
+
+        TIMER.lock().entries.remove(&self.key);
+
+        finished
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Read, net::TcpListener};
+
+    use super::*;
+
+    fn pair(listener: &TcpListener) -> (TcpStream, TcpStream) {
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        (client, server)
+    }
+
+    #[test]
+    fn test_deadlines() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+
+        let (mut slow, slow_server) = pair(&listener);
+        let (mut fast, fast_server) = pair(&listener);
+        let (mut finished, finished_server) = pair(&listener);
+
+        let start = Instant::now();
+
+        let _slow = Watchdog::start(slow_server, Duration::from_millis(400), true);
+        // Started later, but due first, so the timer has to wake up early for it.
+        let _fast = Watchdog::start(fast_server, Duration::from_millis(50), true);
+        let watchdog = Watchdog::start(
+            finished_server.try_clone().unwrap(),
+            Duration::from_millis(50),
+            true,
+        );
+
+        assert!(watchdog.finish());
+
+        let mut response = String::new();
+        fast.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.0 503 Service Unavailable\r\n"));
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        let mut response = String::new();
+        slow.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.0 503 Service Unavailable\r\n"));
+        assert!(start.elapsed() >= Duration::from_millis(400));
+
+        // Finished in time, so nothing was written to it.
+        finished
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+
+        assert!(finished.read(&mut [0; 1]).is_err());
+    }
+}
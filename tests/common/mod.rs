@@ -0,0 +1,67 @@
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+};
+
+use enrgy::{App, HttpServer, Unbound};
+
+pub fn serve(app: App) -> SocketAddr {
+    serve_with(HttpServer::new(app))
+}
+
+/// Like [`serve`], for a server with its own options.
+pub fn serve_with(server: HttpServer<Unbound>) -> SocketAddr {
+    let server = server
+        .disable_signals()
+        .bind(([127, 0, 0, 1], 0))
+        .listen()
+        .unwrap();
+
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || server.run());
+
+    addr
+}
+
+pub fn request(addr: SocketAddr, raw: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+
+    stream.write_all(raw.as_bytes()).unwrap();
+
+    read_response(&mut stream)
+}
+
+pub fn read_response(stream: &mut TcpStream) -> String {
+    let mut response = Vec::new();
+    let mut buffer = [0; 512];
+
+    loop {
+        let read = stream.read(&mut buffer).unwrap();
+
+        if read == 0 {
+            break;
+        }
+
+        response.extend_from_slice(&buffer[..read]);
+
+        let text = String::from_utf8_lossy(&response);
+
+        if let Some(index) = text.find("\r\n\r\n") {
+            let length = text[..index]
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .and_then(|len| len.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+
+            if response.len() >= index + 4 + length {
+                break;
+            }
+        }
+    }
+
+    String::from_utf8(response).unwrap()
+}
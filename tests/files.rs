@@ -0,0 +1,281 @@
+mod common;
+
+use std::{fs, path::PathBuf, sync::Arc};
+
+use common::{request, serve};
+use enrgy::{web, App};
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("enrgy-files-{}-{}", std::process::id(), name));
+
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+#[test]
+fn test_files() {
+    let dir = dir("serve");
+
+    fs::write(dir.join("index.html"), "<h1>Hello World!</h1>").unwrap();
+
+    let addr = serve(App::new().service(web::get("/static/*file").service(web::Files::new(&dir))));
+
+    let response = request(
+        addr,
+        "GET /static/index.html HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    );
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.contains("\r\nContent-Type: text/html; charset=utf-8\r\n"));
+    assert!(response.ends_with("\r\n\r\n<h1>Hello World!</h1>"));
+
+    for path in ["/static/missing.html", "/static/../files.rs"] {
+        let response = request(
+            addr,
+            &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path),
+        );
+
+        assert!(
+            response.starts_with("HTTP/1.0 404 Not Found\r\n"),
+            "{}",
+            path
+        );
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_files_cache() {
+    let dir = dir("cache");
+
+    fs::write(dir.join("app.js"), "one").unwrap();
+
+    let addr = serve(
+        App::new().service(web::get("/static/*file").service(web::Files::new(&dir).cache(true))),
+    );
+
+    let raw = "GET /static/app.js HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    assert!(request(addr, raw).ends_with("\r\n\r\none"));
+
+    fs::write(dir.join("app.js"), "two!").unwrap();
+
+    assert!(request(addr, raw).ends_with("\r\n\r\none"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "dev")]
+#[test]
+fn test_files_watch() {
+    let dir = dir("watch");
+
+    fs::write(dir.join("app.js"), "one").unwrap();
+
+    let addr =
+        serve(App::new().service(
+            web::get("/static/*file").service(web::Files::new(&dir).cache(true).watch(true)),
+        ));
+
+    let raw = "GET /static/app.js HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    assert!(request(addr, raw).ends_with("\r\n\r\none"));
+
+    fs::write(dir.join("app.js"), "two!").unwrap();
+
+    assert!(request(addr, raw).ends_with("\r\n\r\ntwo!"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_files_ranges() {
+    let dir = dir("ranges");
+
+    fs::write(dir.join("data.txt"), "0123456789abcdefghij").unwrap();
+
+    let addr = serve(App::new().service(web::get("/static/*file").service(web::Files::new(&dir))));
+
+    let range = |range: &str| {
+        request(
+            addr,
+            &format!(
+                "GET /static/data.txt HTTP/1.1\r\nHost: localhost\r\nRange: {}\r\n\r\n",
+                range
+            ),
+        )
+    };
+
+    let response = range("bytes=2-5");
+
+    assert!(response.starts_with("HTTP/1.0 206 Partial Content\r\n"));
+    assert!(response.contains("\r\nContent-Range: bytes 2-5/20\r\n"));
+    assert!(response.ends_with("\r\n\r\n2345"));
+
+    let response = range("bytes=0-3, 16-");
+
+    assert!(response.starts_with("HTTP/1.0 206 Partial Content\r\n"));
+
+    let boundary = response
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Type: multipart/byteranges; boundary="))
+        .unwrap();
+
+    let body = response.split_once("\r\n\r\n").unwrap().1;
+
+    assert_eq!(
+        body,
+        format!(
+            "--{0}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-3/20\r\n\r\n0123\r\n\
+             --{0}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 16-19/20\r\n\r\nghij\r\n\
+             --{0}--\r\n",
+            boundary
+        )
+    );
+
+    // Overlapping ranges get the whole file.
+    let response = range("bytes=0-9, 5-14");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n0123456789abcdefghij"));
+
+    let response = range("bytes=30-40");
+
+    assert!(response.starts_with("HTTP/1.0 416 Range Not Satisfiable\r\n"));
+    assert!(response.contains("\r\nContent-Range: bytes */20\r\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_files_conditional() {
+    let dir = dir("conditional");
+
+    fs::write(dir.join("data.txt"), "0123456789").unwrap();
+
+    let header = |response: &str, name: &str| {
+        response
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+            .map(str::to_string)
+    };
+
+    for strong in [false, true] {
+        let addr = serve(App::new().service(
+            web::get("/static/*file").service(web::Files::new(&dir).strong_etags(strong)),
+        ));
+
+        let get = |headers: &str| {
+            request(
+                addr,
+                &format!(
+                    "GET /static/data.txt HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                    headers
+                ),
+            )
+        };
+
+        let response = get("");
+
+        let etag = header(&response, "ETag").unwrap();
+        let last_modified = header(&response, "Last-Modified").unwrap();
+
+        assert_eq!(etag.starts_with("W/\""), !strong, "{}", etag);
+        assert!(last_modified.ends_with(" GMT"));
+
+        let response = get(&format!("If-None-Match: {}\r\n", etag));
+
+        assert!(response.starts_with("HTTP/1.0 304 Not Modified\r\n"));
+        assert_eq!(header(&response, "ETag"), Some(etag.clone()));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let response = get("If-None-Match: \"other\", *\r\n");
+
+        assert!(response.starts_with("HTTP/1.0 304 Not Modified\r\n"));
+
+        let response = get("If-None-Match: \"other\"\r\n");
+
+        assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+        let response = get(&format!("If-Modified-Since: {}\r\n", last_modified));
+
+        assert!(response.starts_with("HTTP/1.0 304 Not Modified\r\n"));
+
+        let response = get("If-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT\r\n");
+
+        assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+        // `If-None-Match` takes precedence.
+        let response = get(&format!(
+            "If-None-Match: \"other\"\r\nIf-Modified-Since: {}\r\n",
+            last_modified
+        ));
+
+        assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+        // Only a strong `ETag` can be used for `If-Range`.
+        let response = get(&format!("Range: bytes=2-5\r\nIf-Range: {}\r\n", etag));
+
+        if strong {
+            assert!(response.ends_with("\r\n\r\n2345"));
+        } else {
+            assert!(response.ends_with("\r\n\r\n0123456789"));
+        }
+
+        let response = get(&format!(
+            "Range: bytes=2-5\r\nIf-Range: {}\r\n",
+            last_modified
+        ));
+
+        assert!(response.ends_with("\r\n\r\n2345"));
+
+        let response = get("Range: bytes=2-5\r\nIf-Range: \"other\"\r\n");
+
+        assert!(response.ends_with("\r\n\r\n0123456789"));
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_secure_path() {
+    let dir = dir("secure");
+
+    fs::create_dir_all(dir.join("base/nested")).unwrap();
+    fs::write(dir.join("base/nested/file.txt"), "inside").unwrap();
+    fs::write(dir.join("secret.txt"), "outside").unwrap();
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(dir.join("secret.txt"), dir.join("base/escape.txt")).unwrap();
+
+    let addr = serve(
+        App::new()
+            .data(Arc::new(web::SecurePathConfig::new(dir.join("base"))))
+            .service(
+                web::get("/files/*file")
+                    .to(|path: web::SecurePath<"file">| fs::read_to_string(&*path).unwrap()),
+            ),
+    );
+
+    let get = |path: &str| {
+        request(
+            addr,
+            &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path),
+        )
+    };
+
+    let response = get("/files/nested/file.txt");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\ninside"));
+
+    assert!(get("/files/nested/../../secret.txt").starts_with("HTTP/1.0 403 Forbidden\r\n"));
+    assert!(get("/files/nested/missing.txt").starts_with("HTTP/1.0 404 Not Found\r\n"));
+
+    #[cfg(unix)]
+    assert!(get("/files/escape.txt").starts_with("HTTP/1.0 403 Forbidden\r\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,260 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use std::{io::Write, net::TcpStream, sync::Arc};
+
+use common::{read_response, request, serve, serve_with};
+use enrgy::{http::HttpResponse, web, App, HttpServer};
+
+fn echo(body: web::Json<serde_json::Value>) -> String {
+    body.to_string()
+}
+
+fn post(content_type: &str, body: &str) -> String {
+    format!(
+        "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn test_json() {
+    let addr = serve(App::new().service(web::post("/").to(echo)));
+
+    let response = request(addr, &post("application/json; charset=utf-8", "{\"a\":1}"));
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n{\"a\":1}"));
+
+    let response = request(addr, &post("application/json", "{\"a\":"));
+
+    assert!(response.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+}
+
+#[test]
+fn test_json_limit() {
+    let addr = serve(
+        App::new()
+            .data(Arc::new(web::JsonConfig::new().limit(16)))
+            .service(web::post("/").to(echo)),
+    );
+
+    let response = request(
+        addr,
+        &post("application/json", "{\"a\":\"0123456789abcdef\"}"),
+    );
+
+    assert!(response.starts_with("HTTP/1.0 413 Payload Too Large\r\n"));
+}
+
+#[test]
+fn test_json_larger_than_head_buffer() {
+    let addr = serve(App::new().service(web::post("/").to(echo)));
+
+    // Past what is read along with the head, but within the 256 KiB `JsonConfig` limit.
+    let body = format!("{{\"a\":\"{}\"}}", "b".repeat(10 * 1024));
+
+    let response = request(addr, &post("application/json", &body));
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with(&format!("\r\n\r\n{}", body)));
+
+    let body = format!("{{\"a\":\"{}\"}}", "b".repeat(300 * 1024));
+
+    let response = request(addr, &post("application/json", &body));
+
+    assert!(response.starts_with("HTTP/1.0 413 Payload Too Large\r\n"));
+}
+
+#[test]
+fn test_json_content_type() {
+    let lenient = serve(App::new().service(web::post("/").to(echo)));
+    let strict = serve(
+        App::new()
+            .data(Arc::new(web::JsonConfig::new().strict(true)))
+            .service(web::post("/").to(echo)),
+    );
+
+    let response = request(lenient, &post("application/vnd.api+json", "{\"a\":1}"));
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+    let response = request(strict, &post("application/vnd.api+json", "{\"a\":1}"));
+
+    assert!(response.starts_with("HTTP/1.0 415 Unsupported Media Type\r\n"));
+
+    for addr in [lenient, strict] {
+        let response = request(addr, &post("text/plain", "{\"a\":1}"));
+
+        assert!(response.starts_with("HTTP/1.0 415 Unsupported Media Type\r\n"));
+    }
+}
+
+#[test]
+fn test_extractor_limits() {
+    const MIB: usize = 1024 * 1024;
+
+    fn upload(body: web::Bytes<{ 10 * 1024 * 1024 }>) -> String {
+        format!("{} bytes", body.len())
+    }
+
+    let server = || {
+        HttpServer::new(
+            App::new()
+                .data(Arc::new(web::JsonConfig::new().limit(64 * 1024)))
+                .service(web::post("/upload").to(upload))
+                .service(web::post("/").to(echo)),
+        )
+        .body_limit(16 * MIB as u64)
+    };
+
+    // Whether or not bodies are streamed, the extractors read them up to their own limits.
+    for addr in [serve_with(server().stream_bodies()), serve_with(server())] {
+        // Larger than the default `PayloadConfig` limit, but within the extractor's own.
+        let mut stream = TcpStream::connect(addr).unwrap();
+
+        write!(
+            stream,
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+            10 * MIB
+        )
+        .unwrap();
+        stream.write_all(&vec![b'a'; 10 * MIB]).unwrap();
+
+        let response = read_response(&mut stream);
+
+        assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n10485760 bytes"));
+
+        let response = request(
+            addr,
+            &format!(
+                "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+                10 * MIB + 1
+            ),
+        );
+
+        assert!(response.starts_with("HTTP/1.0 413 Payload Too Large\r\n"));
+
+        let response = request(
+            addr,
+            &post(
+                "application/json",
+                &format!("\"{}\"", "a".repeat(64 * 1024)),
+            ),
+        );
+
+        assert!(response.starts_with("HTTP/1.0 413 Payload Too Large\r\n"));
+
+        let response = request(addr, &post("application/json", "{\"a\":1}"));
+
+        assert!(response.ends_with("\r\n\r\n{\"a\":1}"));
+    }
+}
+
+#[test]
+fn test_json_ref() {
+    let addr = serve(
+        App::new()
+            .service(web::post("/borrowed").to(|body: web::JsonRef| {
+                let names = match body.get::<Vec<&str>>() {
+                    Ok(names) => names,
+                    Err(err) => return HttpResponse::bad_request().body(err.to_string()),
+                };
+
+                let bytes = body.as_bytes().as_ptr_range();
+                let borrowed = names
+                    .iter()
+                    .filter(|name| bytes.contains(&name.as_ptr()))
+                    .count();
+
+                HttpResponse::text(format!("{} of {} borrowed", borrowed, names.len()))
+            }))
+            .service(
+                web::post("/owned")
+                    .to(|body: web::Json<Vec<String>>| format!("{} owned", body.len())),
+            ),
+    );
+
+    let names = "[\"alice\", \"bob\", \"carol\"]";
+
+    let response = request(
+        addr,
+        &post("application/json", names).replace("POST /", "POST /borrowed"),
+    );
+
+    assert!(
+        response.ends_with("\r\n\r\n3 of 3 borrowed"),
+        "{}",
+        response
+    );
+
+    let response = request(
+        addr,
+        &post("application/json", names).replace("POST /", "POST /owned"),
+    );
+
+    assert!(response.ends_with("\r\n\r\n3 owned"), "{}", response);
+
+    let response = request(
+        addr,
+        &post("application/json", "[\"alice\"").replace("POST /", "POST /borrowed"),
+    );
+
+    assert!(response.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+}
+
+#[test]
+fn test_empty_body() {
+    let addr = serve(
+        App::new()
+            .service(web::post("/json").to(echo))
+            .service(
+                web::post("/optional").to(
+                    |body: Option<web::Json<serde_json::Value>>| match body {
+                        Some(body) => body.to_string(),
+                        None => "no body".to_string(),
+                    },
+                ),
+            )
+            .service(web::post("/form").to(|name: web::Form<"name">| name.to_string()))
+            .service(
+                web::post("/bytes").to(|body: web::Bytes<1024>| format!("{} bytes", body.len())),
+            ),
+    );
+
+    let empty = |path: &str| {
+        request(
+            addr,
+            &format!(
+                "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n",
+                path
+            ),
+        )
+    };
+
+    let response = empty("/json");
+
+    assert!(response.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+    assert!(response.ends_with("\r\n\r\nHTTP request body is empty, expected JSON"));
+
+    assert!(empty("/optional").ends_with("\r\n\r\nno body"));
+
+    let response = request(
+        addr,
+        &post("application/json", "[1,2]").replace("POST /", "POST /optional"),
+    );
+
+    assert!(response.ends_with("\r\n\r\n[1,2]"));
+
+    let response = empty("/form");
+
+    assert!(response.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+    assert!(response
+        .ends_with("\r\n\r\nHTTP request body is empty, expected a form with the key `name`"));
+
+    assert!(empty("/bytes").ends_with("\r\n\r\n0 bytes"));
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "log")]
+
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{request, serve};
+use enrgy::{http::HttpRequest, middleware::Middleware, web, App, MockClock};
+
+struct Capture {
+    records: Arc<Mutex<Vec<(log::Level, String)>>>,
+}
+
+impl log::Log for Capture {
+    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        self.records
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+struct Named(&'static str);
+
+impl<Res> Middleware<HttpRequest, Res> for Named {
+    fn before(&self, _req: &mut HttpRequest) -> Self::Context {}
+
+    fn after(&self, _req: &HttpRequest, res: Res, _ctx: Self::Context) -> Res {
+        res
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+// Loggers are global, so everything is checked in one test.
+#[test]
+fn test_logging() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+
+    log::set_logger(Box::leak(Box::new(Capture {
+        records: Arc::clone(&records),
+    })))
+    .unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    let logged = |level: log::Level, start: &str| {
+        records
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(l, line)| *l == level && line.starts_with(start))
+    };
+
+    let clock = Arc::new(MockClock::new());
+    let slow = Arc::clone(&clock);
+
+    let addr = serve(
+        App::with_defaults()
+            .clock(clock)
+            .wrap(Named("first"))
+            .wrap(Named("second"))
+            .service(web::get("/").to(|| "Hello World!"))
+            .service(web::get("/slow").to(move || {
+                slow.advance(Duration::from_millis(1500));
+
+                "Slow"
+            }))
+            .service(web::get("/missing").to(|id: web::Query<"id">| id.to_string()))
+            .service(web::get("/broken").to(|| -> &'static str { panic!("broken") })),
+    );
+
+    assert!(logged(log::Level::Info, &format!("listening on {}", addr)));
+    assert!(logged(
+        log::Level::Info,
+        "middleware, in order: enrgy::middleware::default_headers::DefaultHeaders, \
+         enrgy::middleware::logger::Logger, first, second"
+    ));
+
+    request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(logged(log::Level::Info, "GET / 200 0ms"));
+
+    request(addr, "GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(logged(log::Level::Info, "GET /slow 200 1500ms"));
+
+    request(addr, "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(logged(log::Level::Warn, "route handler error"));
+
+    request(addr, "GET /broken HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(logged(log::Level::Error, "route handler error"));
+}
@@ -0,0 +1,729 @@
+mod common;
+
+use std::{
+    any::TypeId,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use common::{request, serve};
+use enrgy::{
+    dev::Service as _,
+    http::{
+        headers::{HttpHeaderName, CACHE_CONTROL, CONTENT_TYPE, SET_COOKIE, VARY, X_FRAME_OPTIONS},
+        HttpMethod, HttpRequest, HttpResponse, StatusCode,
+    },
+    middleware::{Cors, Csrf, FetchMetadata, HttpsRedirect, Idempotency, Middleware, Session},
+    web, App, MockClock,
+};
+
+#[test]
+fn test_with_defaults() {
+    let addr = serve(App::with_defaults().service(web::get("/").to(|| "Hello World!")));
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+    for header in [
+        "\r\nX-Content-Type-Options: nosniff\r\n",
+        "\r\nX-Frame-Options: DENY\r\n",
+        "\r\nReferrer-Policy: strict-origin-when-cross-origin\r\n",
+        concat!("\r\nServer: enrgy/", env!("CARGO_PKG_VERSION"), "\r\n"),
+    ] {
+        assert!(response.contains(header), "missing {:?}", header);
+    }
+}
+
+#[test]
+fn test_catch_panics() {
+    let addr = serve(
+        App::new()
+            .catch_panics()
+            .service(web::get("/").to(|| -> &'static str { panic!("handler panic") })),
+    );
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"));
+
+    // The worker survived, and still answers on a new connection.
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"));
+}
+
+#[cfg(feature = "dev")]
+#[test]
+fn test_debug_errors() {
+    let app = |debug| {
+        App::new()
+            .debug_errors(debug)
+            .service(web::get("/").to(|| -> &'static str { panic!("handler <panic>") }))
+    };
+
+    let response = request(
+        serve(app(true)),
+        "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    );
+
+    assert!(response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"));
+    assert!(response.contains("\r\nContent-Type: text/html; charset=utf-8\r\n"));
+    assert!(response.contains("<pre>handler &lt;panic&gt;</pre>"));
+
+    let response = request(
+        serve(app(false).catch_panics()),
+        "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    );
+
+    assert!(response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"));
+    assert!(!response.contains("&lt;panic&gt;"));
+}
+
+struct Uppercase;
+
+impl Middleware<HttpRequest, HttpResponse> for Uppercase {
+    fn before(&self, _req: &mut HttpRequest) -> Self::Context {}
+
+    fn after(
+        &self,
+        _req: &HttpRequest,
+        mut res: HttpResponse,
+        _ctx: Self::Context,
+    ) -> HttpResponse {
+        if let Some(body) = res.body_mut() {
+            body.make_ascii_uppercase();
+        }
+
+        res
+    }
+}
+
+#[test]
+fn test_body_mut() {
+    let addr = serve(
+        App::new()
+            .wrap(Uppercase)
+            .service(web::get("/").to(|| "Hello World!")),
+    );
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.ends_with("\r\n\r\nHELLO WORLD!"));
+}
+
+/// Lets browsers cache images, going by the content type the handler set.
+struct CacheImages;
+
+impl Middleware<HttpRequest, HttpResponse> for CacheImages {
+    fn before(&self, _req: &mut HttpRequest) -> Self::Context {}
+
+    fn after(&self, _req: &HttpRequest, res: HttpResponse, _ctx: Self::Context) -> HttpResponse {
+        let image = res
+            .content_type()
+            .map_or(false, |content_type| content_type.starts_with("image/"));
+
+        if image && res.get_header(&CACHE_CONTROL).is_none() {
+            res.header(CACHE_CONTROL, "max-age=3600")
+        } else {
+            res
+        }
+    }
+}
+
+#[test]
+fn test_response_getters() {
+    let addr = serve(
+        App::new()
+            .wrap(CacheImages)
+            .service(web::get("/logo.png").to(|| {
+                HttpResponse::ok()
+                    .header(CONTENT_TYPE, "image/png")
+                    .body("PNG")
+            }))
+            .service(web::get("/avatar.png").to(|| {
+                HttpResponse::ok()
+                    .header(CONTENT_TYPE, "image/png")
+                    .header(CACHE_CONTROL, "no-store")
+                    .body("PNG")
+            }))
+            .service(web::get("/").to(|| HttpResponse::html("<h1>Hello World!</h1>"))),
+    );
+
+    let response = request(addr, "GET /logo.png HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.contains("\r\nCache-Control: max-age=3600\r\n"));
+
+    let response = request(addr, "GET /avatar.png HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.contains("\r\nCache-Control: no-store\r\n"));
+    assert!(!response.contains("max-age"));
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(!response.contains("Cache-Control"));
+
+    let res = HttpResponse::ok()
+        .header(CONTENT_TYPE, "text/plain")
+        .append_header(SET_COOKIE, "a=1")
+        .append_header(SET_COOKIE, "b=2");
+
+    assert_eq!(res.content_type(), Some("text/plain"));
+    assert_eq!(
+        res.iter_headers()
+            .filter(|(key, _)| **key == SET_COOKIE)
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>(),
+        ["a=1", "b=2"]
+    );
+}
+
+#[test]
+fn test_wrap_fn() {
+    let addr = serve(
+        App::new()
+            .service(
+                web::get("/wrapped")
+                    .to(|| "Hello World!")
+                    .wrap_fn(|req, next| {
+                        let res = next.call(req)?;
+
+                        Ok(res.header(X_FRAME_OPTIONS, "DENY"))
+                    }),
+            )
+            .service(web::get("/").to(|| "Hello World!")),
+    );
+
+    let response = request(addr, "GET /wrapped HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.contains("\r\nX-Frame-Options: DENY\r\n"));
+    assert!(response.ends_with("\r\n\r\nHello World!"));
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(!response.contains("X-Frame-Options"));
+    assert!(response.ends_with("\r\n\r\nHello World!"));
+}
+
+#[test]
+fn test_app_wrap_fn() {
+    let addr = serve(
+        App::new()
+            .wrap(Uppercase)
+            .wrap_fn(|req, srv| {
+                let res = srv.call(req)?;
+
+                Ok(res.header(X_FRAME_OPTIONS, "DENY"))
+            })
+            .wrap_fn(|req, srv| {
+                if req.header_data.url == "/private" {
+                    return Ok(HttpResponse::new(StatusCode::FORBIDDEN).body("Forbidden"));
+                }
+
+                srv.call(req)
+            })
+            .service(web::get("/").to(|| "Hello World!"))
+            .service(web::get("/private").to(|| "Secret")),
+    );
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.contains("\r\nX-Frame-Options: DENY\r\n"));
+    assert!(response.ends_with("\r\n\r\nHELLO WORLD!"));
+
+    // Short-circuited, but still wrapped by the outer `wrap_fn` and the middleware.
+    let response = request(addr, "GET /private HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 403 Forbidden\r\n"));
+    assert!(response.contains("\r\nX-Frame-Options: DENY\r\n"));
+    assert!(response.ends_with("\r\n\r\nFORBIDDEN"));
+
+    let response = request(addr, "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 404 Not Found\r\n"));
+    assert!(response.contains("\r\nX-Frame-Options: DENY\r\n"));
+}
+
+#[test]
+fn test_mount() {
+    let v2 = App::new()
+        .wrap(Uppercase)
+        .service(web::get("/users").to(|| "users"))
+        .service(web::get("/users/:id").to(|id: web::Param<"id">| format!("user {}", *id)));
+
+    let addr = serve(
+        App::new()
+            .service(web::get("/users").to(|| "users"))
+            .mount("/v2", v2),
+    );
+
+    let response = request(addr, "GET /v2/users HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.ends_with("\r\n\r\nUSERS"));
+
+    let response = request(
+        addr,
+        "GET /v2/users/abc HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    );
+
+    assert!(response.ends_with("\r\n\r\nUSER ABC"));
+
+    let response = request(addr, "GET /users HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.ends_with("\r\n\r\nusers"));
+}
+
+#[test]
+fn test_cors_max_age() {
+    const PREFLIGHT: &str = "OPTIONS /api HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\nAccess-Control-Request-Method: PUT\r\nAccess-Control-Request-Headers: Content-Type\r\n\r\n";
+
+    let addr = serve(
+        App::new()
+            .wrap(
+                Cors::new()
+                    .allow_origin("https://example.com")
+                    .allow_methods(&[HttpMethod::Get, HttpMethod::Put])
+                    .max_age(Duration::from_secs(600)),
+            )
+            .service(web::put("/api").to(|| "updated")),
+    );
+
+    let response = request(addr, PREFLIGHT);
+
+    assert!(response.starts_with("HTTP/1.0 204 No Content\r\n"));
+
+    for header in [
+        "\r\nAccess-Control-Allow-Origin: https://example.com\r\n",
+        "\r\nAccess-Control-Allow-Methods: GET, PUT\r\n",
+        "\r\nAccess-Control-Allow-Headers: Content-Type\r\n",
+        "\r\nAccess-Control-Max-Age: 600\r\n",
+    ] {
+        assert!(response.contains(header), "{}", header);
+    }
+
+    let response = request(
+        addr,
+        "PUT /api HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\n\r\n",
+    );
+
+    assert!(response.contains("\r\nAccess-Control-Allow-Origin: https://example.com\r\n"));
+    assert!(!response.contains("Access-Control-Max-Age"));
+    assert!(response.ends_with("\r\n\r\nupdated"));
+
+    let response = request(
+        addr,
+        &PREFLIGHT.replace("https://example.com", "https://evil.example"),
+    );
+
+    assert!(!response.contains("Access-Control-Allow-Origin"));
+
+    for cors in [Cors::new(), Cors::new().max_age(Duration::ZERO)] {
+        let addr = serve(App::new().wrap(cors));
+
+        let response = request(addr, PREFLIGHT);
+
+        assert!(response.starts_with("HTTP/1.0 204 No Content\r\n"));
+        assert!(!response.contains("Access-Control-Max-Age"));
+    }
+}
+
+#[test]
+fn test_cors_preflight_skips_route() {
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+    let addr = serve(
+        App::new()
+            .wrap(Cors::new())
+            .service(web::options("/api").to(|| {
+                CALLED.fetch_add(1, Ordering::SeqCst);
+
+                "options"
+            }))
+            .service(web::get("/api").to(|| {
+                HttpResponse::ok()
+                    .header(VARY, "Accept-Encoding")
+                    .body("api")
+            })),
+    );
+
+    let response = request(
+        addr,
+        "OPTIONS /api HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\nAccess-Control-Request-Method: GET\r\n\r\n",
+    );
+
+    assert!(response.starts_with("HTTP/1.0 204 No Content\r\n"));
+    assert!(response.contains("\r\nAccess-Control-Allow-Origin: https://example.com\r\n"));
+    assert_eq!(CALLED.load(Ordering::SeqCst), 0);
+
+    let response = request(
+        addr,
+        "GET /api HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\n\r\n",
+    );
+
+    assert!(response.contains("\r\nVary: Accept-Encoding, Origin\r\n"));
+    assert!(response.ends_with("\r\n\r\napi"));
+}
+
+#[test]
+fn test_csrf() {
+    static SUBMITTED: AtomicUsize = AtomicUsize::new(0);
+
+    let addr = serve(
+        App::new()
+            .wrap(Csrf::new(b"secret"))
+            .service(web::get("/form").to(|token: web::CsrfToken| token.to_string()))
+            .service(web::post("/submit").to(|| {
+                SUBMITTED.fetch_add(1, Ordering::SeqCst);
+
+                "submitted"
+            })),
+    );
+
+    let response = request(addr, "GET /form HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+
+    let (_, token) = response.split_once("\r\n\r\n").unwrap();
+
+    assert!(response.contains(&format!(
+        "\r\nSet-Cookie: csrf_token={}; Path=/; SameSite=Strict\r\n",
+        token
+    )));
+
+    let response = request(
+        addr,
+        &format!(
+            "POST /submit HTTP/1.1\r\nHost: localhost\r\nCookie: csrf_token={}\r\nX-CSRF-Token: {}\r\n\r\n",
+            token, token
+        ),
+    );
+
+    assert!(response.ends_with("\r\n\r\nsubmitted"));
+
+    let body = format!("name=enrgy&csrf_token={}", token);
+    let response = request(
+        addr,
+        &format!(
+            "POST /submit HTTP/1.1\r\nHost: localhost\r\nCookie: theme=dark; csrf_token={}\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{}",
+            token,
+            body.len(),
+            body
+        ),
+    );
+
+    assert!(response.ends_with("\r\n\r\nsubmitted"));
+    assert_eq!(SUBMITTED.load(Ordering::SeqCst), 2);
+
+    for raw in [
+        "POST /submit HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string(),
+        format!(
+            "POST /submit HTTP/1.1\r\nHost: localhost\r\nCookie: csrf_token={}\r\n\r\n",
+            token
+        ),
+        "POST /submit HTTP/1.1\r\nHost: localhost\r\nCookie: csrf_token=abc.def\r\nX-CSRF-Token: abc.def\r\n\r\n".to_string(),
+    ] {
+        let response = request(addr, &raw);
+
+        assert!(response.starts_with("HTTP/1.0 403 Forbidden\r\n"), "{}", raw);
+    }
+
+    assert_eq!(SUBMITTED.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_https_redirect() {
+    let app = |redirect: HttpsRedirect| {
+        App::new()
+            .wrap(redirect)
+            .service(web::get("/users").to(|| "Hello World!"))
+    };
+
+    let addr = serve(app(HttpsRedirect::new().trust_proxy([127, 0, 0, 1])));
+
+    let forwarded = |addr, proto: &str| {
+        request(
+            addr,
+            &format!(
+                "GET /users?page=2 HTTP/1.1\r\nHost: example.com\r\nX-Forwarded-Proto: {}\r\n\r\n",
+                proto
+            ),
+        )
+    };
+
+    let response = forwarded(addr, "http");
+
+    assert!(response.starts_with("HTTP/1.0 301 Moved Permanently\r\n"));
+    assert!(response.contains("\r\nLocation: https://example.com/users?page=2\r\n"));
+
+    let response = forwarded(addr, "https");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nHello World!"));
+
+    // The header is ignored from anyone but the proxy.
+    let addr = serve(app(HttpsRedirect::new().trust_proxy([10, 0, 0, 1])));
+
+    assert!(forwarded(addr, "http").starts_with("HTTP/1.0 200 OK\r\n"));
+}
+
+#[test]
+fn test_data_raw() {
+    // Only knows the type id of the data it counts requests with.
+    struct Counter(TypeId);
+
+    impl Middleware<HttpRequest, HttpResponse> for Counter {
+        type Context = usize;
+
+        fn before(&self, req: &mut HttpRequest) -> Self::Context {
+            req.data_raw(self.0)
+                .and_then(|data| data.downcast_ref::<web::Data<AtomicUsize>>())
+                .map(|count| count.fetch_add(1, Ordering::SeqCst) + 1)
+                .unwrap_or_default()
+        }
+
+        fn after(&self, _req: &HttpRequest, res: HttpResponse, ctx: Self::Context) -> HttpResponse {
+            res.header(X_REQUEST_COUNT, ctx.to_string())
+        }
+    }
+
+    const X_REQUEST_COUNT: HttpHeaderName = HttpHeaderName::new("X-Request-Count");
+
+    let addr = serve(
+        App::new()
+            .data(Arc::new(AtomicUsize::new(0)))
+            .wrap(Counter(TypeId::of::<web::Data<AtomicUsize>>()))
+            .service(web::get("/").to(|| "Hello World!")),
+    );
+
+    for count in 1..=2 {
+        let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert!(response.contains(&format!("\r\nX-Request-Count: {}\r\n", count)));
+    }
+}
+
+#[test]
+fn test_idempotency() {
+    static CHARGED: AtomicUsize = AtomicUsize::new(0);
+    static RELEASED: AtomicBool = AtomicBool::new(false);
+
+    let addr = serve(
+        App::new()
+            .wrap(Idempotency::new())
+            .service(web::post("/charge").to(|| {
+                let charge = CHARGED.fetch_add(1, Ordering::SeqCst) + 1;
+
+                while !RELEASED.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(5));
+                }
+
+                format!("charge {}", charge)
+            }))
+            .service(web::post("/refund").to(|| "refund")),
+    );
+
+    let charge = move |key: &str| {
+        request(
+            addr,
+            &format!(
+                "POST /charge HTTP/1.1\r\nHost: localhost\r\nIdempotency-Key: {}\r\n\r\n",
+                key
+            ),
+        )
+    };
+
+    let first = thread::spawn(move || charge("a"));
+
+    while CHARGED.load(Ordering::SeqCst) == 0 {
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    // A duplicate sent while the first is still being handled.
+    let response = charge("a");
+
+    assert!(response.starts_with("HTTP/1.0 409 Conflict\r\n"));
+
+    RELEASED.store(true, Ordering::SeqCst);
+
+    let response = first.join().unwrap();
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\ncharge 1"));
+
+    let response = charge("a");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.contains("\r\nIdempotent-Replayed: true\r\n"));
+    assert!(response.ends_with("\r\n\r\ncharge 1"));
+
+    // The same key sent to another route is a different request.
+    let response = request(
+        addr,
+        "POST /refund HTTP/1.1\r\nHost: localhost\r\nIdempotency-Key: a\r\n\r\n",
+    );
+
+    assert!(!response.contains("Idempotent-Replayed"));
+    assert!(response.ends_with("\r\n\r\nrefund"));
+
+    let response = charge("b");
+
+    assert!(response.ends_with("\r\n\r\ncharge 2"));
+    assert_eq!(CHARGED.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_idempotency_ttl() {
+    static CHARGED: AtomicUsize = AtomicUsize::new(0);
+
+    let clock = Arc::new(MockClock::new());
+
+    let addr = serve(
+        App::new()
+            .clock(Arc::clone(&clock))
+            .wrap(Idempotency::new().ttl(Duration::from_secs(60)))
+            .service(
+                web::post("/charge")
+                    .to(|| format!("charge {}", CHARGED.fetch_add(1, Ordering::SeqCst) + 1)),
+            ),
+    );
+
+    let charge = || {
+        request(
+            addr,
+            "POST /charge HTTP/1.1\r\nHost: localhost\r\nIdempotency-Key: a\r\n\r\n",
+        )
+    };
+
+    assert!(charge().ends_with("\r\n\r\ncharge 1"));
+
+    clock.advance(Duration::from_secs(59));
+
+    let response = charge();
+
+    assert!(response.contains("\r\nIdempotent-Replayed: true\r\n"));
+    assert!(response.ends_with("\r\n\r\ncharge 1"));
+
+    clock.advance(Duration::from_secs(1));
+
+    let response = charge();
+
+    assert!(!response.contains("Idempotent-Replayed"));
+    assert!(response.ends_with("\r\n\r\ncharge 2"));
+}
+
+#[test]
+fn test_fetch_metadata() {
+    let addr = serve(
+        App::new()
+            .wrap(FetchMetadata::new())
+            .service(web::get("/").to(|| "Hello World!"))
+            .service(web::post("/submit").to(|| "submitted")),
+    );
+
+    let submit = |headers: &str| {
+        request(
+            addr,
+            &format!(
+                "POST /submit HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                headers
+            ),
+        )
+    };
+
+    let response = submit("Sec-Fetch-Site: same-origin\r\nSec-Fetch-Mode: cors\r\n");
+
+    assert!(response.ends_with("\r\n\r\nsubmitted"));
+
+    let response = submit("Sec-Fetch-Site: cross-site\r\nSec-Fetch-Mode: navigate\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 403 Forbidden\r\n"));
+
+    // Older browsers, and clients that are not browsers, do not send the headers.
+    let response = submit("");
+
+    assert!(response.ends_with("\r\n\r\nsubmitted"));
+
+    // Reading is fine from anywhere.
+    let response = request(
+        addr,
+        "GET / HTTP/1.1\r\nHost: localhost\r\nSec-Fetch-Site: cross-site\r\n\r\n",
+    );
+
+    assert!(response.ends_with("\r\n\r\nHello World!"));
+}
+
+#[test]
+fn test_session() {
+    let addr = serve(
+        App::new()
+            .wrap(Session::new())
+            .service(web::post("/login").to(|session: web::Session| {
+                session.set("user", "ferris");
+
+                "logged in"
+            }))
+            .service(web::get("/").to(|session: web::Session| {
+                session
+                    .get("user")
+                    .unwrap_or_else(|| "anonymous".to_string())
+            }))
+            .service(web::post("/logout").to(|session: web::Session| {
+                session.clear();
+
+                "logged out"
+            })),
+    );
+
+    let send = |line: &str, cookie: Option<&str>| {
+        request(
+            addr,
+            &format!(
+                "{} HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                line,
+                cookie
+                    .map(|id| format!("Cookie: session_id={}\r\n", id))
+                    .unwrap_or_default()
+            ),
+        )
+    };
+
+    // Nothing is stored, so no session is created.
+    let response = send("GET /", None);
+
+    assert!(response.ends_with("\r\n\r\nanonymous"));
+    assert!(!response.contains("Set-Cookie"));
+
+    let response = send("POST /login", None);
+
+    let id = response
+        .split("\r\n")
+        .find_map(|line| line.strip_prefix("Set-Cookie: session_id="))
+        .and_then(|cookie| cookie.split(';').next())
+        .unwrap()
+        .to_string();
+
+    assert_eq!(id.len(), 32);
+    assert!(response.contains("; HttpOnly; SameSite=Lax"));
+
+    let response = send("GET /", Some(&id));
+
+    assert!(response.ends_with("\r\n\r\nferris"));
+
+    // Ids the store does not know are not reused.
+    let response = send("GET /", Some("0123456789abcdef0123456789abcdef"));
+
+    assert!(response.ends_with("\r\n\r\nanonymous"));
+
+    let response = send("POST /logout", Some(&id));
+
+    assert!(response
+        .contains("\r\nSet-Cookie: session_id=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0\r\n"));
+
+    let response = send("GET /", Some(&id));
+
+    assert!(response.ends_with("\r\n\r\nanonymous"));
+}
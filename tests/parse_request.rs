@@ -5,7 +5,7 @@ use enrgy::{
     http::{
         headers::{
             HttpHeaderName, ACCEPT, ACCEPT_CHARSET, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONNECTION,
-            HOST, KEEP_ALIVE, USER_AGENT, CONTENT_LENGTH,
+            CONTENT_LENGTH, HOST, KEEP_ALIVE, USER_AGENT,
         },
         uri::HttpResource,
         *,
@@ -190,3 +190,194 @@ http_test!(
         "\r\n"
     ),
 );
+
+#[test]
+fn test_invalid_header_encoding() {
+    let mut raw = &b"GET / HTTP/1.1\r\nHost: github.com\r\nX-Name: a\x80b\r\n\r\n"[..];
+
+    assert!(matches!(
+        read_request(&mut raw, false),
+        Err(HttpError::InvalidHeaderEncoding { offset: 43 })
+    ));
+}
+
+const FOLDED: &str = "GET / HTTP/1.1\r\nHost: github.com\r\nX-Folded: a\r\n  b\r\n\r\n";
+
+#[test]
+fn test_obs_fold_lenient() {
+    let header_data = parse_header(FOLDED, false).unwrap();
+
+    assert_eq!(
+        header_data
+            .headers
+            .get(&HttpHeaderName::new("X-Folded"))
+            .map(String::as_str),
+        Some("a b")
+    );
+}
+
+#[test]
+fn test_obs_fold_strict() {
+    assert!(matches!(
+        parse_header(FOLDED, true),
+        Err(HttpError::ParseHeaderObsoleteFold)
+    ));
+}
+
+#[test]
+fn test_obs_fold_user_agent() {
+    let head = "GET / HTTP/1.1\r\nHost: github.com\r\nUser-Agent: Mozilla/5.0\r\n\t(X11; Linux x86_64)\r\n   Gecko/20100101\r\nAccept: */*\r\n\r\n";
+
+    let header_data = parse_header(head, false).unwrap();
+
+    assert_eq!(
+        header_data
+            .headers
+            .get(&HttpHeaderName::new("User-Agent"))
+            .map(String::as_str),
+        Some("Mozilla/5.0 (X11; Linux x86_64) Gecko/20100101")
+    );
+    assert_eq!(
+        header_data
+            .headers
+            .get(&HttpHeaderName::new("Accept"))
+            .map(String::as_str),
+        Some("*/*")
+    );
+
+    assert!(matches!(
+        parse_header(head, true),
+        Err(HttpError::ParseHeaderObsoleteFold)
+    ));
+}
+
+#[test]
+fn test_whitespace_strict() {
+    assert!(matches!(
+        parse_header("GET /  HTTP/1.1\r\nHost: github.com\r\n\r\n", true),
+        Err(HttpError::ParseMetaWhitespace)
+    ));
+    assert!(matches!(
+        parse_header("GET / HTTP/1.1\r\nHost : github.com\r\n\r\n", true),
+        Err(HttpError::ParseHeaderWhitespace)
+    ));
+    assert!(parse_header("GET / HTTP/1.1\r\nHost : github.com\r\n\r\n", false).is_ok());
+}
+
+/// Gives out a few bytes per read, so lines are split across reads.
+struct Trickle<'b>(&'b [u8]);
+
+impl<'b> std::io::Read for Trickle<'b> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(7);
+
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_large_header_block() {
+    let mut raw = String::from("POST /upload HTTP/1.1\r\nHost: github.com\r\n");
+
+    for i in 0..24 {
+        raw.push_str(&format!("X-Header-{}: {}\r\n", i, "v".repeat(280)));
+    }
+
+    raw.push_str("Content-Length: 4\r\n\r\nbody");
+
+    let (header_data, body) = read_request(&mut Trickle(raw.as_bytes()), true).unwrap();
+
+    assert_eq!(header_data.method, HttpMethod::Post);
+    assert_eq!(header_data.url, "/upload");
+
+    assert_eq!(
+        header_data
+            .headers
+            .iter()
+            .filter(|(key, value)| key.to_string().starts_with("X-Header-") && value.len() == 280)
+            .count(),
+        24
+    );
+
+    assert_eq!(body, b"body");
+
+    // Encoding errors are still reported at their offset into the whole head.
+    let raw = b"GET / HTTP/1.1\r\nHost: github.com\r\nX-Name: a\x80b\r\n\r\n";
+
+    assert!(matches!(
+        read_request(&mut Trickle(raw), false),
+        Err(HttpError::InvalidHeaderEncoding { offset: 43 })
+    ));
+}
+
+#[test]
+fn test_duplicate_content_length() {
+    let header_data = parse_header(
+        "POST / HTTP/1.1\r\nHost: github.com\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\n",
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(
+        header_data.headers.get(&CONTENT_LENGTH).map(String::as_str),
+        Some("5")
+    );
+
+    let header_data = parse_header(
+        "POST / HTTP/1.1\r\nHost: github.com\r\nContent-Length: 5, 5\r\n\r\n",
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(
+        header_data.headers.get(&CONTENT_LENGTH).map(String::as_str),
+        Some("5")
+    );
+
+    for head in [
+        "POST / HTTP/1.1\r\nHost: github.com\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\n",
+        "POST / HTTP/1.1\r\nHost: github.com\r\nContent-Length: 5, 6\r\n\r\n",
+    ] {
+        assert!(matches!(
+            parse_header(head, false),
+            Err(HttpError::ConflictingContentLength)
+        ));
+    }
+}
+
+#[test]
+fn test_target_control_characters() {
+    for target in [
+        "/files/a%00.txt",
+        "/files/a\x01.txt",
+        "/a%0D%0Ab",
+        "/?q=\x7f",
+    ] {
+        assert!(matches!(
+            parse_header(
+                &format!("GET {} HTTP/1.1\r\nHost: github.com\r\n\r\n", target),
+                false
+            ),
+            Err(HttpError::InvalidTargetControl)
+        ));
+    }
+
+    // Form values can hold any byte once decoded.
+    assert!(parse_header("GET /?q=a%0Ab HTTP/1.1\r\nHost: github.com\r\n\r\n", false).is_ok());
+}
+
+#[test]
+fn test_target_backslash_strict() {
+    for target in ["/files\\a.txt", "/files%5Ca.txt"] {
+        let head = format!("GET {} HTTP/1.1\r\nHost: github.com\r\n\r\n", target);
+
+        assert!(matches!(
+            parse_header(&head, true),
+            Err(HttpError::InvalidTargetBackslash)
+        ));
+        assert!(parse_header(&head, false).is_ok());
+    }
+}
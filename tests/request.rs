@@ -0,0 +1,125 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::UNIX_EPOCH,
+};
+
+use enrgy::{
+    http::{
+        headers::{CONTENT_TYPE, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+        HttpMethod, HttpRequest, StatusCode,
+    },
+    web,
+};
+
+struct Counter(AtomicUsize);
+
+fn increment(
+    id: web::Param<"id">,
+    page: web::Query<"page">,
+    counter: web::Data<Counter>,
+    body: web::Text,
+) -> String {
+    let count = counter.0.fetch_add(1, Ordering::SeqCst) + 1;
+
+    format!("{} {} {} {}", *id, *page, *body, count)
+}
+
+#[test]
+fn test_request_builder() {
+    let counter = Arc::new(Counter(AtomicUsize::new(0)));
+
+    let route = web::post("/counters/:id").to(increment);
+
+    let build = || {
+        HttpRequest::builder()
+            .method(HttpMethod::Post)
+            .uri("/counters/7?page=2")
+            .header(CONTENT_TYPE, "text/plain")
+            .param("id", "7")
+            .body("by one")
+            .data(Arc::clone(&counter))
+            .build()
+    };
+
+    let mut req = build();
+
+    assert_eq!(req.header_data.url, "/counters/7");
+    assert_eq!(req.query_string(), "page=2");
+
+    let res = route.call(&mut req).unwrap();
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body_bytes(), Some(&b"7 2 by one 1"[..]));
+
+    let res = route.call(&mut build()).unwrap();
+
+    assert_eq!(res.body_bytes(), Some(&b"7 2 by one 2"[..]));
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_request_builder_missing_data() {
+    let mut req = HttpRequest::builder().param("id", "7").build();
+
+    let err = web::get("/counters/:id")
+        .to(|counter: web::Data<Counter>| counter.0.load(Ordering::SeqCst).to_string())
+        .call(&mut req)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        err.error_response().status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[test]
+fn test_conditionals() {
+    let route = web::get("/").to(|conditionals: web::Conditionals| {
+        let tags = match &conditionals.if_none_match {
+            Some(web::EntityTags::Tags(tags)) => tags
+                .iter()
+                .map(|tag| format!("{}{}", if tag.weak { "W/" } else { "" }, tag.tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Some(web::EntityTags::Any) => "*".to_string(),
+            None => "none".to_string(),
+        };
+
+        let since = conditionals
+            .if_modified_since
+            .map(|since| since.duration_since(UNIX_EPOCH).unwrap().as_secs());
+
+        format!(
+            "{}, {:?}, {:?}, {}",
+            tags,
+            since,
+            conditionals.if_match,
+            conditionals.not_modified(Some("\"def\""), None)
+        )
+    });
+
+    let mut req = HttpRequest::builder()
+        .header(IF_NONE_MATCH, "\"abc\", W/\"def\", unquoted")
+        .header(IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:37 GMT")
+        .build();
+
+    let res = route.call(&mut req).unwrap();
+
+    assert_eq!(
+        res.body_bytes(),
+        Some(&b"abc W/def, Some(784111777), None, true"[..])
+    );
+
+    let mut req = HttpRequest::builder()
+        .header(IF_NONE_MATCH, "*")
+        .header(IF_MODIFIED_SINCE, "yesterday")
+        .build();
+
+    let res = route.call(&mut req).unwrap();
+
+    assert_eq!(res.body_bytes(), Some(&b"*, None, None, true"[..]));
+}
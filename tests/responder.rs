@@ -0,0 +1,123 @@
+mod common;
+
+use std::{fmt, sync::Arc};
+
+use common::{request, serve};
+use enrgy::{error::ResponseError, http::StatusCode, web, App};
+
+#[derive(Debug)]
+struct UserNotFound;
+
+impl fmt::Display for UserNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "user not found")
+    }
+}
+
+impl ResponseError for UserNotFound {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+}
+
+fn user(name: web::Param<"name">) -> Result<String, UserNotFound> {
+    if *name == "admin" {
+        Ok(format!("Hello {}!", *name))
+    } else {
+        Err(UserNotFound)
+    }
+}
+
+#[test]
+fn test_result_ok() {
+    let addr = serve(App::new().service(web::get("/users/:name").to(user)));
+
+    let response = request(addr, "GET /users/admin HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nHello admin!"));
+}
+
+#[test]
+fn test_result_err() {
+    let addr = serve(App::new().service(web::get("/users/:name").to(user)));
+
+    let response = request(addr, "GET /users/guest HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 404 Not Found\r\n"));
+    assert!(response.ends_with("\r\n\r\nuser not found"));
+}
+
+#[test]
+fn test_tuple_with_headers() {
+    let addr = serve(
+        App::new()
+            .service(web::get("/").to(|| {
+                (
+                    StatusCode::CREATED,
+                    vec![("Location", "/users/admin"), ("X-Request-Id", "42")],
+                    "created",
+                )
+            }))
+            .service(web::get("/invalid").to(|| {
+                (
+                    StatusCode::OK,
+                    vec![("X-Injected", "a\r\nSet-Cookie: b")],
+                    "invalid",
+                )
+            })),
+    );
+
+    let response = request(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 201 Created\r\n"));
+    assert!(response.contains("\r\nLocation: /users/admin\r\n"));
+    assert!(response.contains("\r\nX-Request-Id: 42\r\n"));
+    assert!(response.ends_with("\r\n\r\ncreated"));
+
+    let response = request(addr, "GET /invalid HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"));
+    assert!(!response.contains("Set-Cookie"));
+}
+
+struct User {
+    name: &'static str,
+}
+
+#[test]
+fn test_template() {
+    let addr = serve(
+        App::new()
+            .data(Arc::new(web::TemplateEngine::new(
+                |name: &str, user: &User| match name {
+                    "user.html" => Ok(format!("<h1>{}</h1>", user.name)),
+                    _ => Err(format!("no template named `{}`", name)),
+                },
+            )))
+            .service(web::get("/user").to(|| web::Template("user.html", User { name: "Jane" })))
+            .service(
+                web::get("/missing").to(|| web::Template("missing.html", User { name: "Jane" })),
+            )
+            .service(web::get("/unregistered").to(|| web::Template("user.html", "no engine"))),
+    );
+
+    let response = request(addr, "GET /user HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.contains("\r\nContent-Type: text/html; charset=utf-8\r\n"));
+    assert!(response.ends_with("\r\n\r\n<h1>Jane</h1>"));
+
+    for path in ["/missing", "/unregistered"] {
+        let response = request(
+            addr,
+            &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path),
+        );
+
+        assert!(
+            response.starts_with("HTTP/1.0 500 Internal Server Error\r\n"),
+            "{}",
+            path
+        );
+    }
+}
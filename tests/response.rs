@@ -0,0 +1,228 @@
+use enrgy::http::{
+    headers::{CACHE_CONTROL, CONNECTION, CONTENT_TYPE, LOCATION, SET_COOKIE, UPGRADE},
+    write_response, CacheControl, HttpBody, HttpError, HttpHeaderName, HttpResponse, StatusCode,
+};
+
+#[test]
+fn test_upgrade_required() {
+    let res = HttpResponse::upgrade_required("websocket");
+
+    assert_eq!(res.status, StatusCode::UPGRADE_REQUIRED);
+    assert_eq!(
+        res.headers.get(&UPGRADE).map(String::as_str),
+        Some("websocket")
+    );
+    assert_eq!(
+        res.headers.get(&CONNECTION).map(String::as_str),
+        Some("Upgrade")
+    );
+}
+
+#[test]
+fn test_cache_control() {
+    let res = HttpResponse::ok().cache_control(CacheControl::new().no_store());
+
+    assert_eq!(
+        res.headers.get(&CACHE_CONTROL).map(String::as_str),
+        Some("no-store")
+    );
+}
+
+#[test]
+fn test_body_bytes() {
+    let mut res = HttpResponse::ok().body("Hello");
+
+    assert_eq!(res.body_bytes(), Some(&b"Hello"[..]));
+
+    res.body_mut().unwrap().extend_from_slice(b" World!");
+
+    assert_eq!(res.body_bytes(), Some(&b"Hello World!"[..]));
+
+    res.set_body(b"Bye".to_vec());
+
+    assert_eq!(res.body_bytes(), Some(&b"Bye"[..]));
+}
+
+#[test]
+fn test_html() {
+    let res = HttpResponse::html("<h1>Hello World!</h1>");
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        res.headers.get(&CONTENT_TYPE).map(String::as_str),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(res.body_bytes(), Some(&b"<h1>Hello World!</h1>"[..]));
+
+    let res = HttpResponse::text(format!("Hello {}!", "World"));
+
+    assert_eq!(
+        res.headers.get(&CONTENT_TYPE).map(String::as_str),
+        Some("text/plain; charset=utf-8")
+    );
+    assert_eq!(res.body_bytes(), Some(&b"Hello World!"[..]));
+}
+
+#[test]
+fn test_try_clone() {
+    let mut res = HttpResponse::new(StatusCode::CREATED)
+        .header(CACHE_CONTROL, "max-age=60")
+        .header(LOCATION, "/users/1")
+        .body(b"Hello".to_vec());
+
+    let copy = res.try_clone().unwrap();
+
+    res.body_mut().unwrap().extend_from_slice(b" World!");
+    res.headers.insert(LOCATION, "/users/2".to_string());
+
+    assert_eq!(copy.status, StatusCode::CREATED);
+    assert_eq!(copy.headers.len(), 2);
+    assert_eq!(
+        copy.headers.get(&CACHE_CONTROL).map(String::as_str),
+        Some("max-age=60")
+    );
+    assert_eq!(
+        copy.headers.get(&LOCATION).map(String::as_str),
+        Some("/users/1")
+    );
+    assert_eq!(copy.body_bytes(), Some(&b"Hello"[..]));
+
+    let res = HttpResponse::ok().body("Hello World!");
+    let copy = res.clone();
+
+    assert_eq!(res.body, copy.body);
+    assert_eq!(copy.body, HttpBody::Bytes(b"Hello World!"));
+}
+
+#[test]
+fn test_from_str() {
+    let res = HttpResponse::from("Hello World!");
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, HttpBody::Bytes(b"Hello World!"));
+}
+
+#[test]
+fn test_from_string() {
+    let res = HttpResponse::from(String::from("Hello World!"));
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, HttpBody::Vector(b"Hello World!".to_vec()));
+}
+
+#[test]
+fn test_from_status_tuple() {
+    let res: HttpResponse = (StatusCode::NOT_FOUND, "nope").into();
+
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body_bytes(), Some(&b"nope"[..]));
+
+    let res: HttpResponse = (StatusCode::CREATED, format!("user {}", 1)).into();
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body_bytes(), Some(&b"user 1"[..]));
+}
+
+#[test]
+fn test_with_header() {
+    let res = HttpResponse::ok()
+        .with_header(LOCATION, "/users/1")
+        .unwrap();
+
+    assert_eq!(
+        res.headers.get(&LOCATION).map(String::as_str),
+        Some("/users/1")
+    );
+
+    assert!(matches!(
+        HttpResponse::ok().with_header(LOCATION, "/\r\nSet-Cookie: session=stolen"),
+        Err(HttpError::InvalidHeaderValue)
+    ));
+    assert!(matches!(
+        HttpResponse::ok().with_header(HttpHeaderName::new("X Bad"), "value"),
+        Err(HttpError::InvalidHeaderName)
+    ));
+}
+
+#[test]
+fn test_insert_and_append_header() {
+    let values = |res: &HttpResponse| {
+        res.headers
+            .iter()
+            .filter(|(key, _)| **key == SET_COOKIE)
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let res = HttpResponse::ok()
+        .append_header(SET_COOKIE, "a=1")
+        .append_header(SET_COOKIE, "b=2");
+
+    assert_eq!(values(&res), ["a=1", "b=2"]);
+
+    let mut written = Vec::new();
+
+    write_response(res.clone(), false, &mut written).unwrap();
+
+    let written = String::from_utf8(written).unwrap();
+
+    assert!(written.contains("\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\n"));
+
+    let res = res.insert_header(SET_COOKIE, "c=3");
+
+    assert_eq!(values(&res), ["c=3"]);
+
+    let res = res
+        .header(CONTENT_TYPE, "text/plain")
+        .header(CONTENT_TYPE, "text/html");
+
+    assert_eq!(
+        res.headers.get(&CONTENT_TYPE).map(String::as_str),
+        Some("text/html")
+    );
+}
+
+#[test]
+fn test_reason() {
+    let written = |res: HttpResponse| {
+        let mut written = Vec::new();
+
+        write_response(res, false, &mut written).unwrap();
+
+        String::from_utf8(written).unwrap()
+    };
+
+    assert!(written(HttpResponse::ok().reason("Awesome")).starts_with("HTTP/1.0 200 Awesome\r\n"));
+    assert!(written(HttpResponse::ok()).starts_with("HTTP/1.0 200 OK\r\n"));
+
+    // A reason that would split the status line falls back to the standard one.
+    assert!(
+        written(HttpResponse::new(StatusCode::NOT_FOUND).reason("Gone\r\nX-Injected: 1"))
+            .starts_with("HTTP/1.0 404 Not Found\r\n")
+    );
+}
+
+#[test]
+fn test_finish() {
+    let res = HttpResponse::new(StatusCode::NO_CONTENT)
+        .header(LOCATION, "/items/1")
+        .finish();
+
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    assert_eq!(
+        res.headers.get(&LOCATION).map(String::as_str),
+        Some("/items/1")
+    );
+    assert_eq!(res.body_bytes(), Some(&b""[..]));
+    assert!(matches!(res.body, HttpBody::None));
+
+    let res = HttpResponse::ok().body("Hello").finish();
+
+    assert!(matches!(res.body, HttpBody::None));
+
+    let mut written = Vec::new();
+
+    write_response(res, false, &mut written).unwrap();
+
+    assert!(String::from_utf8(written).unwrap().ends_with("\r\n\r\n"));
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use common::{request, serve};
+use enrgy::{web, App};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+type Spans = Arc<Mutex<HashMap<u64, (&'static str, HashMap<&'static str, String>)>>>;
+
+struct Recorder {
+    next: AtomicU64,
+    spans: Spans,
+}
+
+struct Fields<'a>(&'a mut HashMap<&'static str, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let id = self.next.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut fields = HashMap::new();
+
+        span.record(&mut Fields(&mut fields));
+
+        self.spans
+            .lock()
+            .unwrap()
+            .insert(id, (span.metadata().name(), fields));
+
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+        if let Some((_, fields)) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut Fields(fields));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn test_request_span() {
+    let spans = Spans::default();
+
+    tracing::subscriber::set_global_default(Recorder {
+        next: AtomicU64::new(0),
+        spans: Arc::clone(&spans),
+    })
+    .unwrap();
+
+    let addr = serve(App::new().service(web::get("/users/:id").to(|| "user")));
+
+    request(addr, "GET /users/7 HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    request(addr, "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    let spans = spans.lock().unwrap();
+    let mut requests = spans
+        .values()
+        .filter(|(name, _)| *name == "request")
+        .map(|(_, fields)| fields)
+        .collect::<Vec<_>>();
+
+    requests.sort_by_key(|fields| fields["path"].clone());
+
+    assert_eq!(requests.len(), 2);
+
+    assert_eq!(requests[0]["method"], "GET");
+    assert_eq!(requests[0]["path"], "/missing");
+    assert_eq!(requests[0]["pattern"], "");
+    assert_eq!(requests[0]["status"], "404");
+
+    assert_eq!(requests[1]["path"], "/users/7");
+    assert_eq!(requests[1]["pattern"], "/users/:id");
+    assert_eq!(requests[1]["status"], "200");
+}